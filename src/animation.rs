@@ -0,0 +1,170 @@
+use std::{fs::File, io::BufReader, path::Path, time::Duration};
+
+use anyhow::{Context, Result};
+use image::{
+    AnimationDecoder, DynamicImage, Frames, RgbaImage,
+    codecs::{gif::GifDecoder, png::PngDecoder, webp::WebPDecoder},
+    imageops::FilterType,
+};
+use serde::{Deserialize, Serialize};
+
+use crate::format::Format;
+
+/// Delays shorter than this are shown as `DEFAULT_DELAY`, like browsers do,
+/// since many GIFs store 0 and expect it to mean "as fast as sensible".
+const MIN_DELAY: Duration = Duration::from_millis(20);
+const DEFAULT_DELAY: Duration = Duration::from_millis(100);
+
+/// Frame count and running time of an animated image.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AnimationInfo {
+    pub frames: usize,
+    pub duration: Duration,
+}
+
+impl std::fmt::Display for AnimationInfo {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} frames, {:.1}s",
+            self.frames,
+            self.duration.as_secs_f32()
+        )
+    }
+}
+
+/// A decoded frame and how long it stays on screen.
+pub struct Frame {
+    pub image: RgbaImage,
+    pub delay: Duration,
+}
+
+/// The frames of a GIF, APNG or animated WebP, or `None` for other images.
+fn open_frames(path: &Path) -> Result<Option<Frames<'static>>> {
+    let reader = || {
+        File::open(path)
+            .map(BufReader::new)
+            .with_context(|| format!("Failed to open image: {}", path.display()))
+    };
+
+    let frames = match Format::of_file(path) {
+        Some(Format::Gif) => GifDecoder::new(reader()?)?.into_frames(),
+        Some(Format::Png) => {
+            let decoder = PngDecoder::new(reader()?)?;
+            if !decoder.is_apng()? {
+                return Ok(None);
+            }
+            decoder.apng()?.into_frames()
+        }
+        Some(Format::Webp) => {
+            let decoder = WebPDecoder::new(reader()?)?;
+            if !decoder.has_animation() {
+                return Ok(None);
+            }
+            decoder.into_frames()
+        }
+        _ => return Ok(None),
+    };
+    Ok(Some(frames))
+}
+
+fn frame_delay(frame: &image::Frame) -> Duration {
+    let delay = Duration::from(frame.delay());
+    if delay < MIN_DELAY {
+        DEFAULT_DELAY
+    } else {
+        delay
+    }
+}
+
+/// Frame count and running time of the image at `path`, or `None` unless
+/// it has more than one frame.
+pub fn animation_info(path: &Path) -> Option<AnimationInfo> {
+    let frames = open_frames(path).ok().flatten()?;
+    let mut info = AnimationInfo {
+        frames: 0,
+        duration: Duration::ZERO,
+    };
+    // A broken frame ends the animation, as it does when playing
+    for frame in frames.map_while(Result::ok) {
+        info.frames += 1;
+        info.duration += frame_delay(&frame);
+    }
+    (info.frames > 1).then_some(info)
+}
+
+/// Decodes every frame, scaled down to fit within `max_width`x`max_height`.
+pub fn decode_frames(path: &Path, max_width: u32, max_height: u32) -> Result<Vec<Frame>> {
+    let Some(frames) = open_frames(path)? else {
+        return Ok(Vec::new());
+    };
+
+    frames
+        .map(|frame| {
+            let frame = frame.with_context(|| format!("Failed to decode {}", path.display()))?;
+            let delay = frame_delay(&frame);
+            let mut image = frame.into_buffer();
+            if image.width() > max_width || image.height() > max_height {
+                image = DynamicImage::ImageRgba8(image)
+                    .resize(max_width, max_height, FilterType::Triangle)
+                    .to_rgba8();
+            }
+            Ok(Frame { image, delay })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::{Delay, Rgba, codecs::gif::GifEncoder};
+    use tempfile::tempdir;
+
+    fn write_gif(path: &Path, frames: &[(u8, u32)]) {
+        let mut encoder = GifEncoder::new(File::create(path).unwrap());
+        encoder
+            .encode_frames(frames.iter().map(|&(shade, delay_ms)| {
+                image::Frame::from_parts(
+                    RgbaImage::from_pixel(40, 20, Rgba([shade, shade, shade, 255])),
+                    0,
+                    0,
+                    Delay::from_numer_denom_ms(delay_ms, 1),
+                )
+            }))
+            .unwrap();
+    }
+
+    #[test]
+    fn test_animated_gif() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("loop.gif");
+        write_gif(&path, &[(0, 200), (120, 300), (250, 0)]);
+
+        let info = animation_info(&path).unwrap();
+        assert_eq!(info.to_string(), "3 frames, 0.6s");
+
+        let frames = decode_frames(&path, 20, 20).unwrap();
+        assert_eq!(frames.len(), 3);
+        assert_eq!(frames[0].image.dimensions(), (20, 10));
+        assert_eq!(frames[1].delay, Duration::from_millis(300));
+        // A zero delay plays at the default speed
+        assert_eq!(frames[2].delay, DEFAULT_DELAY);
+        assert_eq!(
+            info.duration,
+            frames.iter().map(|frame| frame.delay).sum::<Duration>()
+        );
+    }
+
+    #[test]
+    fn test_still_images() {
+        let dir = tempdir().unwrap();
+        let gif = dir.path().join("still.gif");
+        write_gif(&gif, &[(80, 100)]);
+        let png = dir.path().join("still.png");
+        RgbaImage::new(4, 4).save(&png).unwrap();
+
+        assert_eq!(animation_info(&gif), None);
+        assert_eq!(animation_info(&png), None);
+        assert!(decode_frames(&png, 4, 4).unwrap().is_empty());
+    }
+}
@@ -0,0 +1,293 @@
+#[cfg(unix)]
+use std::os::unix::fs::symlink;
+use std::{
+    path::{Path, PathBuf},
+    process::Command,
+};
+
+use anyhow::{Context, Result, bail};
+
+use crate::{
+    config::CONFIG,
+    derivative::{derivative_link, render_derivatives},
+    format::Format,
+    framing::Framing,
+    library::Library,
+    outputs::{OutputConfig, output_link, resolve_outputs},
+    palette::Palette,
+    prescale::prescale_outputs,
+    span::span_wallpaper,
+};
+
+/// Which outputs a wallpaper is applied to.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ApplyTarget {
+    All,
+    Output(String),
+    /// Split the wallpaper across all outputs.
+    Span,
+}
+
+/// Points the `current_wallpaper` symlink at `img_path` and runs the post script.
+///
+/// With outputs configured, a `current_wallpaper-<output>` symlink is created and
+/// the post script runs once for each targeted output.
+pub fn apply_wallpaper(img_path: &Path, target: &ApplyTarget) -> Result<()> {
+    let config = CONFIG.load();
+    let outputs = resolve_outputs(&config);
+    let files = match target {
+        ApplyTarget::All => output_files(img_path, &outputs),
+        ApplyTarget::Output(output) => {
+            let targeted = outputs
+                .iter()
+                .find(|o| o.name == *output)
+                .cloned()
+                .unwrap_or_else(|| OutputConfig::named(output));
+            output_files(img_path, &[targeted])
+        }
+        ApplyTarget::Span => {
+            let cache_dir = config
+                .cache_subdir("span")
+                .context("Cache path not configured")?;
+            let bezel = config.span.bezel.unwrap_or(0);
+            span_wallpaper(img_path, &outputs, bezel, &cache_dir)?
+        }
+    };
+
+    if config.palette.enabled == Some(true)
+        && let Err(e) = export_palette(img_path)
+    {
+        eprintln!("Failed to export color palette: {:#}", e);
+    }
+    if !config.derivatives.is_empty()
+        && let Err(e) = link_derivatives(img_path)
+    {
+        eprintln!("Failed to render derivatives: {:#}", e);
+    }
+
+    apply_files(&main_file(img_path), &files)
+}
+
+/// The file each output shows: the wallpaper itself, or a copy scaled to the
+/// output's resolution when pre-scaling is enabled.
+fn output_files(img_path: &Path, outputs: &[OutputConfig]) -> Vec<(String, PathBuf)> {
+    let config = CONFIG.load();
+    let unscaled = || {
+        outputs
+            .iter()
+            .map(|o| (o.name.clone(), img_path.to_path_buf()))
+            .collect()
+    };
+    if config.prescale.enabled != Some(true) && !rasterizes(img_path) {
+        return unscaled();
+    }
+
+    let Some(cache_dir) = config.cache_subdir("scaled") else {
+        eprintln!("Cache path not configured");
+        return unscaled();
+    };
+    let mode = config.prescale.mode.unwrap_or_default();
+    let framing = load_framing(img_path);
+    prescale_outputs(img_path, outputs, mode, &framing, &cache_dir).unwrap_or_else(|e| {
+        eprintln!("Failed to pre-scale wallpaper: {:#}", e);
+        unscaled()
+    })
+}
+
+/// Whether `img_path` is an SVG to rasterize before handing it to the setter.
+fn rasterizes(img_path: &Path) -> bool {
+    CONFIG.load().prescale.rasterize_svg != Some(false)
+        && Format::of_file(img_path) == Some(Format::Svg)
+}
+
+/// The file the unsuffixed `current_wallpaper` link points at: the wallpaper,
+/// or for an SVG a rendering of it, so setters get a file they can read even
+/// when no outputs are known.
+fn main_file(img_path: &Path) -> PathBuf {
+    if !rasterizes(img_path) {
+        return img_path.to_path_buf();
+    }
+    output_files(img_path, &[OutputConfig::named("")])
+        .pop()
+        .map(|(_, file)| file)
+        .unwrap_or_else(|| img_path.to_path_buf())
+}
+
+/// The framing set for the wallpaper in the crop editor.
+fn load_framing(img_path: &Path) -> Framing {
+    let config = CONFIG.load();
+    let (Some(library_path), Some(name)) = (config.library_path.as_deref(), img_path.file_name())
+    else {
+        return Framing::default();
+    };
+    match Library::load(library_path) {
+        Ok(library) => library.framing(&name.to_string_lossy()),
+        Err(e) => {
+            eprintln!("Failed to load library: {:#}", e);
+            Framing::default()
+        }
+    }
+}
+
+/// Writes the wallpaper's color templates, so hooks can pick them up.
+fn export_palette(img_path: &Path) -> Result<()> {
+    let config = CONFIG.load();
+    let cache_dir = config
+        .cache_subdir("palettes")
+        .context("Cache path not configured")?;
+    let output_dir = config
+        .palette
+        .output_dir
+        .as_ref()
+        .context("Palette output directory not configured")?;
+    let count = config.palette.colors.unwrap_or(8).max(1);
+
+    Palette::cached(img_path, count, &cache_dir)?.export(img_path, output_dir)?;
+    Ok(())
+}
+
+/// Renders the configured derivatives and links them next to `current_wallpaper`.
+fn link_derivatives(img_path: &Path) -> Result<()> {
+    let config = CONFIG.load();
+    let cache_dir = config
+        .cache_subdir("derivatives")
+        .context("Cache path not configured")?;
+    let current_wallpaper = config
+        .current_wallpaper
+        .as_ref()
+        .context("Current wallpaper path not configured")?;
+
+    for (name, file) in render_derivatives(img_path, &config.derivatives, &cache_dir)? {
+        link(&file, &derivative_link(current_wallpaper, &name))?;
+    }
+    Ok(())
+}
+
+/// Links `main_file` and each output's file and runs the post script for each
+/// output. No outputs means a single, unnamed output showing `main_file`.
+fn apply_files(main_file: &Path, files: &[(String, PathBuf)]) -> Result<()> {
+    let config = CONFIG.load();
+    let current_wallpaper = config
+        .current_wallpaper
+        .as_ref()
+        .context("Current wallpaper path not configured")?;
+    let post_script = config.post_script.as_deref().unwrap_or("");
+
+    // The unsuffixed link always follows the most recently applied wallpaper
+    link(main_file, current_wallpaper)?;
+
+    if files.is_empty() {
+        return run_post_script(&expand_hook(post_script, "", current_wallpaper));
+    }
+
+    for (output, file) in files {
+        let output_wallpaper = output_link(current_wallpaper, output);
+        link(file, &output_wallpaper)?;
+        run_post_script(&expand_hook(post_script, output, &output_wallpaper))
+            .with_context(|| format!("Post script failed for output {}", output))?;
+    }
+
+    Ok(())
+}
+
+fn link(img_path: &Path, link_path: &Path) -> Result<()> {
+    let _ = std::fs::remove_file(link_path);
+
+    #[cfg(unix)]
+    symlink(img_path, link_path)
+        .with_context(|| format!("Failed to create symlink: {}", link_path.display()))?;
+
+    Ok(())
+}
+
+/// Substitutes `{output}` and `{wallpaper}` in the post script. Without an
+/// output, a `{output}` argument is left out, see [`without_output_argument`].
+pub fn expand_hook(post_script: &str, output: &str, wallpaper: &Path) -> String {
+    let post_script = if output.is_empty() {
+        without_output_argument(post_script)
+    } else {
+        post_script.to_string()
+    };
+    post_script
+        .replace("{output}", output)
+        .replace("{wallpaper}", &wallpaper.to_string_lossy())
+}
+
+/// Drops each argument that is only `{output}`, along with the option before
+/// it, so `swww img -o {output} ...` sets every output rather than failing
+/// on an empty output name.
+fn without_output_argument(post_script: &str) -> String {
+    let mut words: Vec<&str> = Vec::new();
+    for word in post_script.split(' ') {
+        if word.trim_matches(['\'', '"']) == "{output}" {
+            if words.last().is_some_and(|option| option.starts_with('-')) {
+                words.pop();
+            }
+            continue;
+        }
+        words.push(word);
+    }
+    words.join(" ")
+}
+
+fn run_post_script(post_script: &str) -> Result<()> {
+    if post_script.is_empty() {
+        return Ok(());
+    }
+
+    let status = Command::new("sh")
+        .arg("-c")
+        .arg(post_script)
+        .status()
+        .context("Failed to execute post script")?;
+    if !status.success() {
+        bail!("Post script exited with non-zero status: {}", status);
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_expand_hook() {
+        let wallpaper = Path::new("/home/u/.current_wallpaper-DP-1");
+        assert_eq!(
+            expand_hook(
+                "hyprctl hyprpaper wallpaper '{output},{wallpaper}'",
+                "DP-1",
+                wallpaper
+            ),
+            "hyprctl hyprpaper wallpaper 'DP-1,/home/u/.current_wallpaper-DP-1'"
+        );
+        assert_eq!(
+            expand_hook("swww img {wallpaper}", "", wallpaper),
+            "swww img /home/u/.current_wallpaper-DP-1"
+        );
+        assert_eq!(
+            expand_hook("notify-send done", "DP-1", wallpaper),
+            "notify-send done"
+        );
+
+        // Without an output, a `{output}` argument goes along with its option
+        let wallpaper = Path::new("/home/u/.current_wallpaper");
+        assert_eq!(
+            expand_hook("swww img -o {output} '{wallpaper}'", "", wallpaper),
+            "swww img '/home/u/.current_wallpaper'"
+        );
+        assert_eq!(
+            expand_hook("setter \"{output}\" {wallpaper}", "", wallpaper),
+            "setter /home/u/.current_wallpaper"
+        );
+        assert_eq!(
+            expand_hook(
+                "hyprctl hyprpaper reload '{output},{wallpaper}'",
+                "",
+                wallpaper
+            ),
+            "hyprctl hyprpaper reload ',/home/u/.current_wallpaper'"
+        );
+    }
+}
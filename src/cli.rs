@@ -0,0 +1,286 @@
+use std::{env, fs, path::PathBuf};
+
+use anyhow::{Context, Result, bail};
+use clap::{Args, Parser, Subcommand};
+
+use crate::{
+    apply::{ApplyTarget, apply_wallpaper},
+    config::{AppConfig, BaseDirs, CONFIG, Severity, Tool, schema, template},
+    daemon,
+    library::{Tone, load_classified, system_tone},
+    rotation::{RotationOrder, RotationSource},
+    tags::TagStore,
+    util::{get_absolute_path, parse_duration},
+};
+
+#[derive(Debug, Parser)]
+#[command(name = "wallity", version, about = "Wallpaper manager for Wayland")]
+pub struct Cli {
+    /// Read the config from this file instead of $WALLITY_CONFIG or
+    /// $XDG_CONFIG_HOME/wallity/wallity.toml
+    #[arg(long, global = true, value_name = "FILE")]
+    pub config: Option<PathBuf>,
+    /// Override a config key, e.g. --set daemon.interval=5m. Wins over the
+    /// config file and WALLITY_<KEY> environment variables
+    #[arg(long, global = true, value_name = "KEY=VALUE")]
+    pub set: Vec<String>,
+    /// Use this [profile.<name>] of the config instead of $WALLITY_PROFILE or
+    /// the one named after this machine
+    #[arg(long, global = true, value_name = "NAME")]
+    pub profile: Option<String>,
+    #[command(subcommand)]
+    pub command: Option<Command>,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum Command {
+    /// Manage the tag database
+    Tags {
+        #[command(subcommand)]
+        action: TagsCommand,
+    },
+    /// Rotate wallpapers on an interval
+    Daemon(DaemonArgs),
+    /// Apply a wallpaper
+    Apply {
+        image: PathBuf,
+        /// Only apply to this output
+        #[arg(long, conflicts_with = "span")]
+        output: Option<String>,
+        /// Split the image across all configured outputs
+        #[arg(long)]
+        span: bool,
+    },
+    /// Apply a random wallpaper
+    Random {
+        /// Wallpapers to pick from: all, favorites, tag:<name> or folder:<path>
+        #[arg(long, value_parser = parse_source)]
+        source: Option<RotationSource>,
+        /// Only pick wallpapers matching the light or dark theme, taken from
+        /// `prefer_dark` or else the desktop's color scheme
+        #[arg(long)]
+        match_theme: bool,
+    },
+    /// Inspect the configuration file
+    Config {
+        #[command(subcommand)]
+        action: ConfigCommand,
+    },
+}
+
+#[derive(Debug, Args)]
+pub struct DaemonArgs {
+    /// Time between wallpaper changes, e.g. 90s, 30m or 1h
+    #[arg(long)]
+    pub interval: Option<String>,
+    /// Wallpapers to rotate through: all, favorites, tag:<name> or folder:<path>
+    #[arg(long, value_parser = parse_source)]
+    pub source: Option<RotationSource>,
+    /// Shuffle, or go through wallpapers in name order
+    #[arg(long, value_enum)]
+    pub order: Option<RotationOrder>,
+}
+
+fn parse_source(value: &str) -> Result<RotationSource, String> {
+    value.parse().map_err(|e: anyhow::Error| e.to_string())
+}
+
+#[derive(Debug, Subcommand)]
+pub enum TagsCommand {
+    /// Write the tag database to a .toml or .json file
+    Export { file: PathBuf },
+    /// Merge tags from a .toml or .json file into the tag database
+    Import { file: PathBuf },
+}
+
+#[derive(Debug, Subcommand)]
+pub enum ConfigCommand {
+    /// Write a config file listing every setting with its default
+    Init {
+        /// Overwrite the config file if it exists
+        #[arg(long)]
+        force: bool,
+    },
+    /// Report errors and unknown keys, failing if the file can't be used
+    Check,
+    /// Print the settings from the config file, environment and --set flags
+    Show {
+        /// Include defaults, showing the effective value of every key
+        #[arg(long)]
+        resolved: bool,
+    },
+    /// Print the JSON Schema of the config file, for editor completion
+    Schema,
+}
+
+pub fn run(command: Command) -> Result<()> {
+    match command {
+        Command::Tags { action } => run_tags(action),
+        Command::Daemon(args) => run_daemon(args),
+        Command::Apply {
+            image,
+            output,
+            span,
+        } => {
+            let target = match (output, span) {
+                (Some(output), _) => ApplyTarget::Output(output),
+                (None, true) => ApplyTarget::Span,
+                (None, false) => ApplyTarget::All,
+            };
+            let image = get_absolute_path(&image.to_string_lossy())?;
+            apply_wallpaper(&image, &target)
+        }
+        Command::Random {
+            source,
+            match_theme,
+        } => run_random(source.unwrap_or(RotationSource::All), match_theme),
+        Command::Config { action } => run_config(action),
+    }
+}
+
+fn run_config(action: ConfigCommand) -> Result<()> {
+    match action {
+        ConfigCommand::Init { force } => {
+            let path = AppConfig::file_path()?;
+            if path.exists() && !force {
+                bail!(
+                    "{} already exists, pass --force to overwrite it",
+                    path.display()
+                );
+            }
+
+            let dirs = BaseDirs::from_env();
+            let tools = Tool::detect(env::var_os("PATH").as_deref());
+            let contents = template(
+                &AppConfig::defaults_in(dirs.as_ref()),
+                &tools,
+                dirs.as_ref().map(|dirs| dirs.home.as_path()),
+            );
+            if let Some(parent) = path.parent() {
+                fs::create_dir_all(parent)
+                    .with_context(|| format!("Failed to create {}", parent.display()))?;
+            }
+            fs::write(&path, contents)
+                .with_context(|| format!("Failed to write {}", path.display()))?;
+
+            println!("Wrote {}", path.display());
+            if let Some(tool) = tools.first() {
+                println!("Set post_script up for {}", tool);
+            }
+            Ok(())
+        }
+        ConfigCommand::Check => {
+            let report = AppConfig::resolve()?.report;
+            print!("{}", report);
+            if report.has_errors() {
+                bail!(
+                    "{} has {} error(s)",
+                    report.path.display(),
+                    report.count(Severity::Error)
+                );
+            }
+            match report.count(Severity::Warning) {
+                0 => println!("{} is valid", report.path.display()),
+                warnings => println!(
+                    "{} is valid with {} warning(s)",
+                    report.path.display(),
+                    warnings
+                ),
+            }
+            Ok(())
+        }
+        ConfigCommand::Show { resolved } => {
+            let config = AppConfig::resolve()?;
+            eprint!("{}", config.report);
+            println!("# {}", config.report.path.display());
+            if let Some(ref profile) = config.profile {
+                println!("# profile {}", profile);
+            }
+            print!("{}", config.describe(resolved));
+            Ok(())
+        }
+        ConfigCommand::Schema => {
+            println!("{}", serde_json::to_string_pretty(&schema())?);
+            Ok(())
+        }
+    }
+}
+
+fn run_random(source: RotationSource, match_theme: bool) -> Result<()> {
+    let config = CONFIG.load();
+    let mut pool = source.collect(config.wallpaper_path.as_deref(), &daemon::load_tags());
+
+    if match_theme {
+        let tone = config
+            .prefer_dark
+            .map(Tone::from_prefer_dark)
+            .or_else(system_tone)
+            .context("Could not detect the system theme, set prefer_dark in the config")?;
+        pool = load_classified(&config, &pool).filter_tone(pool, tone);
+        if pool.is_empty() {
+            bail!("No {} wallpapers found for source '{}'", tone, source);
+        }
+    }
+
+    // Prefer a change over re-applying the current wallpaper
+    if pool.len() > 1
+        && let Some(current) = config
+            .current_wallpaper
+            .as_ref()
+            .and_then(|link| std::fs::read_link(link).ok())
+    {
+        pool.retain(|path| *path != current);
+    }
+
+    let image = fastrand::choice(&pool)
+        .with_context(|| format!("No wallpapers found for source '{}'", source))?;
+    apply_wallpaper(image, &ApplyTarget::All)?;
+    println!("Applied {}", image.display());
+    Ok(())
+}
+
+fn run_daemon(args: DaemonArgs) -> Result<()> {
+    let config = &CONFIG.load().daemon;
+    let interval = args
+        .interval
+        .or_else(|| config.interval.clone())
+        .unwrap_or_else(|| String::from("30m"));
+    let interval = parse_duration(&interval)?;
+    if interval.is_zero() {
+        bail!("Daemon interval must be greater than zero");
+    }
+    let source = args
+        .source
+        .or_else(|| config.source.clone())
+        .unwrap_or(RotationSource::All);
+    let order = args.order.or(config.order).unwrap_or_default();
+
+    daemon::run(interval, source, order)
+}
+
+fn run_tags(action: TagsCommand) -> Result<()> {
+    let config = CONFIG.load();
+    let tags_path = config
+        .tags_path
+        .as_ref()
+        .context("Tags path not configured")?;
+    let mut store = TagStore::load(tags_path)?;
+
+    match action {
+        TagsCommand::Export { file } => {
+            store.export(&file)?;
+            println!(
+                "Exported tags for {} wallpapers to {}",
+                store.wallpapers.len(),
+                file.display()
+            );
+        }
+        TagsCommand::Import { file } => {
+            let count = store.import(&file)?;
+            store.save(tags_path)?;
+            println!("Imported tags for {} wallpapers", count);
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,499 @@
+use std::{
+    fmt, fs, io,
+    ops::Range,
+    path::{Path, PathBuf},
+};
+
+use serde::{
+    Deserializer,
+    de::{self, DeserializeOwned, Visitor},
+    forward_to_deserialize_any,
+};
+use toml::de::{DeTable, DeValue};
+
+use crate::{derivative::DerivativeConfig, outputs::OutputConfig, schedule::ScheduleRule};
+
+use super::{
+    AppConfig, DaemonConfig, PaletteConfig, PrescaleConfig, Source, SpanConfig, ThemeConfig,
+    profile::{ConfigFile, PROFILE_KEY, Profiles},
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Severity {
+    /// The file could not be used, so the defaults apply instead.
+    Error,
+    /// Something in the file is ignored.
+    Warning,
+}
+
+impl fmt::Display for Severity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Severity::Error => write!(f, "error"),
+            Severity::Warning => write!(f, "warning"),
+        }
+    }
+}
+
+/// A problem with the config, with its 1-based line and column if it is in
+/// the config file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Diagnostic {
+    pub severity: Severity,
+    pub source: Source,
+    pub location: Option<(usize, usize)>,
+    pub message: String,
+}
+
+impl Diagnostic {
+    /// Where the problem is, e.g. `line 3, column 12` or `$WALLITY_SPAN_BEZEL`.
+    pub fn place(&self) -> Option<String> {
+        match (&self.source, self.location) {
+            (Source::File, Some((line, column))) => {
+                Some(format!("line {}, column {}", line, column))
+            }
+            (Source::File | Source::Default, None) => None,
+            (source, _) => Some(source.to_string()),
+        }
+    }
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some(place) = self.place() {
+            write!(f, "{}: ", place)?;
+        }
+        write!(f, "{}: {}", self.severity, self.message)
+    }
+}
+
+/// Everything wrong with one config file.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ConfigReport {
+    pub path: PathBuf,
+    pub diagnostics: Vec<Diagnostic>,
+}
+
+impl ConfigReport {
+    pub fn is_empty(&self) -> bool {
+        self.diagnostics.is_empty()
+    }
+
+    pub fn has_errors(&self) -> bool {
+        self.count(Severity::Error) > 0
+    }
+
+    pub fn count(&self, severity: Severity) -> usize {
+        self.diagnostics
+            .iter()
+            .filter(|diagnostic| diagnostic.severity == severity)
+            .count()
+    }
+}
+
+/// One `path:line:column: severity: message` line per diagnostic, like a
+/// compiler, with the variable or flag instead of the path for overrides.
+impl fmt::Display for ConfigReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for diagnostic in &self.diagnostics {
+            match diagnostic.source {
+                Source::File | Source::Default => {
+                    write!(f, "{}", self.path.display())?;
+                    if let Some((line, column)) = diagnostic.location {
+                        write!(f, ":{}:{}", line, column)?;
+                    }
+                }
+                ref source => write!(f, "{}", source)?,
+            }
+            writeln!(f, ": {}: {}", diagnostic.severity, diagnostic.message)?;
+        }
+        Ok(())
+    }
+}
+
+/// Reads the config file at `path`. A missing file is an empty config unless
+/// it is `required`; a file with errors is reported and replaced by an empty
+/// config.
+pub fn read(path: &Path, required: bool) -> (ConfigFile, ConfigReport) {
+    let (file, diagnostics) = match fs::read_to_string(path) {
+        Ok(input) => parse(&input),
+        Err(e) if e.kind() == io::ErrorKind::NotFound && !required => {
+            (ConfigFile::empty(), Vec::new())
+        }
+        Err(e) => (
+            ConfigFile::empty(),
+            vec![Diagnostic {
+                severity: Severity::Error,
+                source: Source::File,
+                location: None,
+                message: format!("failed to read the file: {}", e),
+            }],
+        ),
+    };
+    let report = ConfigReport {
+        path: path.to_path_buf(),
+        diagnostics,
+    };
+    (file, report)
+}
+
+/// Parses a config file, reporting syntax and type errors with the key they
+/// are under, and keys wallity doesn't know with a suggested correction.
+pub fn parse(input: &str) -> (ConfigFile, Vec<Diagnostic>) {
+    let table = DeTable::parse(input).ok();
+
+    let parsed = toml::from_str::<AppConfig>(input).and_then(|config| {
+        let profiles = toml::from_str::<Profiles>(input)?.profile;
+        Ok(ConfigFile { config, profiles })
+    });
+    let file = match parsed {
+        Ok(file) => file,
+        Err(e) => {
+            let span = e.span();
+            let key = span
+                .clone()
+                .zip(table.as_ref())
+                .and_then(|(span, table)| key_at(table.get_ref(), &span, ""));
+            let message = match key {
+                Some(key) => format!("`{}`: {}", key, e.message().trim()),
+                None => e.message().trim().to_string(),
+            };
+            let error = Diagnostic {
+                severity: Severity::Error,
+                source: Source::File,
+                location: span.map(|span| location(input, span.start)),
+                message,
+            };
+            return (ConfigFile::empty(), vec![error]);
+        }
+    };
+
+    let mut diagnostics = Vec::new();
+    if let Some(table) = table {
+        unknown_keys(input, table.get_ref(), "", "", &mut diagnostics);
+    }
+    // Tables iterate in key order, report in file order instead
+    diagnostics.sort_by_key(|diagnostic| diagnostic.location);
+    (file, diagnostics)
+}
+
+/// Line and column of byte `offset` in `input`, both starting at 1.
+fn location(input: &str, offset: usize) -> (usize, usize) {
+    let before = &input[..offset.min(input.len())];
+    let line_start = before.rfind('\n').map_or(0, |idx| idx + 1);
+    (
+        before.matches('\n').count() + 1,
+        before[line_start..].chars().count() + 1,
+    )
+}
+
+fn join(path: &str, key: &str) -> String {
+    if path.is_empty() {
+        key.to_string()
+    } else {
+        format!("{}.{}", path, key)
+    }
+}
+
+/// Dotted key of the innermost value covering `span`, for errors that
+/// only point at a value.
+fn key_at(table: &DeTable<'_>, span: &Range<usize>, path: &str) -> Option<String> {
+    for (key, value) in table.iter() {
+        let key_path = join(path, key.get_ref());
+        let covers = |range: Range<usize>| range.start <= span.start && span.end <= range.end;
+        let nested = match value.get_ref() {
+            DeValue::Table(table) => key_at(table, span, &key_path),
+            DeValue::Array(array) => array.iter().find_map(|item| match item.get_ref() {
+                DeValue::Table(table) => key_at(table, span, &key_path),
+                _ => None,
+            }),
+            _ => None,
+        };
+        if nested.is_some() {
+            return nested;
+        }
+        if covers(value.span()) || covers(key.span()) {
+            return Some(key_path);
+        }
+    }
+    None
+}
+
+/// Keys allowed in the table at dotted `path`, or `None` for tables whose keys
+/// are not checked. Entries of arrays of tables share their array's path.
+pub(super) fn known_keys(path: &str) -> Option<&'static [&'static str]> {
+    Some(match path {
+        "" => field_names::<AppConfig>(),
+        "daemon" => field_names::<DaemonConfig>(),
+        "schedule" => field_names::<ScheduleRule>(),
+        "outputs" => field_names::<OutputConfig>(),
+        "span" => field_names::<SpanConfig>(),
+        "palette" => field_names::<PaletteConfig>(),
+        "derivatives" => field_names::<DerivativeConfig>(),
+        "prescale" => field_names::<PrescaleConfig>(),
+        "theme" => field_names::<ThemeConfig>(),
+        _ => return None,
+    })
+}
+
+/// Reports the unknown keys of `table` at dotted `path`. Keys in a profile
+/// are checked like those at the top level and shown under `profile`, the
+/// `profile.<name>` they are in.
+fn unknown_keys(
+    input: &str,
+    table: &DeTable<'_>,
+    path: &str,
+    profile: &str,
+    out: &mut Vec<Diagnostic>,
+) {
+    let Some(known) = known_keys(path) else {
+        return;
+    };
+
+    for (key, value) in table.iter() {
+        let name = key.get_ref();
+        let key_path = join(path, name);
+        if path.is_empty()
+            && profile.is_empty()
+            && name == PROFILE_KEY
+            && let DeValue::Table(profiles) = value.get_ref()
+        {
+            for (name, value) in profiles.iter() {
+                if let DeValue::Table(table) = value.get_ref() {
+                    let profile = join(PROFILE_KEY, name.get_ref());
+                    unknown_keys(input, table, "", &profile, out);
+                }
+            }
+            continue;
+        }
+        if !known.contains(&name.as_ref()) {
+            let mut message = format!("unknown key `{}`", join(profile, &key_path));
+            if let Some(suggestion) = suggest(name, known) {
+                message.push_str(&format!(", did you mean `{}`?", suggestion));
+            }
+            out.push(Diagnostic {
+                severity: Severity::Warning,
+                source: Source::File,
+                location: Some(location(input, key.span().start)),
+                message,
+            });
+            continue;
+        }
+
+        match value.get_ref() {
+            DeValue::Table(table) => unknown_keys(input, table, &key_path, profile, out),
+            DeValue::Array(array) => {
+                for item in array.iter() {
+                    if let DeValue::Table(table) = item.get_ref() {
+                        unknown_keys(input, table, &key_path, profile, out);
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Every key that holds a single setting, e.g. `daemon.interval`. Arrays of
+/// tables such as `outputs` count as one setting.
+pub(super) fn leaf_keys() -> Vec<String> {
+    let mut keys = Vec::new();
+    for &field in field_names::<AppConfig>() {
+        match known_keys(field) {
+            Some(nested) if !ARRAY_SECTIONS.contains(&field) => {
+                keys.extend(nested.iter().map(|key| join(field, key)));
+            }
+            _ => keys.push(field.to_string()),
+        }
+    }
+    keys
+}
+
+/// Sections written as arrays of tables, like `[[outputs]]`.
+pub(super) const ARRAY_SECTIONS: [&str; 3] = ["schedule", "outputs", "derivatives"];
+
+/// The known key closest to a misspelled one, if any is close enough.
+pub(super) fn suggest<'a, S: AsRef<str>>(key: &str, known: &'a [S]) -> Option<&'a str> {
+    known
+        .iter()
+        .map(|candidate| candidate.as_ref())
+        .map(|candidate| {
+            (
+                strsim::normalized_damerau_levenshtein(key, candidate),
+                candidate,
+            )
+        })
+        .filter(|(similarity, _)| *similarity >= 0.6)
+        .max_by(|a, b| a.0.total_cmp(&b.0))
+        .map(|(_, candidate)| candidate)
+}
+
+/// Field names of a struct as serde sees them, learned by asking its
+/// `Deserialize` impl to read from a deserializer that only records them.
+fn field_names<T: DeserializeOwned>() -> &'static [&'static str] {
+    let mut fields: &'static [&'static str] = &[];
+    let _ = T::deserialize(FieldNames(&mut fields));
+    fields
+}
+
+struct FieldNames<'a>(&'a mut &'static [&'static str]);
+
+impl<'de> Deserializer<'de> for FieldNames<'_> {
+    type Error = de::value::Error;
+
+    fn deserialize_any<V: Visitor<'de>>(self, _visitor: V) -> Result<V::Value, Self::Error> {
+        Err(de::Error::custom("not a struct"))
+    }
+
+    fn deserialize_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        fields: &'static [&'static str],
+        _visitor: V,
+    ) -> Result<V::Value, Self::Error> {
+        *self.0 = fields;
+        Err(de::Error::custom("field names recorded"))
+    }
+
+    forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string
+        bytes byte_buf option unit unit_struct newtype_struct seq tuple
+        tuple_struct map enum identifier ignored_any
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_field_names() {
+        assert_eq!(field_names::<SpanConfig>(), &["bezel"]);
+        // Read through the raw form it is converted from
+        assert!(field_names::<ScheduleRule>().contains(&"cron"));
+        assert!(field_names::<AppConfig>().contains(&"prescale"));
+        assert!(field_names::<String>().is_empty());
+    }
+
+    #[test]
+    fn test_leaf_keys() {
+        let keys = leaf_keys();
+        assert!(keys.contains(&"wallpaper_path".to_string()));
+        assert!(keys.contains(&"daemon.interval".to_string()));
+        assert!(keys.contains(&"prescale.rasterize_svg".to_string()));
+        assert!(keys.contains(&"outputs".to_string()));
+        assert!(!keys.contains(&"daemon".to_string()));
+        assert!(!keys.iter().any(|key| key.starts_with("outputs.")));
+    }
+
+    #[test]
+    fn test_valid_config() {
+        let (file, diagnostics) = parse(
+            "wallpaper_path = \"/walls\"\n\n[daemon]\ninterval = \"5m\"\n\n[[outputs]]\nname = \"DP-1\"\n",
+        );
+        assert!(diagnostics.is_empty());
+        assert_eq!(file.config.daemon.interval, Some("5m".to_string()));
+        assert!(file.profiles.is_empty());
+    }
+
+    #[test]
+    fn test_type_error() {
+        let (file, diagnostics) = parse("prefer_dark = true\n\n[daemon]\ninterval = 5\n");
+        assert!(file.config.prefer_dark.is_none());
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].severity, Severity::Error);
+        assert_eq!(diagnostics[0].location, Some((4, 12)));
+        assert!(
+            diagnostics[0].message.starts_with("`daemon.interval`: "),
+            "{}",
+            diagnostics[0].message
+        );
+    }
+
+    #[test]
+    fn test_syntax_error() {
+        let (_, diagnostics) = parse("wallpaper_path = \"/walls\"\npost_script \"x\"\n");
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].severity, Severity::Error);
+        assert_eq!(diagnostics[0].location.map(|(line, _)| line), Some(2));
+    }
+
+    #[test]
+    fn test_unknown_keys() {
+        let (file, diagnostics) = parse(
+            "wallpaper_pth = \"/walls\"\nprefer_dark = true\n\n[daemon]\nintervall = \"5m\"\n\n[[outputs]]\nname = \"DP-1\"\nzoom = 2\n",
+        );
+        // The rest of the file still applies
+        assert_eq!(file.config.prefer_dark, Some(true));
+
+        let messages: Vec<_> = diagnostics.iter().map(ToString::to_string).collect();
+        assert_eq!(
+            messages,
+            vec![
+                "line 1, column 1: warning: unknown key `wallpaper_pth`, did you mean `wallpaper_path`?",
+                "line 5, column 1: warning: unknown key `daemon.intervall`, did you mean `interval`?",
+                "line 9, column 1: warning: unknown key `outputs.zoom`",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_profiles() {
+        let (file, diagnostics) = parse(
+            "wallpaper_path = \"/walls\"\n\n[profile.laptop]\nwallpaper_path = \"/laptop\"\n\n[profile.laptop.daemon]\nintervall = \"5m\"\n\n[profile.desktop]\nprofile = \"x\"\n",
+        );
+        assert_eq!(file.config.wallpaper_path, Some(PathBuf::from("/walls")));
+        assert_eq!(
+            file.profiles["laptop"].wallpaper_path,
+            Some(PathBuf::from("/laptop"))
+        );
+
+        let messages: Vec<_> = diagnostics.iter().map(ToString::to_string).collect();
+        assert_eq!(
+            messages,
+            vec![
+                "line 7, column 1: warning: unknown key `profile.laptop.daemon.intervall`, did you mean `interval`?",
+                "line 10, column 1: warning: unknown key `profile.desktop.profile`",
+            ]
+        );
+
+        let (file, diagnostics) = parse("[profile.laptop]\nprefer_dark = \"yes\"\n");
+        assert!(file.profiles.is_empty());
+        assert_eq!(diagnostics[0].severity, Severity::Error);
+        assert!(
+            diagnostics[0]
+                .message
+                .starts_with("`profile.laptop.prefer_dark`: "),
+            "{}",
+            diagnostics[0].message
+        );
+    }
+
+    #[test]
+    fn test_read_report() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("wallity.toml");
+
+        let (_, report) = read(&path, false);
+        assert!(report.is_empty());
+        // A file given with --config has to exist
+        let (_, report) = read(&path, true);
+        assert!(report.has_errors());
+
+        fs::write(&path, "[span]\nbezle = 10\n").unwrap();
+        let (_, report) = read(&path, false);
+        assert!(!report.has_errors());
+        assert_eq!(report.count(Severity::Warning), 1);
+        assert_eq!(
+            report.to_string(),
+            format!(
+                "{}:2:1: warning: unknown key `span.bezle`, did you mean `bezel`?\n",
+                path.display()
+            )
+        );
+
+        fs::write(&path, "[span\n").unwrap();
+        let (_, report) = read(&path, false);
+        assert!(report.has_errors());
+    }
+}
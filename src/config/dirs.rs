@@ -0,0 +1,155 @@
+use std::{
+    env,
+    ffi::OsString,
+    path::{Path, PathBuf},
+};
+
+use anyhow::{Context, Result};
+
+use crate::util::get_absolute_path;
+
+/// Environment variable naming the config file, overridden by `--config`.
+pub const CONFIG_ENV: &str = "WALLITY_CONFIG";
+
+/// The XDG base directories, each falling back to its default under the home
+/// directory when the variable is unset or not an absolute path, as the spec
+/// requires.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BaseDirs {
+    pub home: PathBuf,
+    /// `$XDG_CONFIG_HOME`, `~/.config` by default.
+    pub config: PathBuf,
+    /// `$XDG_CACHE_HOME`, `~/.cache` by default.
+    pub cache: PathBuf,
+    /// `$XDG_DATA_HOME`, `~/.local/share` by default.
+    pub data: PathBuf,
+    /// `$XDG_STATE_HOME`, `~/.local/state` by default.
+    pub state: PathBuf,
+}
+
+impl BaseDirs {
+    /// The base directories of the current user, `None` without a home directory.
+    pub fn from_env() -> Option<Self> {
+        Self::from_vars(|name| env::var_os(name))
+    }
+
+    fn from_vars(var: impl Fn(&str) -> Option<OsString>) -> Option<Self> {
+        let home = var("HOME")
+            .map(PathBuf::from)
+            .filter(|home| home.is_absolute())
+            .or_else(|| get_absolute_path("~").ok())?;
+        let dir = |name: &str, default: &str| {
+            var(name)
+                .map(PathBuf::from)
+                .filter(|dir| dir.is_absolute())
+                .unwrap_or_else(|| home.join(default))
+        };
+
+        Some(Self {
+            config: dir("XDG_CONFIG_HOME", ".config"),
+            cache: dir("XDG_CACHE_HOME", ".cache"),
+            data: dir("XDG_DATA_HOME", ".local/share"),
+            state: dir("XDG_STATE_HOME", ".local/state"),
+            home,
+        })
+    }
+
+    /// The default config file, `$XDG_CONFIG_HOME/wallity/wallity.toml`.
+    pub fn config_file(&self) -> PathBuf {
+        self.config.join("wallity/wallity.toml")
+    }
+}
+
+/// Where the config file is and whether it was picked explicitly, in which
+/// case it has to exist. `--config` wins over `$WALLITY_CONFIG`, which wins
+/// over the XDG location.
+pub fn locate(
+    flag: Option<&Path>,
+    env_value: Option<OsString>,
+    dirs: Option<&BaseDirs>,
+) -> Result<(PathBuf, bool)> {
+    if let Some(path) = flag {
+        return Ok((get_absolute_path(&path.to_string_lossy())?, true));
+    }
+    if let Some(value) = env_value.filter(|value| !value.is_empty()) {
+        return Ok((get_absolute_path(&value.to_string_lossy())?, true));
+    }
+    let dirs = dirs.context("Could not find the home directory for the config file")?;
+    Ok((dirs.config_file(), false))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+    use tempfile::tempdir;
+
+    fn dirs_with(vars: &[(&str, &Path)]) -> BaseDirs {
+        let vars: HashMap<_, _> = vars
+            .iter()
+            .map(|(name, value)| (name.to_string(), value.as_os_str().to_owned()))
+            .collect();
+        BaseDirs::from_vars(|name| vars.get(name).cloned()).unwrap()
+    }
+
+    #[test]
+    fn test_default_base_dirs() {
+        let home = tempdir().unwrap();
+        let dirs = dirs_with(&[("HOME", home.path())]);
+
+        assert_eq!(dirs.config, home.path().join(".config"));
+        assert_eq!(dirs.cache, home.path().join(".cache"));
+        assert_eq!(dirs.data, home.path().join(".local/share"));
+        assert_eq!(dirs.state, home.path().join(".local/state"));
+        assert_eq!(
+            dirs.config_file(),
+            home.path().join(".config/wallity/wallity.toml")
+        );
+    }
+
+    #[test]
+    fn test_xdg_base_dirs() {
+        let home = tempdir().unwrap();
+        let xdg = tempdir().unwrap();
+        let dirs = dirs_with(&[
+            ("HOME", home.path()),
+            ("XDG_CONFIG_HOME", &xdg.path().join("config")),
+            ("XDG_CACHE_HOME", &xdg.path().join("cache")),
+            ("XDG_STATE_HOME", &xdg.path().join("state")),
+            // Relative paths are invalid and ignored
+            ("XDG_DATA_HOME", Path::new("relative/data")),
+        ]);
+
+        assert_eq!(dirs.config, xdg.path().join("config"));
+        assert_eq!(dirs.cache, xdg.path().join("cache"));
+        assert_eq!(dirs.state, xdg.path().join("state"));
+        assert_eq!(dirs.data, home.path().join(".local/share"));
+    }
+
+    #[test]
+    fn test_locate_config_file() {
+        let home = tempdir().unwrap();
+        let dirs = dirs_with(&[("HOME", home.path())]);
+        let flag = home.path().join("flag.toml");
+        let env_file = home.path().join("env.toml");
+
+        assert_eq!(
+            locate(None, None, Some(&dirs)).unwrap(),
+            (dirs.config_file(), false)
+        );
+        assert_eq!(
+            locate(None, Some(OsString::new()), Some(&dirs)).unwrap(),
+            (dirs.config_file(), false)
+        );
+        assert_eq!(
+            locate(None, Some(env_file.clone().into()), Some(&dirs)).unwrap(),
+            (env_file.clone(), true)
+        );
+        assert_eq!(
+            locate(Some(&flag), Some(env_file.into()), Some(&dirs)).unwrap(),
+            (flag.clone(), true)
+        );
+        assert_eq!(locate(Some(&flag), None, None).unwrap(), (flag, true));
+        assert!(locate(None, None, None).is_err());
+    }
+}
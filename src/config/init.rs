@@ -0,0 +1,394 @@
+use std::{
+    env,
+    ffi::OsStr,
+    fmt,
+    path::{Path, PathBuf},
+};
+
+use schemars::Schema;
+use serde_json::{Value as Json, json};
+use toml::{Table, Value};
+
+use super::{
+    AppConfig,
+    check::{ARRAY_SECTIONS, known_keys},
+    profile::PROFILE_KEY,
+};
+
+const HEADER: &str = "\
+# wallity configuration, written by `wallity config init`.
+#
+# Every setting is listed at its default, commented out. Uncomment a line to
+# change it, and run `wallity config check` to find mistakes.
+";
+
+const PROFILE_DESCRIPTION: &str = "\
+Settings for particular machines, overriding the ones above. The profile
+named after the hostname is used, unless --profile or $WALLITY_PROFILE
+picks another.";
+
+/// A wallpaper tool the generated config can set `post_script` up for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Tool {
+    Hyprpaper,
+    Swww,
+}
+
+impl Tool {
+    const ALL: [Tool; 2] = [Tool::Hyprpaper, Tool::Swww];
+
+    fn program(self) -> &'static str {
+        match self {
+            Tool::Hyprpaper => "hyprpaper",
+            Tool::Swww => "swww",
+        }
+    }
+
+    fn post_script(self) -> &'static str {
+        match self {
+            Tool::Hyprpaper => "hyprctl hyprpaper reload '{output},{wallpaper}'",
+            Tool::Swww => "swww img -o {output} '{wallpaper}'",
+        }
+    }
+
+    /// The tools installed in the directories of `path`, formatted like `$PATH`.
+    pub fn detect(path: Option<&OsStr>) -> Vec<Tool> {
+        let dirs: Vec<PathBuf> = path
+            .map(|path| env::split_paths(path).collect())
+            .unwrap_or_default();
+        Self::ALL
+            .into_iter()
+            .filter(|tool| dirs.iter().any(|dir| dir.join(tool.program()).is_file()))
+            .collect()
+    }
+}
+
+impl fmt::Display for Tool {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.program())
+    }
+}
+
+/// JSON Schema of the config file, for editors to complete and check it.
+pub fn schema() -> Schema {
+    let mut schema = schemars::schema_for!(AppConfig);
+    schema.insert("title".to_string(), json!("wallity.toml"));
+    if let Some(Json::Object(properties)) = schema.get_mut("properties") {
+        properties.insert(
+            PROFILE_KEY.to_string(),
+            json!({
+                "description": PROFILE_DESCRIPTION,
+                "type": "object",
+                // Profiles hold the same settings as the file itself
+                "additionalProperties": { "$ref": "#" },
+            }),
+        );
+    }
+    schema
+}
+
+/// A config file listing every setting at its value in `defaults`, commented
+/// out and described. `post_script` and `detect_outputs` are set up for the
+/// first of `tools`, and paths under `home` are written with `~`.
+pub fn template(defaults: &AppConfig, tools: &[Tool], home: Option<&Path>) -> String {
+    let schema = schema();
+    let docs = Docs(schema.as_value());
+    let values = Table::try_from(defaults).unwrap_or_default();
+    let mut out = String::from(HEADER);
+
+    let fields = known_keys("").unwrap_or_default();
+    let (sections, settings): (Vec<&str>, Vec<&str>) = fields
+        .iter()
+        .copied()
+        .partition(|field| known_keys(field).is_some());
+
+    for &key in &settings {
+        let property = docs.property(docs.0, key);
+        out.push('\n');
+        push_comment(
+            &mut out,
+            property.and_then(|property| docs.description(property)),
+        );
+        match key {
+            "post_script" if !tools.is_empty() => {
+                for (idx, tool) in tools.iter().enumerate() {
+                    let prefix = if idx == 0 { "" } else { "# " };
+                    let script = Value::String(tool.post_script().to_string());
+                    out.push_str(&format!("{}{} = {}\n", prefix, key, script));
+                }
+            }
+            "detect_outputs" if !tools.is_empty() => out.push_str(&format!("{} = true\n", key)),
+            _ => push_setting(&mut out, key, values.get(key), property, home),
+        }
+    }
+
+    for &section in &sections {
+        let Some(property) = docs.property(docs.0, section) else {
+            continue;
+        };
+        out.push('\n');
+        push_comment(&mut out, docs.description(property));
+
+        if ARRAY_SECTIONS.contains(&section) {
+            // Arrays are empty by default, so show the keys of one entry instead
+            out.push_str(&format!("# [[{}]]\n", section));
+            let entry = property.get("items").map(|items| docs.resolve(items));
+            for &key in known_keys(section).unwrap_or_default() {
+                let field = entry.and_then(|entry| docs.property(entry, key));
+                push_comment(&mut out, field.and_then(|field| docs.description(field)));
+                push_setting(&mut out, key, None, field, home);
+            }
+            continue;
+        }
+
+        out.push_str(&format!("[{}]\n", section));
+        let table = values.get(section).and_then(Value::as_table);
+        let section_schema = docs.resolve(property);
+        for (idx, &key) in known_keys(section).unwrap_or_default().iter().enumerate() {
+            let field = docs.property(section_schema, key);
+            if idx > 0 {
+                out.push('\n');
+            }
+            push_comment(&mut out, field.and_then(|field| docs.description(field)));
+            push_setting(
+                &mut out,
+                key,
+                table.and_then(|table| table.get(key)),
+                field,
+                home,
+            );
+        }
+    }
+
+    out.push('\n');
+    push_comment(&mut out, Some(PROFILE_DESCRIPTION));
+    out.push_str(&format!("# [{}.laptop]\n", PROFILE_KEY));
+    out.push_str("# wallpaper_path = \"~/Pictures/laptop\"\n");
+    out
+}
+
+fn push_comment(out: &mut String, text: Option<&str>) {
+    for line in text.into_iter().flat_map(str::lines) {
+        if line.is_empty() {
+            out.push_str("#\n");
+        } else {
+            out.push_str(&format!("# {}\n", line));
+        }
+    }
+}
+
+/// `# key = value`, with an example of the right type for settings without
+/// a default.
+fn push_setting(
+    out: &mut String,
+    key: &str,
+    value: Option<&Value>,
+    schema: Option<&Json>,
+    home: Option<&Path>,
+) {
+    let value = match value {
+        Some(Value::String(text)) => {
+            match home.and_then(|home| Path::new(text).strip_prefix(home).ok()) {
+                Some(relative) => Value::String(format!("~/{}", relative.display())).to_string(),
+                None => Value::String(text.clone()).to_string(),
+            }
+        }
+        Some(value) => value.to_string(),
+        None => example(schema),
+    };
+    out.push_str(&format!("# {} = {}\n", key, value));
+}
+
+/// The schema's own example of the setting, or a blank value of its type.
+fn example(schema: Option<&Json>) -> String {
+    if let Some(example) = schema
+        .and_then(|schema| schema.get("examples"))
+        .and_then(|examples| examples.get(0))
+        .and_then(|example| Value::try_from(example.clone()).ok())
+    {
+        return example.to_string();
+    }
+    let types: Vec<&str> = match schema.and_then(|schema| schema.get("type")) {
+        Some(Json::String(name)) => vec![name.as_str()],
+        Some(Json::Array(names)) => names.iter().filter_map(Json::as_str).collect(),
+        _ => Vec::new(),
+    };
+    match types.into_iter().find(|name| *name != "null") {
+        Some("boolean") => "false",
+        Some("integer") => "0",
+        Some("number") => "0.0",
+        Some("array") => "[]",
+        _ => "\"\"",
+    }
+    .to_string()
+}
+
+/// Looks up the descriptions of settings in the schema.
+struct Docs<'a>(&'a Json);
+
+impl<'a> Docs<'a> {
+    /// The definition `schema` refers to, or `schema` itself.
+    fn resolve(&self, schema: &'a Json) -> &'a Json {
+        schema
+            .get("$ref")
+            .and_then(Json::as_str)
+            .and_then(|reference| reference.strip_prefix("#/$defs/"))
+            .and_then(|name| self.0.get("$defs")?.get(name))
+            .unwrap_or(schema)
+    }
+
+    fn property(&self, schema: &'a Json, key: &str) -> Option<&'a Json> {
+        self.resolve(schema).get("properties")?.get(key)
+    }
+
+    /// The description of a property, or of the type it refers to.
+    fn description(&self, schema: &'a Json) -> Option<&'a str> {
+        schema
+            .get("description")
+            .or_else(|| self.resolve(schema).get("description"))
+            .and_then(Json::as_str)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::{BaseDirs, check::parse};
+    use std::fs;
+
+    fn defaults(home: &Path) -> AppConfig {
+        AppConfig::defaults_in(Some(&BaseDirs {
+            home: home.to_path_buf(),
+            config: home.join(".config"),
+            cache: home.join(".cache"),
+            data: home.join(".local/share"),
+            state: home.join(".local/state"),
+        }))
+    }
+
+    #[test]
+    fn test_template() {
+        let home = Path::new("/home/u");
+        let template = template(&defaults(home), &[], Some(home));
+
+        // Everything is commented out, so the file changes nothing
+        let (file, diagnostics) = parse(&template);
+        assert!(diagnostics.is_empty(), "{:?}", diagnostics);
+        assert!(file.config.post_script.is_none());
+        assert!(file.config.daemon.interval.is_none());
+
+        for line in [
+            "# Directory containing the wallpapers.\n# wallpaper_path = \"~/Pictures/wallpapers\"\n",
+            "# cache_path = \"~/.cache/wallity/thumbnails\"\n",
+            "# post_script = \"\"\n",
+            "# prefer_dark = false\n",
+            "[daemon]\n",
+            "# interval = \"30m\"\n",
+            "# order = \"shuffle\"\n",
+            "# [[outputs]]\n",
+            "# name = \"\"\n",
+            "# mode = \"fill\"\n",
+            "# [profile.laptop]\n",
+        ] {
+            assert!(
+                template.contains(line),
+                "{:?} missing from\n{}",
+                line,
+                template
+            );
+        }
+    }
+
+    #[test]
+    fn test_template_uncommented() {
+        let home = Path::new("/home/u");
+        let defaults = defaults(home);
+        let template = template(&defaults, &[], Some(home));
+
+        // Uncommenting the settings outside arrays and profiles gives the defaults
+        let mut uncommented = String::new();
+        let mut in_array = false;
+        for line in template.lines() {
+            if line.starts_with("# [") {
+                in_array = true;
+            } else if line.starts_with('[') {
+                in_array = false;
+            }
+            let setting = line.strip_prefix("# ").filter(|rest| {
+                !in_array
+                    && rest
+                        .split_once(" = ")
+                        .is_some_and(|(key, _)| !key.contains(' '))
+            });
+            uncommented.push_str(setting.unwrap_or(line));
+            uncommented.push('\n');
+        }
+        let (file, diagnostics) = parse(&uncommented.replace("~", "/home/u"));
+        assert!(diagnostics.is_empty(), "{:?}", diagnostics);
+        let parsed = Table::try_from(&file.config).unwrap();
+        let mut expected = Table::try_from(&defaults).unwrap();
+        // Unset settings are written with an example value
+        expected.insert("prefer_dark".to_string(), Value::Boolean(false));
+        let theme = expected["theme"].as_table_mut().unwrap();
+        theme.insert("base".to_string(), Value::from("dark"));
+        theme.insert("background".to_string(), Value::from("#1e1e2e"));
+        assert_eq!(parsed, expected);
+    }
+
+    #[test]
+    fn test_template_with_tools() {
+        let template = template(
+            &defaults(Path::new("/home/u")),
+            &[Tool::Hyprpaper, Tool::Swww],
+            None,
+        );
+        assert!(
+            template
+                .contains("\npost_script = \"hyprctl hyprpaper reload '{output},{wallpaper}'\"\n")
+        );
+        assert!(template.contains("\n# post_script = \"swww img -o {output} '{wallpaper}'\"\n"));
+        assert!(template.contains("\ndetect_outputs = true\n"));
+
+        let (file, diagnostics) = parse(&template);
+        assert!(diagnostics.is_empty(), "{:?}", diagnostics);
+        assert_eq!(file.config.detect_outputs, Some(true));
+    }
+
+    #[test]
+    fn test_tool_post_scripts_without_outputs() {
+        // No outputs known, e.g. when detecting them failed
+        let wallpaper = Path::new("/home/u/.current_wallpaper");
+        assert_eq!(
+            crate::apply::expand_hook(Tool::Swww.post_script(), "", wallpaper),
+            "swww img '/home/u/.current_wallpaper'"
+        );
+        assert_eq!(
+            crate::apply::expand_hook(Tool::Hyprpaper.post_script(), "", wallpaper),
+            "hyprctl hyprpaper reload ',/home/u/.current_wallpaper'"
+        );
+    }
+
+    #[test]
+    fn test_detect_tools() {
+        let dir = tempfile::tempdir().unwrap();
+        let bin = dir.path().join("bin");
+        fs::create_dir(&bin).unwrap();
+        fs::write(bin.join("swww"), "").unwrap();
+        let path = env::join_paths([dir.path(), &bin]).unwrap();
+
+        assert_eq!(Tool::detect(Some(&path)), vec![Tool::Swww]);
+        assert!(Tool::detect(None).is_empty());
+    }
+
+    #[test]
+    fn test_schema() {
+        let schema = schema();
+        let properties = schema.get("properties").unwrap();
+        assert_eq!(
+            properties["wallpaper_path"]["description"],
+            "Directory containing the wallpapers."
+        );
+        assert!(properties.get("daemon").is_some());
+        assert_eq!(properties[PROFILE_KEY]["additionalProperties"]["$ref"], "#");
+    }
+}
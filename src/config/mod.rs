@@ -0,0 +1,838 @@
+use crate::derivative::DerivativeConfig;
+use crate::outputs::OutputConfig;
+use crate::prescale::ScaleMode;
+use crate::rotation::{RotationOrder, RotationSource};
+use crate::schedule::ScheduleRule;
+use crate::theme::{
+    Accent, BaseTheme, DEFAULT_ACCENT, DEFAULT_BORDER_RADIUS, DEFAULT_BORDER_WIDTH,
+    DEFAULT_FONT_SIZE, DEFAULT_PLACEHOLDER, HexColor,
+};
+use crate::util::{get_absolute_path, resolve_dir_path, resolve_file_path};
+use arc_swap::ArcSwap;
+use once_cell::sync::{Lazy, OnceCell};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::env;
+use std::ffi::OsString;
+use std::path::PathBuf;
+use std::sync::Arc;
+
+pub use self::check::{ConfigReport, Diagnostic, Severity};
+pub use self::dirs::{BaseDirs, CONFIG_ENV};
+pub use self::init::{Tool, schema, template};
+pub use self::overrides::{Resolved, Source};
+pub use self::profile::{ConfigFile, PROFILE_ENV};
+
+mod check;
+mod dirs;
+mod init;
+mod overrides;
+mod profile;
+
+/// Config sources given on the command line.
+#[derive(Debug, Default)]
+pub struct CliOverrides {
+    /// `--config`, read instead of the default config file.
+    pub file: Option<PathBuf>,
+    /// `--set key=value` flags, applied over the file and the environment.
+    pub set: Vec<String>,
+    /// `--profile`, the profile to use instead of the one for this machine.
+    pub profile: Option<String>,
+}
+
+static CLI_OVERRIDES: OnceCell<CliOverrides> = OnceCell::new();
+
+/// Sets the command line overrides, before [`CONFIG`] is first used.
+pub fn use_cli_overrides(overrides: CliOverrides) {
+    let _ = CLI_OVERRIDES.set(overrides);
+}
+
+/// The config and what was wrong with its file, which is printed to stderr
+/// once when the config is first used.
+static LOADED: Lazy<(AppConfig, ConfigReport)> = Lazy::new(|| {
+    let (config, report) = AppConfig::load().unwrap_or_else(|e| {
+        eprintln!(
+            "Warning: Failed to load configuration: {}. Using defaults.",
+            e
+        );
+        (AppConfig::default(), ConfigReport::default())
+    });
+    eprint!("{}", report);
+    if report.has_errors() {
+        eprintln!(
+            "Warning: Ignoring {} and using defaults.",
+            report.path.display()
+        );
+    }
+    (config, report)
+});
+
+/// The current config, replaced when the app reloads it. Functions load it
+/// once and use that snapshot throughout.
+pub static CONFIG: Lazy<ArcSwap<AppConfig>> = Lazy::new(|| ArcSwap::from_pointee(LOADED.0.clone()));
+
+/// Problems found in the config file while first loading [`CONFIG`].
+pub fn config_report() -> &'static ConfigReport {
+    &LOADED.1
+}
+
+/// Reads the config again and makes it current. A file with errors leaves
+/// the current config in place rather than falling back to the defaults,
+/// since it is most likely being edited. Returns the config now current.
+pub fn reload() -> anyhow::Result<(Arc<AppConfig>, ConfigReport)> {
+    let (config, report) = AppConfig::load()?;
+    eprint!("{}", report);
+    if report.has_errors() {
+        eprintln!(
+            "Warning: Ignoring {} and keeping the current settings.",
+            report.path.display()
+        );
+    } else {
+        CONFIG.store(Arc::new(config));
+    }
+    Ok((CONFIG.load_full(), report))
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct AppConfig {
+    /// Directory containing the wallpapers.
+    pub wallpaper_path: Option<PathBuf>,
+    /// Symlink pointing at the current wallpaper, with one per output next
+    /// to it.
+    pub current_wallpaper: Option<PathBuf>,
+    /// Command run after a wallpaper is applied, with `{output}` and
+    /// `{wallpaper}` replaced by the output name and the symlink.
+    pub post_script: Option<String>,
+    /// Directory where thumbnails are cached.
+    pub cache_path: Option<PathBuf>,
+    /// File where wallpaper tags are stored.
+    pub tags_path: Option<PathBuf>,
+    /// File where the light/dark classification and framing of each
+    /// wallpaper is stored.
+    pub library_path: Option<PathBuf>,
+    /// Restricts rotation to dark (`true`) or light (`false`) wallpapers.
+    pub prefer_dark: Option<bool>,
+    #[serde(default)]
+    pub daemon: DaemonConfig,
+    /// Rules choosing the wallpaper by time of day, checked in order.
+    #[serde(default)]
+    pub schedule: Vec<ScheduleRule>,
+    /// Outputs and their place in the desktop layout.
+    #[serde(default)]
+    pub outputs: Vec<OutputConfig>,
+    /// Query hyprctl or swww for the outputs when none are configured.
+    pub detect_outputs: Option<bool>,
+    #[serde(default)]
+    pub span: SpanConfig,
+    #[serde(default)]
+    pub palette: PaletteConfig,
+    /// Variants of the applied wallpaper written next to it, e.g. a blurred
+    /// one for the lock screen.
+    #[serde(default)]
+    pub derivatives: Vec<DerivativeConfig>,
+    #[serde(default)]
+    pub prescale: PrescaleConfig,
+    #[serde(default)]
+    pub theme: ThemeConfig,
+}
+
+/// Settings for `wallity daemon`.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct DaemonConfig {
+    /// Time between wallpaper changes, e.g. `90s`, `30m` or `1h`.
+    pub interval: Option<String>,
+    /// `all`, `favorites`, `tag:<name>` or `folder:<path>`.
+    pub source: Option<RotationSource>,
+    /// `shuffle` or `sequential`.
+    pub order: Option<RotationOrder>,
+    /// File where the rotation position is saved.
+    pub state_path: Option<PathBuf>,
+}
+
+/// Settings for spanning one wallpaper across outputs.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct SpanConfig {
+    /// Pixels hidden behind the frames between neighbouring monitors.
+    pub bezel: Option<u32>,
+}
+
+/// Settings for extracting a color palette from applied wallpapers.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct PaletteConfig {
+    /// Extract a color palette when a wallpaper is applied.
+    pub enabled: Option<bool>,
+    /// Number of colors to extract.
+    pub colors: Option<usize>,
+    /// Directory the color templates are written to.
+    pub output_dir: Option<PathBuf>,
+}
+
+/// Settings for pre-scaling wallpapers to each output's resolution.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct PrescaleConfig {
+    /// Scale wallpapers to each output's resolution before applying them.
+    pub enabled: Option<bool>,
+    /// `fill` to cover the output, `fit` to fit inside it, or `center` to
+    /// keep the original size.
+    pub mode: Option<ScaleMode>,
+    /// Rasterize SVG wallpapers at each output's resolution, even when
+    /// pre-scaling is disabled.
+    pub rasterize_svg: Option<bool>,
+}
+
+/// Colors and sizes of the wallpaper browser.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct ThemeConfig {
+    /// Built-in theme the others are based on, e.g. `dark`, `light`, `nord`
+    /// or `catppuccin-mocha`. Follows the system when unset.
+    #[schemars(example = &"dark")]
+    pub base: Option<BaseTheme>,
+    /// Color of the selection and highlights as `#rrggbb`, or `wallpaper` to
+    /// take it from the applied wallpaper's palette.
+    pub accent: Option<Accent>,
+    /// Window background as `#rrggbb`, the base theme's when unset.
+    #[schemars(example = "#1e1e2e")]
+    pub background: Option<HexColor>,
+    /// Color shown in place of thumbnails that are still loading.
+    pub placeholder: Option<HexColor>,
+    /// Width of the selection border in pixels.
+    pub border_width: Option<f32>,
+    /// Corner radius of the selection border in pixels.
+    pub border_radius: Option<f32>,
+    /// Size of titles and the footer; other text is drawn a little smaller.
+    pub font_size: Option<f32>,
+}
+
+impl ThemeConfig {
+    fn merge(mut self, other: ThemeConfig) -> Self {
+        if other.base.is_some() {
+            self.base = other.base;
+        }
+        if other.accent.is_some() {
+            self.accent = other.accent;
+        }
+        if other.background.is_some() {
+            self.background = other.background;
+        }
+        if other.placeholder.is_some() {
+            self.placeholder = other.placeholder;
+        }
+        if other.border_width.is_some() {
+            self.border_width = other.border_width;
+        }
+        if other.border_radius.is_some() {
+            self.border_radius = other.border_radius;
+        }
+        if other.font_size.is_some() {
+            self.font_size = other.font_size;
+        }
+        self
+    }
+}
+
+impl PrescaleConfig {
+    fn merge(mut self, other: PrescaleConfig) -> Self {
+        if other.enabled.is_some() {
+            self.enabled = other.enabled;
+        }
+        if other.mode.is_some() {
+            self.mode = other.mode;
+        }
+        if other.rasterize_svg.is_some() {
+            self.rasterize_svg = other.rasterize_svg;
+        }
+        self
+    }
+}
+
+impl PaletteConfig {
+    fn merge(mut self, other: PaletteConfig) -> Self {
+        if other.enabled.is_some() {
+            self.enabled = other.enabled;
+        }
+        if other.colors.is_some() {
+            self.colors = other.colors;
+        }
+        if let Some(path) = other.output_dir {
+            self.output_dir = get_absolute_path(&path.to_string_lossy()).ok();
+        }
+        self
+    }
+}
+
+impl SpanConfig {
+    fn merge(mut self, other: SpanConfig) -> Self {
+        if other.bezel.is_some() {
+            self.bezel = other.bezel;
+        }
+        self
+    }
+}
+
+impl DaemonConfig {
+    fn merge(mut self, other: DaemonConfig) -> Self {
+        if other.interval.is_some() {
+            self.interval = other.interval;
+        }
+        if other.source.is_some() {
+            self.source = other.source;
+        }
+        if other.order.is_some() {
+            self.order = other.order;
+        }
+        if let Some(path) = other.state_path {
+            self.state_path = get_absolute_path(&path.to_string_lossy()).ok();
+        }
+        self
+    }
+}
+
+impl Default for AppConfig {
+    fn default() -> Self {
+        Self::defaults_in(BaseDirs::from_env().as_ref())
+    }
+}
+
+impl AppConfig {
+    /// The defaults, with paths under the given XDG base directories.
+    pub fn defaults_in(dirs: Option<&BaseDirs>) -> Self {
+        let path =
+            |base: fn(&BaseDirs) -> &PathBuf, file: &str| dirs.map(|dirs| base(dirs).join(file));
+
+        Self {
+            wallpaper_path: path(|dirs| &dirs.home, "Pictures/wallpapers"),
+            current_wallpaper: path(|dirs| &dirs.config, "wallity/.current_wallpaper"),
+            post_script: Some(String::from("")),
+            cache_path: path(|dirs| &dirs.cache, "wallity/thumbnails"),
+            tags_path: path(|dirs| &dirs.data, "wallity/tags.toml"),
+            library_path: path(|dirs| &dirs.data, "wallity/library.toml"),
+            prefer_dark: None,
+            daemon: DaemonConfig {
+                interval: Some(String::from("30m")),
+                source: Some(RotationSource::All),
+                order: Some(RotationOrder::Shuffle),
+                state_path: path(|dirs| &dirs.state, "wallity/daemon.toml"),
+            },
+            schedule: Vec::new(),
+            outputs: Vec::new(),
+            detect_outputs: Some(false),
+            span: SpanConfig { bezel: Some(0) },
+            palette: PaletteConfig {
+                enabled: Some(false),
+                colors: Some(8),
+                output_dir: path(|dirs| &dirs.cache, "wallity/colors"),
+            },
+            derivatives: Vec::new(),
+            prescale: PrescaleConfig {
+                enabled: Some(false),
+                mode: Some(ScaleMode::Fill),
+                rasterize_svg: Some(true),
+            },
+            theme: ThemeConfig {
+                base: None,
+                accent: Some(Accent::Color(DEFAULT_ACCENT)),
+                background: None,
+                placeholder: Some(DEFAULT_PLACEHOLDER),
+                border_width: Some(DEFAULT_BORDER_WIDTH),
+                border_radius: Some(DEFAULT_BORDER_RADIUS),
+                font_size: Some(DEFAULT_FONT_SIZE),
+            },
+        }
+    }
+
+    fn merge(mut self, other: AppConfig) -> Self {
+        if let Some(path) = other.wallpaper_path {
+            self.wallpaper_path = get_absolute_path(&path.to_string_lossy()).ok();
+        }
+        if let Some(path) = other.current_wallpaper {
+            self.current_wallpaper = get_absolute_path(&path.to_string_lossy()).ok();
+        }
+        if other.post_script.is_some() {
+            self.post_script = other.post_script;
+        }
+        if let Some(path) = other.cache_path {
+            self.cache_path = get_absolute_path(&path.to_string_lossy()).ok();
+        }
+        if let Some(path) = other.tags_path {
+            self.tags_path = get_absolute_path(&path.to_string_lossy()).ok();
+        }
+        if let Some(path) = other.library_path {
+            self.library_path = get_absolute_path(&path.to_string_lossy()).ok();
+        }
+        if other.prefer_dark.is_some() {
+            self.prefer_dark = other.prefer_dark;
+        }
+        self.daemon = self.daemon.merge(other.daemon);
+        if !other.schedule.is_empty() {
+            self.schedule = other.schedule;
+        }
+        if !other.outputs.is_empty() {
+            self.outputs = other.outputs;
+        }
+        if other.detect_outputs.is_some() {
+            self.detect_outputs = other.detect_outputs;
+        }
+        self.span = self.span.merge(other.span);
+        self.palette = self.palette.merge(other.palette);
+        if !other.derivatives.is_empty() {
+            self.derivatives = other.derivatives;
+        }
+        self.prescale = self.prescale.merge(other.prescale);
+        self.theme = self.theme.merge(other.theme);
+        self
+    }
+
+    pub fn empty() -> Self {
+        Self {
+            wallpaper_path: None,
+            current_wallpaper: None,
+            post_script: None,
+            cache_path: None,
+            tags_path: None,
+            library_path: None,
+            prefer_dark: None,
+            daemon: DaemonConfig::default(),
+            schedule: Vec::new(),
+            outputs: Vec::new(),
+            detect_outputs: None,
+            span: SpanConfig::default(),
+            palette: PaletteConfig::default(),
+            derivatives: Vec::new(),
+            prescale: PrescaleConfig::default(),
+            theme: ThemeConfig::default(),
+        }
+    }
+
+    /// A cache directory next to the thumbnail cache, e.g. `~/.cache/wallity/<name>`.
+    pub fn cache_subdir(&self, name: &str) -> Option<PathBuf> {
+        let cache_path = self.cache_path.as_ref()?;
+        Some(cache_path.parent().unwrap_or(cache_path).join(name))
+    }
+
+    /// The config file, which doesn't have to exist yet.
+    pub fn file_path() -> anyhow::Result<PathBuf> {
+        Ok(Self::locate()?.0)
+    }
+
+    fn locate() -> anyhow::Result<(PathBuf, bool)> {
+        let cli = CLI_OVERRIDES.get_or_init(CliOverrides::default);
+        dirs::locate(
+            cli.file.as_deref(),
+            env::var_os(CONFIG_ENV),
+            BaseDirs::from_env().as_ref(),
+        )
+    }
+
+    /// Builds the config from its layers, each merged over the previous one:
+    /// the defaults, the config file, `WALLITY_<KEY>` environment variables
+    /// and `--set` flags.
+    pub fn resolve() -> anyhow::Result<Resolved> {
+        let cli = CLI_OVERRIDES.get_or_init(CliOverrides::default);
+        let (path, explicit) = Self::locate()?;
+
+        let (file, mut report) = check::read(&path, explicit);
+
+        let env_profile = env::var(PROFILE_ENV).ok().filter(|name| !name.is_empty());
+        let requested = match (&cli.profile, &env_profile) {
+            (Some(name), _) => Some((name.as_str(), Source::ProfileFlag)),
+            (None, Some(name)) => Some((name.as_str(), Source::Env(PROFILE_ENV.to_string()))),
+            (None, None) => None,
+        };
+        let hostname = gethostname::gethostname().into_string().ok();
+        let profile = file
+            .select(requested, hostname.as_deref())
+            .unwrap_or_else(|diagnostic| {
+                report.diagnostics.push(diagnostic);
+                None
+            });
+
+        Ok(Self::layered(
+            Self::default(),
+            &file,
+            profile,
+            report,
+            env::vars_os(),
+            &cli.set,
+        ))
+    }
+
+    /// Merges the layers of the config, each over the ones before: the
+    /// `defaults`, the config file, its profile, `WALLITY_<KEY>` variables
+    /// among `vars`, and `--set` flags.
+    fn layered(
+        defaults: AppConfig,
+        file: &ConfigFile,
+        profile: Option<(&str, &AppConfig)>,
+        mut report: ConfigReport,
+        vars: impl IntoIterator<Item = (OsString, OsString)>,
+        flags: &[String],
+    ) -> Resolved {
+        let (env_overrides, env_diagnostics) = overrides::from_env(vars);
+        let (flag_overrides, flag_diagnostics) = overrides::from_flags(flags);
+        report.diagnostics.extend(env_diagnostics);
+        report.diagnostics.extend(flag_diagnostics);
+
+        let mut layers = vec![(Source::File, &file.config)];
+        layers.extend(profile.map(|(name, profile)| (Source::Profile(name.to_string()), profile)));
+
+        let config = layers
+            .iter()
+            .fold(defaults, |config, (_, layer)| {
+                config.merge((*layer).clone())
+            })
+            .merge(overrides::to_config(&env_overrides))
+            .merge(overrides::to_config(&flag_overrides));
+        let overrides = env_overrides.into_iter().chain(flag_overrides).collect();
+        Resolved::new(config, report, &layers, overrides)
+    }
+
+    pub fn load() -> anyhow::Result<(Self, ConfigReport)> {
+        let Resolved {
+            config: merged_config,
+            report,
+            ..
+        } = Self::resolve()?;
+
+        if let Some(ref current) = merged_config.current_wallpaper {
+            let _ = resolve_file_path(&current.to_string_lossy());
+        }
+        if let Some(ref cache) = merged_config.cache_path {
+            let _ = resolve_dir_path(&cache.to_string_lossy());
+        }
+
+        Ok((merged_config, report))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_default_config() {
+        let config = AppConfig::default();
+        assert!(config.post_script.is_some());
+    }
+
+    #[test]
+    fn test_defaults_in_base_dirs() {
+        let dir = tempfile::tempdir().unwrap();
+        let dirs = BaseDirs {
+            home: dir.path().join("home"),
+            config: dir.path().join("config"),
+            cache: dir.path().join("cache"),
+            data: dir.path().join("data"),
+            state: dir.path().join("state"),
+        };
+        let config = AppConfig::defaults_in(Some(&dirs));
+
+        let under = |path: &str| Some(dir.path().join(path));
+        assert_eq!(config.wallpaper_path, under("home/Pictures/wallpapers"));
+        assert_eq!(
+            config.current_wallpaper,
+            under("config/wallity/.current_wallpaper")
+        );
+        assert_eq!(config.cache_path, under("cache/wallity/thumbnails"));
+        assert_eq!(config.tags_path, under("data/wallity/tags.toml"));
+        assert_eq!(config.library_path, under("data/wallity/library.toml"));
+        assert_eq!(config.daemon.state_path, under("state/wallity/daemon.toml"));
+        assert_eq!(config.palette.output_dir, under("cache/wallity/colors"));
+
+        // Without a home directory only the paths are missing
+        let config = AppConfig::defaults_in(None);
+        assert!(config.wallpaper_path.is_none());
+        assert_eq!(config.daemon.interval, Some("30m".to_string()));
+    }
+
+    #[test]
+    fn test_empty_config() {
+        let config = AppConfig::empty();
+        assert!(config.wallpaper_path.is_none());
+        assert!(config.current_wallpaper.is_none());
+        assert!(config.post_script.is_none());
+        assert!(config.cache_path.is_none());
+        assert!(config.tags_path.is_none());
+    }
+
+    #[test]
+    fn test_merge_config() {
+        let mut config = AppConfig::empty();
+        let other = AppConfig {
+            wallpaper_path: Some(PathBuf::from("/tmp")),
+            current_wallpaper: None,
+            post_script: Some("test".to_string()),
+            cache_path: None,
+            tags_path: None,
+            library_path: None,
+            prefer_dark: None,
+            daemon: DaemonConfig::default(),
+            schedule: Vec::new(),
+            outputs: Vec::new(),
+            detect_outputs: None,
+            span: SpanConfig::default(),
+            palette: PaletteConfig::default(),
+            derivatives: Vec::new(),
+            prescale: PrescaleConfig::default(),
+            theme: ThemeConfig::default(),
+        };
+        config = config.merge(other);
+        assert!(config.wallpaper_path.is_some());
+        assert_eq!(config.post_script, Some("test".to_string()));
+    }
+
+    #[test]
+    fn test_merge_all_fields() {
+        let mut config = AppConfig::empty();
+        let other = AppConfig {
+            wallpaper_path: Some(PathBuf::from("/wallpapers")),
+            current_wallpaper: Some(PathBuf::from("/current")),
+            post_script: Some("script".to_string()),
+            cache_path: Some(PathBuf::from("/cache")),
+            tags_path: Some(PathBuf::from("/tags.toml")),
+            library_path: Some(PathBuf::from("/library.toml")),
+            prefer_dark: Some(true),
+            daemon: DaemonConfig {
+                interval: Some("5m".to_string()),
+                source: Some(RotationSource::Favorites),
+                order: Some(RotationOrder::Sequential),
+                state_path: Some(PathBuf::from("/state.toml")),
+            },
+            schedule: Vec::new(),
+            outputs: Vec::new(),
+            detect_outputs: None,
+            span: SpanConfig::default(),
+            palette: PaletteConfig::default(),
+            derivatives: Vec::new(),
+            prescale: PrescaleConfig::default(),
+            theme: ThemeConfig::default(),
+        };
+        config = config.merge(other);
+        assert!(config.wallpaper_path.is_some());
+        assert!(config.current_wallpaper.is_some());
+        assert_eq!(config.post_script, Some("script".to_string()));
+        assert!(config.cache_path.is_some());
+        assert!(config.tags_path.is_some());
+        assert!(config.library_path.is_some());
+        assert_eq!(config.prefer_dark, Some(true));
+        assert_eq!(config.daemon.interval, Some("5m".to_string()));
+        assert_eq!(config.daemon.source, Some(RotationSource::Favorites));
+        assert_eq!(config.daemon.order, Some(RotationOrder::Sequential));
+        assert!(config.daemon.state_path.is_some());
+    }
+
+    #[test]
+    fn test_merge_none() {
+        let original = AppConfig::default();
+        let merged = original.clone().merge(AppConfig::empty());
+
+        assert_eq!(original.wallpaper_path, merged.wallpaper_path);
+        assert_eq!(original.current_wallpaper, merged.current_wallpaper);
+        assert_eq!(original.post_script, merged.post_script);
+        assert_eq!(original.cache_path, merged.cache_path);
+        assert_eq!(original.tags_path, merged.tags_path);
+        assert_eq!(original.library_path, merged.library_path);
+        assert_eq!(original.prefer_dark, merged.prefer_dark);
+        assert_eq!(original.daemon, merged.daemon);
+        assert_eq!(original.schedule, merged.schedule);
+        assert_eq!(original.outputs, merged.outputs);
+        assert_eq!(original.detect_outputs, merged.detect_outputs);
+        assert_eq!(original.span, merged.span);
+        assert_eq!(original.palette, merged.palette);
+        assert_eq!(original.derivatives, merged.derivatives);
+        assert_eq!(original.prescale, merged.prescale);
+        assert_eq!(original.theme, merged.theme);
+    }
+
+    #[test]
+    fn test_parse_daemon_section() {
+        let user_config: AppConfig = toml::from_str(
+            r#"
+            [daemon]
+            interval = "10m"
+            source = "tag:dark"
+            "#,
+        )
+        .unwrap();
+        let config = AppConfig::default().merge(user_config);
+
+        assert_eq!(config.daemon.interval, Some("10m".to_string()));
+        assert_eq!(
+            config.daemon.source,
+            Some(RotationSource::Tag("dark".to_string()))
+        );
+        assert_eq!(config.daemon.order, Some(RotationOrder::Shuffle));
+
+        assert!(toml::from_str::<AppConfig>("[daemon]\nsource = \"nope\"").is_err());
+    }
+
+    #[test]
+    fn test_parse_schedule_section() {
+        let user_config: AppConfig = toml::from_str(
+            r#"
+            [[schedule]]
+            from = "06:00"
+            to = "12:00"
+            tag = "morning"
+
+            [[schedule]]
+            cron = "* 20-23 * * *"
+            folder = "~/Pictures/night"
+            "#,
+        )
+        .unwrap();
+        let config = AppConfig::default().merge(user_config);
+        assert_eq!(config.schedule.len(), 2);
+
+        let config = config.merge(AppConfig::empty());
+        assert_eq!(config.schedule.len(), 2);
+    }
+
+    #[test]
+    fn test_parse_outputs_section() {
+        let user_config: AppConfig = toml::from_str(
+            r#"
+            detect_outputs = true
+
+            [[outputs]]
+            name = "DP-1"
+
+            [[outputs]]
+            name = "HDMI-A-1"
+            "#,
+        )
+        .unwrap();
+        let config = AppConfig::default().merge(user_config);
+
+        assert_eq!(config.detect_outputs, Some(true));
+        let names: Vec<_> = config.outputs.iter().map(|o| o.name.as_str()).collect();
+        assert_eq!(names, vec!["DP-1", "HDMI-A-1"]);
+    }
+
+    #[test]
+    fn test_parse_palette_section() {
+        let user_config: AppConfig = toml::from_str(
+            r#"
+            [palette]
+            enabled = true
+            output_dir = "/tmp/colors"
+            "#,
+        )
+        .unwrap();
+        let config = AppConfig::default().merge(user_config);
+
+        assert_eq!(config.palette.enabled, Some(true));
+        assert_eq!(config.palette.colors, Some(8));
+        assert_eq!(
+            config.palette.output_dir,
+            Some(PathBuf::from("/tmp/colors"))
+        );
+    }
+
+    #[test]
+    fn test_parse_derivatives_section() {
+        let user_config: AppConfig = toml::from_str(
+            r#"
+            [[derivatives]]
+            name = "blur"
+            blur = 20.0
+            brightness = 0.6
+
+            [[derivatives]]
+            name = "pixel"
+            pixelate = 16
+            grayscale = true
+            "#,
+        )
+        .unwrap();
+        let config = AppConfig::default().merge(user_config);
+
+        assert_eq!(config.derivatives.len(), 2);
+        assert_eq!(config.derivatives[0].blur, Some(20.0));
+        assert!(!config.derivatives[0].grayscale);
+        assert_eq!(config.derivatives[1].pixelate, Some(16));
+    }
+
+    #[test]
+    fn test_parse_prescale_section() {
+        let user_config: AppConfig = toml::from_str("[prescale]\nenabled = true").unwrap();
+        let config = AppConfig::default().merge(user_config);
+        assert_eq!(config.prescale.enabled, Some(true));
+        assert_eq!(config.prescale.mode, Some(ScaleMode::Fill));
+        assert_eq!(config.prescale.rasterize_svg, Some(true));
+
+        let user_config: AppConfig = toml::from_str("[prescale]\nmode = \"fit\"").unwrap();
+        assert_eq!(user_config.prescale.mode, Some(ScaleMode::Fit));
+        assert!(toml::from_str::<AppConfig>("[prescale]\nmode = \"stretch\"").is_err());
+
+        let user_config: AppConfig = toml::from_str("[prescale]\nrasterize_svg = false").unwrap();
+        let config = AppConfig::default().merge(user_config);
+        assert_eq!(config.prescale.rasterize_svg, Some(false));
+    }
+
+    #[test]
+    fn test_cache_subdir() {
+        let mut config = AppConfig::empty();
+        assert_eq!(config.cache_subdir("span"), None);
+
+        config.cache_path = Some(PathBuf::from("/home/u/.cache/wallity/thumbnails"));
+        assert_eq!(
+            config.cache_subdir("span"),
+            Some(PathBuf::from("/home/u/.cache/wallity/span"))
+        );
+    }
+
+    #[test]
+    fn test_profile_precedence() {
+        let (file, report) = check::parse(
+            "wallpaper_path = \"/walls\"\npost_script = \"base\"\nprefer_dark = false\n\n[span]\nbezel = 10\n\n[profile.laptop]\nwallpaper_path = \"/laptop\"\npost_script = \"laptop\"\n\n[profile.laptop.span]\nbezel = 20\n",
+        );
+        assert!(report.is_empty());
+        let profile = file.select(Some(("laptop", Source::File)), None).unwrap();
+        let vars = [("WALLITY_POST_SCRIPT".into(), "env".into())];
+        let flags = ["span.bezel=30".to_string()];
+
+        let resolved = AppConfig::layered(
+            AppConfig::defaults_in(None),
+            &file,
+            profile,
+            ConfigReport::default(),
+            vars,
+            &flags,
+        );
+        let config = &resolved.config;
+        assert_eq!(config.prefer_dark, Some(false));
+        assert_eq!(config.wallpaper_path, Some(PathBuf::from("/laptop")));
+        assert_eq!(config.post_script, Some("env".to_string()));
+        assert_eq!(config.span.bezel, Some(30));
+        assert_eq!(config.daemon.interval, Some("30m".to_string()));
+
+        assert_eq!(resolved.profile, Some("laptop".to_string()));
+        assert_eq!(resolved.source("prefer_dark"), Source::File);
+        assert_eq!(
+            resolved.source("wallpaper_path"),
+            Source::Profile("laptop".to_string())
+        );
+        assert_eq!(
+            resolved.source("post_script"),
+            Source::Env("WALLITY_POST_SCRIPT".to_string())
+        );
+        assert_eq!(resolved.source("span.bezel"), Source::Flag);
+        assert_eq!(resolved.source("daemon.interval"), Source::Default);
+
+        // Without a profile the file's own settings apply
+        let resolved = AppConfig::layered(
+            AppConfig::defaults_in(None),
+            &file,
+            None,
+            ConfigReport::default(),
+            [],
+            &[],
+        );
+        assert_eq!(
+            resolved.config.wallpaper_path,
+            Some(PathBuf::from("/walls"))
+        );
+        assert_eq!(resolved.config.span.bezel, Some(10));
+        assert_eq!(resolved.profile, None);
+    }
+}
@@ -0,0 +1,408 @@
+use std::{ffi::OsString, fmt};
+
+use toml::{Table, Value};
+
+use super::{
+    AppConfig, CONFIG_ENV, ConfigReport, Diagnostic, PROFILE_ENV, Severity,
+    check::{leaf_keys, suggest},
+};
+
+const ENV_PREFIX: &str = "WALLITY_";
+
+/// Where a setting came from, from lowest to highest precedence.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Source {
+    Default,
+    File,
+    /// A `[profile.<name>]` section of the config file.
+    Profile(String),
+    /// A `WALLITY_<KEY>` environment variable.
+    Env(String),
+    /// A `--set key=value` flag.
+    Flag,
+    /// The `--profile` flag.
+    ProfileFlag,
+}
+
+impl fmt::Display for Source {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Source::Default => write!(f, "default"),
+            Source::File => write!(f, "config file"),
+            Source::Profile(name) => write!(f, "profile {}", name),
+            Source::Env(name) => write!(f, "${}", name),
+            Source::Flag => write!(f, "--set"),
+            Source::ProfileFlag => write!(f, "--profile"),
+        }
+    }
+}
+
+/// A value for one config key from the environment or the command line.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Override {
+    pub source: Source,
+    pub key: String,
+    pub value: Value,
+}
+
+/// The environment variable for a config key, e.g. `WALLITY_DAEMON_INTERVAL`
+/// for `daemon.interval`.
+pub fn env_name(key: &str) -> String {
+    format!("{}{}", ENV_PREFIX, key.replace('.', "_").to_uppercase())
+}
+
+/// Overrides from `WALLITY_<KEY>` variables among `vars`, ordered by key.
+pub fn from_env(
+    vars: impl IntoIterator<Item = (OsString, OsString)>,
+) -> (Vec<Override>, Vec<Diagnostic>) {
+    let keys = leaf_keys();
+    let names: Vec<String> = keys.iter().map(|key| env_name(key)).collect();
+    let mut overrides = Vec::new();
+    let mut diagnostics = Vec::new();
+
+    for (name, value) in vars {
+        let (Some(name), Some(value)) = (name.to_str(), value.to_str()) else {
+            continue;
+        };
+        if !name.starts_with(ENV_PREFIX) || name == CONFIG_ENV || name == PROFILE_ENV {
+            continue;
+        }
+
+        let source = Source::Env(name.to_string());
+        let Some(idx) = names.iter().position(|known| known == name) else {
+            // Compared without the prefix, which every name shares
+            let unprefixed: Vec<&str> = names
+                .iter()
+                .map(|known| &known[ENV_PREFIX.len()..])
+                .collect();
+            let mut message = String::from("unknown variable");
+            if let Some(suggestion) = suggest(&name[ENV_PREFIX.len()..], &unprefixed) {
+                message.push_str(&format!(", did you mean `{}{}`?", ENV_PREFIX, suggestion));
+            }
+            diagnostics.push(warning(source, message));
+            continue;
+        };
+        match parse_override(source.clone(), &keys[idx], value) {
+            Ok(value) => overrides.push(value),
+            Err(message) => diagnostics.push(warning(source, message)),
+        }
+    }
+
+    overrides.sort_by(|a, b| a.key.cmp(&b.key));
+    (overrides, diagnostics)
+}
+
+/// Overrides from `--set key=value` flags, later flags winning.
+pub fn from_flags(flags: &[String]) -> (Vec<Override>, Vec<Diagnostic>) {
+    let keys = leaf_keys();
+    let mut overrides = Vec::new();
+    let mut diagnostics = Vec::new();
+
+    for flag in flags {
+        let Some((key, value)) = flag.split_once('=') else {
+            let message = format!("`{}` is not in the form key=value", flag);
+            diagnostics.push(warning(Source::Flag, message));
+            continue;
+        };
+        let key = key.trim();
+        if !keys.iter().any(|known| known == key) {
+            let mut message = format!("unknown key `{}`", key);
+            if let Some(suggestion) = suggest(key, &keys) {
+                message.push_str(&format!(", did you mean `{}`?", suggestion));
+            }
+            diagnostics.push(warning(Source::Flag, message));
+            continue;
+        }
+        match parse_override(Source::Flag, key, value.trim()) {
+            Ok(value) => overrides.push(value),
+            Err(message) => diagnostics.push(warning(Source::Flag, message)),
+        }
+    }
+
+    (overrides, diagnostics)
+}
+
+fn warning(source: Source, message: String) -> Diagnostic {
+    Diagnostic {
+        severity: Severity::Warning,
+        source,
+        location: None,
+        message,
+    }
+}
+
+/// Reads `raw` as a TOML value such as `true`, `8` or `["a", "b"]`, or else
+/// as a string so strings don't need quotes, keeping whichever `key` accepts.
+fn parse_override(source: Source, key: &str, raw: &str) -> Result<Override, String> {
+    let typed = toml::from_str::<Table>(&format!("value = {}", raw))
+        .ok()
+        .and_then(|mut table| table.remove("value"))
+        .filter(|value| !value.is_str());
+
+    let mut first_error = None;
+    for value in typed.into_iter().chain([Value::String(raw.to_string())]) {
+        let mut table = Table::new();
+        insert(&mut table, key, value.clone());
+        match Value::Table(table).try_into::<AppConfig>() {
+            Ok(_) => {
+                return Ok(Override {
+                    source,
+                    key: key.to_string(),
+                    value,
+                });
+            }
+            Err(e) => {
+                first_error.get_or_insert_with(|| format!("ignoring `{}`: {}", key, e.message()));
+            }
+        }
+    }
+    Err(first_error.unwrap_or_default())
+}
+
+/// Sets dotted `key` in `table`, creating tables on the way.
+fn insert(table: &mut Table, key: &str, value: Value) {
+    match key.split_once('.') {
+        Some((section, rest)) => {
+            let entry = table
+                .entry(section)
+                .or_insert_with(|| Value::Table(Table::new()));
+            if let Value::Table(section) = entry {
+                insert(section, rest, value);
+            }
+        }
+        None => {
+            table.insert(key.to_string(), value);
+        }
+    }
+}
+
+fn get<'a>(table: &'a Table, key: &str) -> Option<&'a Value> {
+    match key.split_once('.') {
+        Some((section, rest)) => get(table.get(section)?.as_table()?, rest),
+        None => table.get(key),
+    }
+}
+
+/// A config holding just the `overrides`, to merge over the file config.
+pub fn to_config(overrides: &[Override]) -> AppConfig {
+    let mut table = Table::new();
+    for value in overrides {
+        insert(&mut table, &value.key, value.value.clone());
+    }
+    // Every override was checked on its own when it was parsed
+    Value::Table(table)
+        .try_into()
+        .unwrap_or_else(|_| AppConfig::empty())
+}
+
+/// The effective config, the problems found building it, and enough of each
+/// layer to tell where a value came from.
+#[derive(Debug, Clone)]
+pub struct Resolved {
+    pub config: AppConfig,
+    pub report: ConfigReport,
+    /// The profile applied over the config file, if any.
+    pub profile: Option<String>,
+    /// The settings of the config file and then of the profile, serialized.
+    layers: Vec<(Source, Table)>,
+    overrides: Vec<Override>,
+}
+
+impl Resolved {
+    /// `layers` are the config file and the profile, lowest precedence first.
+    pub fn new(
+        config: AppConfig,
+        report: ConfigReport,
+        layers: &[(Source, &AppConfig)],
+        overrides: Vec<Override>,
+    ) -> Self {
+        let profile = layers.iter().find_map(|(source, _)| match source {
+            Source::Profile(name) => Some(name.clone()),
+            _ => None,
+        });
+        Self {
+            config,
+            report,
+            profile,
+            layers: layers
+                .iter()
+                .map(|(source, config)| {
+                    (source.clone(), Table::try_from(config).unwrap_or_default())
+                })
+                .collect(),
+            overrides,
+        }
+    }
+
+    /// The layer that set `key`: the last override for it, the profile, the
+    /// file, or else the defaults.
+    pub fn source(&self, key: &str) -> Source {
+        if let Some(value) = self.overrides.iter().rev().find(|value| value.key == key) {
+            return value.source.clone();
+        }
+        self.layers
+            .iter()
+            .rev()
+            .find(|(_, settings)| match get(settings, key) {
+                // An empty array is what a layer has when it didn't set one
+                Some(Value::Array(array)) => !array.is_empty(),
+                Some(_) => true,
+                None => false,
+            })
+            .map_or(Source::Default, |(source, _)| source.clone())
+    }
+
+    /// Every setting that has a value as `key = value  # source` lines, which
+    /// read as TOML. Settings left at their default are skipped unless
+    /// `with_defaults` is set.
+    pub fn describe(&self, with_defaults: bool) -> String {
+        let settings = Table::try_from(&self.config).unwrap_or_default();
+        let lines: Vec<(String, Source)> = leaf_keys()
+            .into_iter()
+            .filter_map(|key| {
+                let value = get(&settings, &key)?;
+                let source = self.source(&key);
+                (with_defaults || source != Source::Default)
+                    .then(|| (format!("{} = {}", key, value), source))
+            })
+            .collect();
+
+        let width = lines.iter().map(|(line, _)| line.len()).max().unwrap_or(0);
+        lines
+            .iter()
+            .map(|(line, source)| format!("{:<width$}  # {}\n", line, source, width = width))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::{PrescaleConfig, SpanConfig};
+    use crate::prescale::ScaleMode;
+
+    fn env(vars: &[(&str, &str)]) -> Vec<(OsString, OsString)> {
+        vars.iter()
+            .map(|(name, value)| (name.into(), value.into()))
+            .collect()
+    }
+
+    #[test]
+    fn test_env_name() {
+        assert_eq!(env_name("wallpaper_path"), "WALLITY_WALLPAPER_PATH");
+        assert_eq!(env_name("daemon.interval"), "WALLITY_DAEMON_INTERVAL");
+    }
+
+    #[test]
+    fn test_from_env() {
+        let (overrides, diagnostics) = from_env(env(&[
+            ("HOME", "/home/u"),
+            ("WALLITY_CONFIG", "/tmp/wallity.toml"),
+            ("WALLITY_SPAN_BEZEL", "40"),
+            ("WALLITY_POST_SCRIPT", "notify-send done"),
+            ("WALLITY_PRESCALE_MODE", "fit"),
+            ("WALLITY_PALETTE_COLORS", "many"),
+            ("WALLITY_PREFER_DRAK", "true"),
+        ]));
+
+        let config = to_config(&overrides);
+        assert_eq!(config.span.bezel, Some(40));
+        assert_eq!(config.post_script, Some("notify-send done".to_string()));
+        assert_eq!(config.prescale.mode, Some(ScaleMode::Fit));
+        assert!(config.palette.colors.is_none());
+        assert_eq!(
+            overrides[0].source,
+            Source::Env("WALLITY_POST_SCRIPT".to_string())
+        );
+
+        let messages: Vec<_> = diagnostics.iter().map(ToString::to_string).collect();
+        assert_eq!(messages.len(), 2);
+        assert!(
+            messages[0].starts_with(
+                "$WALLITY_PALETTE_COLORS: warning: ignoring `palette.colors`: invalid type"
+            ),
+            "{}",
+            messages[0]
+        );
+        assert_eq!(
+            messages[1],
+            "$WALLITY_PREFER_DRAK: warning: unknown variable, did you mean `WALLITY_PREFER_DARK`?"
+        );
+    }
+
+    #[test]
+    fn test_from_flags() {
+        let flags = [
+            "prefer_dark=true",
+            "daemon.interval = 5m",
+            // Numbers are kept as strings for string keys
+            "post_script=1",
+            "outputs=[{ name = \"DP-1\", width = 1920 }]",
+            "span.bezel=10",
+            "span.bezel=20",
+            "bezel=1",
+            "detect_outputs",
+        ]
+        .map(String::from);
+        let (overrides, diagnostics) = from_flags(&flags);
+
+        let config = to_config(&overrides);
+        assert_eq!(config.prefer_dark, Some(true));
+        assert_eq!(config.daemon.interval, Some("5m".to_string()));
+        assert_eq!(config.post_script, Some("1".to_string()));
+        assert_eq!(config.outputs[0].width, Some(1920));
+        assert_eq!(config.span.bezel, Some(20));
+
+        let messages: Vec<_> = diagnostics.iter().map(ToString::to_string).collect();
+        assert_eq!(
+            messages,
+            vec![
+                "--set: warning: unknown key `bezel`",
+                "--set: warning: `detect_outputs` is not in the form key=value",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_sources() {
+        let mut file_config = AppConfig::empty();
+        file_config.span = SpanConfig { bezel: Some(10) };
+        file_config.prescale = PrescaleConfig {
+            enabled: Some(true),
+            ..PrescaleConfig::default()
+        };
+        let (env_overrides, _) = from_env(env(&[("WALLITY_SPAN_BEZEL", "20")]));
+        let (flag_overrides, _) = from_flags(&["prescale.mode=center".to_string()]);
+
+        let config = AppConfig::defaults_in(None)
+            .merge(file_config.clone())
+            .merge(to_config(&env_overrides))
+            .merge(to_config(&flag_overrides));
+        let overrides = env_overrides.into_iter().chain(flag_overrides).collect();
+        let resolved = Resolved::new(
+            config,
+            ConfigReport::default(),
+            &[(Source::File, &file_config)],
+            overrides,
+        );
+
+        assert_eq!(
+            resolved.source("span.bezel"),
+            Source::Env("WALLITY_SPAN_BEZEL".to_string())
+        );
+        assert_eq!(resolved.source("prescale.enabled"), Source::File);
+        assert_eq!(resolved.source("prescale.mode"), Source::Flag);
+        assert_eq!(resolved.source("daemon.interval"), Source::Default);
+        assert_eq!(resolved.source("outputs"), Source::Default);
+
+        assert_eq!(
+            resolved.describe(false),
+            "span.bezel = 20           # $WALLITY_SPAN_BEZEL\n\
+             prescale.enabled = true   # config file\n\
+             prescale.mode = \"center\"  # --set\n"
+        );
+        let all = resolved.describe(true);
+        assert!(all.contains("daemon.interval = \"30m\""), "{}", all);
+        assert!(all.contains("# default"), "{}", all);
+    }
+}
@@ -0,0 +1,138 @@
+use std::collections::BTreeMap;
+
+use serde::Deserialize;
+
+use super::{AppConfig, Diagnostic, Severity, Source, check::suggest};
+
+/// Environment variable naming the profile to use, overridden by `--profile`.
+pub const PROFILE_ENV: &str = "WALLITY_PROFILE";
+
+/// The table holding the profiles, as in `[profile.laptop]`.
+pub(super) const PROFILE_KEY: &str = "profile";
+
+/// The settings of a config file, and the `[profile.<name>]` sections that
+/// override them on particular machines.
+#[derive(Debug, Clone)]
+pub struct ConfigFile {
+    pub config: AppConfig,
+    pub profiles: BTreeMap<String, AppConfig>,
+}
+
+impl ConfigFile {
+    pub fn empty() -> Self {
+        Self {
+            config: AppConfig::empty(),
+            profiles: BTreeMap::new(),
+        }
+    }
+
+    /// The profile to apply: the one `requested` by `--profile` or
+    /// `$WALLITY_PROFILE`, or else the one named after this machine, if any.
+    /// A requested profile the file doesn't have is reported through
+    /// `source`.
+    pub fn select(
+        &self,
+        requested: Option<(&str, Source)>,
+        hostname: Option<&str>,
+    ) -> Result<Option<(&str, &AppConfig)>, Diagnostic> {
+        let Some((name, source)) = requested else {
+            return Ok(hostname.and_then(|hostname| {
+                self.profiles
+                    .iter()
+                    .find(|(name, _)| name.eq_ignore_ascii_case(hostname))
+                    .map(|(name, profile)| (name.as_str(), profile))
+            }));
+        };
+
+        match self.profiles.get_key_value(name) {
+            Some((name, profile)) => Ok(Some((name.as_str(), profile))),
+            None => {
+                let names: Vec<&str> = self.profiles.keys().map(String::as_str).collect();
+                let mut message = format!("no profile `{}` in the config file", name);
+                if let Some(suggestion) = suggest(name, &names) {
+                    message.push_str(&format!(", did you mean `{}`?", suggestion));
+                }
+                Err(Diagnostic {
+                    severity: Severity::Warning,
+                    source,
+                    location: None,
+                    message,
+                })
+            }
+        }
+    }
+}
+
+/// Just the profiles of a config file. The rest of it is read as an
+/// [`AppConfig`], which ignores them in turn.
+#[derive(Debug, Deserialize)]
+pub(super) struct Profiles {
+    #[serde(default)]
+    pub profile: BTreeMap<String, AppConfig>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn file_with(names: &[&str]) -> ConfigFile {
+        let mut file = ConfigFile::empty();
+        for name in names {
+            let mut profile = AppConfig::empty();
+            profile.post_script = Some(name.to_string());
+            file.profiles.insert(name.to_string(), profile);
+        }
+        file
+    }
+
+    fn selected(
+        file: &ConfigFile,
+        requested: Option<&str>,
+        hostname: Option<&str>,
+    ) -> Option<String> {
+        file.select(requested.map(|name| (name, Source::ProfileFlag)), hostname)
+            .unwrap()
+            .map(|(name, _)| name.to_string())
+    }
+
+    #[test]
+    fn test_select_profile() {
+        let file = file_with(&["desktop", "laptop"]);
+
+        assert_eq!(selected(&file, None, None), None);
+        assert_eq!(selected(&file, None, Some("server")), None);
+        // Hostnames are case-insensitive
+        assert_eq!(
+            selected(&file, None, Some("Laptop")),
+            Some("laptop".to_string())
+        );
+        // Asking for a profile wins over the hostname
+        assert_eq!(
+            selected(&file, Some("desktop"), Some("laptop")),
+            Some("desktop".to_string())
+        );
+    }
+
+    #[test]
+    fn test_unknown_profile() {
+        let file = file_with(&["desktop", "laptop"]);
+        let source = Source::Env(PROFILE_ENV.to_string());
+
+        let diagnostic = file
+            .select(Some(("laptpo", source)), Some("laptop"))
+            .unwrap_err();
+        assert_eq!(
+            diagnostic.to_string(),
+            "$WALLITY_PROFILE: warning: no profile `laptpo` in the config file, did you mean `laptop`?"
+        );
+
+        // The flag is blamed rather than the config file
+        let diagnostic = file
+            .select(Some(("server", Source::ProfileFlag)), None)
+            .unwrap_err();
+        assert_eq!(
+            diagnostic.to_string(),
+            "--profile: warning: no profile `server` in the config file"
+        );
+    }
+}
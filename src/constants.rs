@@ -3,3 +3,6 @@ pub const THUMBNAIL_WIDTH: f32 = 320.0;
 pub const THUMBNAIL_HEIGHT: f32 = 150.0;
 pub const ROW_HEIGHT: f32 = 155.0;
 pub const VIEWPORT_HEIGHT: f32 = 600.0;
+/// Largest size a wallpaper is shown at in the preview and the crop editor.
+pub const PREVIEW_WIDTH: f32 = 960.0;
+pub const PREVIEW_HEIGHT: f32 = 540.0;
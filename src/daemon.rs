@@ -0,0 +1,134 @@
+use std::{
+    path::PathBuf,
+    thread,
+    time::{Duration, Instant},
+};
+
+use anyhow::{Context, Result};
+
+use crate::{
+    apply::{ApplyTarget, apply_wallpaper},
+    config::CONFIG,
+    library::{Tone, load_classified},
+    rotation::{RotationOrder, RotationSource, RotationState},
+    schedule::{Clock, LocalClock, ScheduleTarget, Scheduler},
+    tags::TagStore,
+    util::get_absolute_path,
+};
+
+/// Longest time between checks for a change in the active schedule rule.
+const SCHEDULE_CHECK: Duration = Duration::from_secs(60);
+
+/// Applies a new wallpaper from `source` every `interval`, forever. While a
+/// `[[schedule]]` rule is active, its target replaces `source`.
+pub fn run(interval: Duration, source: RotationSource, order: RotationOrder) -> Result<()> {
+    let config = CONFIG.load();
+    let clock = LocalClock;
+    let rules = &config.schedule;
+    let state_path = config
+        .daemon
+        .state_path
+        .clone()
+        .context("Daemon state path not configured")?;
+    let mut state = RotationState::load(&state_path);
+    let mut rng = fastrand::Rng::new();
+
+    println!(
+        "Rotating wallpapers from '{}' every {}s ({:?}), {} schedule rule(s)",
+        source,
+        interval.as_secs(),
+        order,
+        rules.len()
+    );
+
+    let mut scheduler = Scheduler::default();
+    let mut current_rule = None;
+    let mut next_change = Instant::now();
+
+    loop {
+        let rule = scheduler.active(rules, clock.now());
+        let switched = current_rule != Some(rule);
+        if switched {
+            match rule {
+                Some(idx) => println!("Schedule '{}' is now active", rules[idx].label(idx)),
+                None if current_rule.is_some() => println!("No schedule active"),
+                None => {}
+            }
+            current_rule = Some(rule);
+        }
+
+        if switched || Instant::now() >= next_change {
+            let target = rule.map(|idx| &rules[idx].target);
+            match target {
+                Some(ScheduleTarget::Wallpaper(path)) => {
+                    // A fixed wallpaper only needs applying when its rule starts
+                    if switched {
+                        match get_absolute_path(&path.to_string_lossy()) {
+                            Ok(path) => apply(&path),
+                            Err(e) => eprintln!("{:#}", e),
+                        }
+                    }
+                }
+                _ => {
+                    let source = target
+                        .and_then(ScheduleTarget::source)
+                        .unwrap_or_else(|| source.clone());
+                    let tags = load_tags();
+                    let mut pool = source.collect(config.wallpaper_path.as_deref(), &tags);
+                    if let Some(prefer_dark) = config.prefer_dark {
+                        pool = restrict_tone(pool, Tone::from_prefer_dark(prefer_dark));
+                    }
+
+                    match state.next(&source, order, &pool, &mut rng) {
+                        Some(path) => {
+                            apply(&path);
+                            if let Err(e) = state.save(&state_path) {
+                                eprintln!("Failed to save rotation state: {:#}", e);
+                            }
+                        }
+                        None => eprintln!("No wallpapers found for source '{}'", source),
+                    }
+                }
+            }
+            next_change = Instant::now() + interval;
+        }
+
+        // Waking at the next change, so intervals that aren't whole minutes don't drift
+        thread::sleep(
+            next_change
+                .saturating_duration_since(Instant::now())
+                .min(SCHEDULE_CHECK),
+        );
+    }
+}
+
+fn apply(path: &std::path::Path) {
+    if let Err(e) = apply_wallpaper(path, &ApplyTarget::All) {
+        eprintln!("Failed to apply {}: {:#}", path.display(), e);
+    }
+}
+
+/// Keeps the wallpapers of the preferred tone, unless the source has none.
+fn restrict_tone(pool: Vec<PathBuf>, tone: Tone) -> Vec<PathBuf> {
+    let config = CONFIG.load();
+    let matching = load_classified(&config, &pool).filter_tone(pool.clone(), tone);
+    if matching.is_empty() && !pool.is_empty() {
+        eprintln!("No {} wallpapers in source, ignoring prefer_dark", tone);
+        return pool;
+    }
+    matching
+}
+
+pub fn load_tags() -> TagStore {
+    let config = CONFIG.load();
+    config
+        .tags_path
+        .as_deref()
+        .map(|path| {
+            TagStore::load(path).unwrap_or_else(|e| {
+                eprintln!("Failed to load tags: {:#}", e);
+                TagStore::default()
+            })
+        })
+        .unwrap_or_default()
+}
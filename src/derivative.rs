@@ -0,0 +1,229 @@
+use std::{
+    collections::hash_map::DefaultHasher,
+    hash::{Hash, Hasher},
+    path::{Path, PathBuf},
+};
+
+use anyhow::{Context, Result};
+use image::{DynamicImage, ImageFormat, imageops::FilterType};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use crate::{format::open_image, util::is_newer};
+
+/// A processed copy of the applied wallpaper from the `[[derivatives]]` config
+/// section, e.g. a blurred background for the lock screen.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct DerivativeConfig {
+    /// Suffix of the file written next to the wallpaper symlink.
+    pub name: String,
+    /// Gaussian blur radius (sigma) in pixels.
+    pub blur: Option<f32>,
+    /// Brightness multiplier, e.g. `0.6` dims to 60%.
+    pub brightness: Option<f32>,
+    /// Drop the colors.
+    #[serde(default)]
+    pub grayscale: bool,
+    /// Size of the pixelation blocks in pixels.
+    pub pixelate: Option<u32>,
+}
+
+impl DerivativeConfig {
+    /// Applies grayscale, brightness, blur and pixelation, in that order.
+    pub fn render(&self, img: &DynamicImage) -> DynamicImage {
+        let mut img = if self.grayscale {
+            DynamicImage::ImageRgba8(img.grayscale().to_rgba8())
+        } else {
+            img.clone()
+        };
+
+        if let Some(factor) = self.brightness {
+            let mut rgba = img.to_rgba8();
+            for pixel in rgba.pixels_mut() {
+                for channel in &mut pixel.0[..3] {
+                    *channel = (*channel as f32 * factor).round().clamp(0.0, 255.0) as u8;
+                }
+            }
+            img = DynamicImage::ImageRgba8(rgba);
+        }
+        if let Some(radius) = self.blur.filter(|radius| *radius > 0.0) {
+            img = img.fast_blur(radius);
+        }
+        if let Some(block) = self.pixelate.filter(|block| *block > 1) {
+            let (width, height) = (img.width(), img.height());
+            img = img
+                .resize_exact(
+                    width.div_ceil(block),
+                    height.div_ceil(block),
+                    FilterType::Triangle,
+                )
+                .resize_exact(width, height, FilterType::Nearest);
+        }
+
+        img
+    }
+
+    /// Cache file for this derivative of `source`. The name changes with the
+    /// source path and the settings, so edited settings are picked up.
+    fn cache_file(&self, source: &Path, cache_dir: &Path) -> PathBuf {
+        let mut hasher = DefaultHasher::new();
+        source.hash(&mut hasher);
+        format!("{:?}", self).hash(&mut hasher);
+
+        let stem = source
+            .file_stem()
+            .map(|s| s.to_string_lossy().to_string())
+            .unwrap_or_default();
+        cache_dir.join(format!(
+            "{}-{}-{:016x}.png",
+            stem,
+            self.name,
+            hasher.finish()
+        ))
+    }
+}
+
+/// Renders each derivative of `img_path` into `cache_dir`, reusing cached
+/// files that are newer than the source. Returns each derivative name with
+/// its file.
+pub fn render_derivatives(
+    img_path: &Path,
+    derivatives: &[DerivativeConfig],
+    cache_dir: &Path,
+) -> Result<Vec<(String, PathBuf)>> {
+    let mut source: Option<DynamicImage> = None;
+
+    derivatives
+        .iter()
+        .map(|derivative| {
+            let path = derivative.cache_file(img_path, cache_dir);
+            if !is_newer(&path, img_path) {
+                let img = match source {
+                    Some(ref img) => img,
+                    None => source.insert(open_image(img_path)?),
+                };
+
+                std::fs::create_dir_all(cache_dir).with_context(|| {
+                    format!("Failed to create directory: {}", cache_dir.display())
+                })?;
+                derivative
+                    .render(img)
+                    .save_with_format(&path, ImageFormat::Png)
+                    .with_context(|| format!("Failed to write {}", path.display()))?;
+            }
+            Ok((derivative.name.clone(), path))
+        })
+        .collect()
+}
+
+/// Symlink next to `current_wallpaper` for a derivative, e.g. `.current_wallpaper.blur`.
+pub fn derivative_link(current_wallpaper: &Path, name: &str) -> PathBuf {
+    let mut file_name = current_wallpaper
+        .file_name()
+        .map(|n| n.to_os_string())
+        .unwrap_or_default();
+    file_name.push(format!(".{}", name));
+    current_wallpaper.with_file_name(file_name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::{Rgb, RgbImage};
+    use tempfile::tempdir;
+
+    fn derivative(name: &str) -> DerivativeConfig {
+        DerivativeConfig {
+            name: name.to_string(),
+            blur: None,
+            brightness: None,
+            grayscale: false,
+            pixelate: None,
+        }
+    }
+
+    #[test]
+    fn test_render_operations() {
+        let img = DynamicImage::ImageRgb8(RgbImage::from_fn(8, 8, |x, y| {
+            Rgb([(x * 30) as u8, (y * 30) as u8, 200])
+        }));
+
+        let dimmed = DerivativeConfig {
+            brightness: Some(0.5),
+            ..derivative("dim")
+        }
+        .render(&img)
+        .to_rgb8();
+        assert_eq!(dimmed.get_pixel(2, 4).0, [30, 60, 100]);
+
+        let gray = DerivativeConfig {
+            grayscale: true,
+            ..derivative("gray")
+        }
+        .render(&img)
+        .to_rgb8();
+        let [r, g, b] = gray.get_pixel(3, 5).0;
+        assert!(r == g && g == b);
+
+        let pixelated = DerivativeConfig {
+            pixelate: Some(4),
+            ..derivative("pixel")
+        }
+        .render(&img)
+        .to_rgb8();
+        assert_eq!(pixelated.dimensions(), (8, 8));
+        assert_eq!(pixelated.get_pixel(0, 0), pixelated.get_pixel(3, 3));
+        assert_ne!(pixelated.get_pixel(0, 0), pixelated.get_pixel(4, 4));
+
+        let blurred = DerivativeConfig {
+            blur: Some(2.0),
+            ..derivative("blur")
+        }
+        .render(&img);
+        assert_eq!((blurred.width(), blurred.height()), (8, 8));
+    }
+
+    #[test]
+    fn test_render_derivatives_is_cached() {
+        let dir = tempdir().unwrap();
+        let source = dir.path().join("forest.png");
+        RgbImage::from_pixel(16, 16, Rgb([100, 150, 200]))
+            .save(&source)
+            .unwrap();
+        let cache_dir = dir.path().join("derivatives");
+
+        let blur = DerivativeConfig {
+            blur: Some(4.0),
+            ..derivative("blur")
+        };
+        let rendered =
+            render_derivatives(&source, std::slice::from_ref(&blur), &cache_dir).unwrap();
+        assert_eq!(rendered.len(), 1);
+        assert_eq!(rendered[0].0, "blur");
+        assert!(rendered[0].1.exists());
+
+        // An unchanged source reuses the cached file
+        std::fs::write(&rendered[0].1, b"cached").unwrap();
+        let again = render_derivatives(&source, std::slice::from_ref(&blur), &cache_dir).unwrap();
+        assert_eq!(again, rendered);
+        assert_eq!(std::fs::read(&rendered[0].1).unwrap(), b"cached");
+
+        // Different settings render to a different file
+        let stronger = DerivativeConfig {
+            blur: Some(8.0),
+            ..blur
+        };
+        let other = render_derivatives(&source, &[stronger], &cache_dir).unwrap();
+        assert_ne!(other[0].1, rendered[0].1);
+        assert!(image::open(&other[0].1).is_ok());
+    }
+
+    #[test]
+    fn test_derivative_link() {
+        let current = Path::new("/home/u/.config/wallity/.current_wallpaper");
+        assert_eq!(
+            derivative_link(current, "blur"),
+            PathBuf::from("/home/u/.config/wallity/.current_wallpaper.blur")
+        );
+    }
+}
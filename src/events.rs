@@ -1,13 +1,26 @@
+use anyhow::Context;
 use iced::futures::StreamExt;
 use iced::{Subscription, futures::SinkExt};
-use std::sync::mpsc;
+use notify::{EventKind, RecursiveMode, Watcher};
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    sync::mpsc,
+    time::Duration,
+};
 
-use crate::{message::Message, wallpaper::load_wallpapers};
+use crate::{config, message::Message, wallpaper::load_wallpapers};
 use iced::futures::channel::mpsc as futures_mpsc;
 use iced::stream;
 
-pub fn wallpaper_stream() -> Subscription<Message> {
-    Subscription::run(|| {
+/// How long the config file has to stay untouched before it is reloaded, as
+/// editors save in several steps.
+const RELOAD_DELAY: Duration = Duration::from_millis(200);
+
+/// Discovers the wallpapers, again whenever the wallpaper or cache directory
+/// in the config changes.
+pub fn wallpaper_stream(dirs: (Option<PathBuf>, Option<PathBuf>)) -> Subscription<Message> {
+    Subscription::run_with(dirs, |_| {
         stream::channel(
             500,
             |mut output: futures_mpsc::Sender<Message>| async move {
@@ -40,3 +53,66 @@ pub fn wallpaper_stream() -> Subscription<Message> {
         )
     })
 }
+
+/// Reloads the config whenever the file at `path` changes.
+pub fn config_stream(path: PathBuf) -> Subscription<Message> {
+    Subscription::run_with(path, |path| {
+        let path = path.clone();
+        stream::channel(10, |mut output: futures_mpsc::Sender<Message>| async move {
+            let (bridge_tx, mut bridge_rx) = futures_mpsc::channel(10);
+
+            std::thread::spawn(move || {
+                if let Err(e) = watch_config(&path, bridge_tx) {
+                    eprintln!("Failed to watch {}: {:#}", path.display(), e);
+                }
+            });
+
+            while let Some(message) = bridge_rx.next().await {
+                let _ = output.send(message).await;
+            }
+
+            loop {
+                std::future::pending::<()>().await;
+            }
+        })
+    })
+}
+
+fn watch_config(path: &Path, mut output: futures_mpsc::Sender<Message>) -> anyhow::Result<()> {
+    let dir = path.parent().context("The config file has no directory")?;
+    // Without a config file its directory may not exist yet, and a file
+    // created there later should still be picked up
+    fs::create_dir_all(dir).with_context(|| format!("Failed to create {}", dir.display()))?;
+    let (tx, rx) = mpsc::channel();
+    let mut watcher = notify::recommended_watcher(tx)?;
+    // Editors often replace the file instead of writing to it, which only
+    // shows up when watching its directory
+    watcher.watch(dir, RecursiveMode::NonRecursive)?;
+
+    while let Ok(event) = rx.recv() {
+        let Ok(event) = event else {
+            continue;
+        };
+        let touched = event
+            .paths
+            .iter()
+            .any(|changed| changed.file_name() == path.file_name());
+        if !touched || matches!(event.kind, EventKind::Access(_)) {
+            continue;
+        }
+        while rx.recv_timeout(RELOAD_DELAY).is_ok() {}
+
+        match config::reload() {
+            Ok((config, report)) => {
+                if output
+                    .try_send(Message::ConfigReloaded(config, report))
+                    .is_err()
+                {
+                    break;
+                }
+            }
+            Err(e) => eprintln!("Failed to reload configuration: {:#}", e),
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,250 @@
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+    process::{Command, Stdio},
+};
+
+use anyhow::{Context, Result, bail};
+
+use crate::thumbnail::{self, info_path};
+
+/// Something to do with the selected wallpaper's file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FileAction {
+    Rename,
+    Move,
+    Copy,
+    Trash,
+    CopyPath,
+    Reveal,
+}
+
+impl FileAction {
+    pub const ALL: [FileAction; 6] = [
+        FileAction::Rename,
+        FileAction::Move,
+        FileAction::Copy,
+        FileAction::Trash,
+        FileAction::CopyPath,
+        FileAction::Reveal,
+    ];
+
+    /// Whether the action applies to every marked wallpaper at once, rather
+    /// than only the one under the cursor.
+    pub fn is_bulk(self) -> bool {
+        !matches!(self, FileAction::Rename | FileAction::Reveal)
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            FileAction::Rename => "Rename",
+            FileAction::Move => "Move to folder",
+            FileAction::Copy => "Copy to folder",
+            FileAction::Trash => "Move to trash",
+            FileAction::CopyPath => "Copy path",
+            FileAction::Reveal => "Open containing folder",
+        }
+    }
+}
+
+/// Moves the wallpaper to the trash, following the freedesktop trash spec,
+/// and drops its cached thumbnail.
+pub fn trash_wallpaper(img_path: &Path, thumbnail_path: &Path) -> Result<()> {
+    trash::delete(img_path)
+        .with_context(|| format!("Failed to move {} to the trash", img_path.display()))?;
+    remove_thumbnail(thumbnail_path);
+    Ok(())
+}
+
+/// Renames the wallpaper within its directory, keeping its extension when
+/// `name` has none, and renames its thumbnail along with it. Returns the new
+/// image and thumbnail paths.
+pub fn rename_wallpaper(
+    img_path: &Path,
+    thumbnail_path: &Path,
+    name: &str,
+) -> Result<(PathBuf, PathBuf)> {
+    let name = name.trim();
+    if name.is_empty() || name.contains('/') || name == "." || name == ".." {
+        bail!("Invalid file name '{}'", name);
+    }
+    let mut new_path = img_path.with_file_name(name);
+    if new_path.extension().is_none()
+        && let Some(extension) = img_path.extension()
+    {
+        new_path.set_extension(extension);
+    }
+    if new_path == img_path {
+        return Ok((img_path.to_path_buf(), thumbnail_path.to_path_buf()));
+    }
+    if new_path.exists() {
+        bail!("{} already exists", new_path.display());
+    }
+    // Thumbnails are named by the whole file name, which is free by now
+    let new_thumbnail =
+        thumbnail::thumbnail_path(thumbnail_path.parent().unwrap_or(Path::new("")), &new_path);
+
+    fs::rename(img_path, &new_path)
+        .with_context(|| format!("Failed to rename {}", img_path.display()))?;
+
+    for (from, to) in [
+        (thumbnail_path.to_path_buf(), new_thumbnail.clone()),
+        (info_path(thumbnail_path), info_path(&new_thumbnail)),
+    ] {
+        if let Err(e) = fs::rename(&from, &to)
+            && e.kind() != io::ErrorKind::NotFound
+        {
+            eprintln!("Failed to rename thumbnail {}: {}", from.display(), e);
+        }
+    }
+    Ok((new_path, new_thumbnail))
+}
+
+/// Moves the wallpaper into the existing directory `dir`, copying it when
+/// the directory is on another file system, and drops its cached thumbnail.
+/// Returns the new path.
+pub fn move_wallpaper(img_path: &Path, thumbnail_path: &Path, dir: &Path) -> Result<PathBuf> {
+    let new_path = target_in(img_path, dir)?;
+    match fs::rename(img_path, &new_path) {
+        Ok(()) => {}
+        Err(e) if e.kind() == io::ErrorKind::CrossesDevices => {
+            fs::copy(img_path, &new_path)
+                .with_context(|| format!("Failed to copy {}", img_path.display()))?;
+            fs::remove_file(img_path)
+                .with_context(|| format!("Failed to remove {}", img_path.display()))?;
+        }
+        Err(e) => {
+            return Err(e).with_context(|| format!("Failed to move {}", img_path.display()));
+        }
+    }
+    remove_thumbnail(thumbnail_path);
+    Ok(new_path)
+}
+
+/// Copies the wallpaper into the existing directory `dir`. Returns the path
+/// of the copy.
+pub fn copy_wallpaper(img_path: &Path, dir: &Path) -> Result<PathBuf> {
+    let new_path = target_in(img_path, dir)?;
+    fs::copy(img_path, &new_path)
+        .with_context(|| format!("Failed to copy {}", img_path.display()))?;
+    Ok(new_path)
+}
+
+/// Where the wallpaper goes in `dir`, which has to exist and must not
+/// already have a file of that name.
+fn target_in(img_path: &Path, dir: &Path) -> Result<PathBuf> {
+    if !dir.is_dir() {
+        bail!("{} is not a directory", dir.display());
+    }
+    let file_name = img_path
+        .file_name()
+        .context("The wallpaper has no file name")?;
+    let new_path = dir.join(file_name);
+    if new_path.exists() {
+        bail!("{} already exists", new_path.display());
+    }
+    Ok(new_path)
+}
+
+/// Opens the directory containing `path` in the file manager.
+pub fn reveal(path: &Path) -> Result<()> {
+    let dir = path.parent().context("The wallpaper has no directory")?;
+    Command::new("xdg-open")
+        .arg(dir)
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()
+        .context("Failed to run xdg-open")?;
+    Ok(())
+}
+
+fn remove_thumbnail(thumbnail_path: &Path) {
+    for path in [thumbnail_path.to_path_buf(), info_path(thumbnail_path)] {
+        if let Err(e) = fs::remove_file(&path)
+            && e.kind() != io::ErrorKind::NotFound
+        {
+            eprintln!("Failed to remove thumbnail {}: {}", path.display(), e);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_rename_wallpaper() {
+        let dir = tempdir().unwrap();
+        let img_path = dir.path().join("old.png");
+        let thumbnail_path = dir.path().join("old.png.jpeg");
+        fs::write(&img_path, "image").unwrap();
+        fs::write(&thumbnail_path, "thumbnail").unwrap();
+        fs::write(info_path(&thumbnail_path), "{}").unwrap();
+        fs::write(dir.path().join("taken.png"), "").unwrap();
+
+        // The extension is kept unless a new one is given
+        let (new_path, new_thumbnail) =
+            rename_wallpaper(&img_path, &thumbnail_path, "new").unwrap();
+        assert_eq!(new_path, dir.path().join("new.png"));
+        assert_eq!(new_thumbnail, dir.path().join("new.png.jpeg"));
+        assert_eq!(fs::read_to_string(&new_path).unwrap(), "image");
+        assert_eq!(fs::read_to_string(&new_thumbnail).unwrap(), "thumbnail");
+        assert!(info_path(&new_thumbnail).exists());
+        assert!(!img_path.exists());
+
+        assert!(rename_wallpaper(&new_path, &new_thumbnail, "taken").is_err());
+        assert!(rename_wallpaper(&new_path, &new_thumbnail, "../escape.png").is_err());
+        assert!(rename_wallpaper(&new_path, &new_thumbnail, " ").is_err());
+        assert!(new_path.exists());
+
+        // A wallpaper with the same stem keeps its own thumbnail
+        fs::write(dir.path().join("other.jpg.jpeg"), "other").unwrap();
+        let (_, other_thumbnail) =
+            rename_wallpaper(&new_path, &new_thumbnail, "other.png").unwrap();
+        assert_eq!(other_thumbnail, dir.path().join("other.png.jpeg"));
+        assert_eq!(fs::read_to_string(&other_thumbnail).unwrap(), "thumbnail");
+        assert_eq!(
+            fs::read_to_string(dir.path().join("other.jpg.jpeg")).unwrap(),
+            "other"
+        );
+    }
+
+    #[test]
+    fn test_move_wallpaper() {
+        let dir = tempdir().unwrap();
+        let archive = dir.path().join("archive");
+        fs::create_dir(&archive).unwrap();
+        let img_path = dir.path().join("a.png");
+        let thumbnail_path = dir.path().join("a.jpeg");
+        fs::write(&img_path, "image").unwrap();
+        fs::write(&thumbnail_path, "thumbnail").unwrap();
+
+        assert!(move_wallpaper(&img_path, &thumbnail_path, &dir.path().join("missing")).is_err());
+
+        let new_path = move_wallpaper(&img_path, &thumbnail_path, &archive).unwrap();
+        assert_eq!(new_path, archive.join("a.png"));
+        assert_eq!(fs::read_to_string(&new_path).unwrap(), "image");
+        assert!(!img_path.exists());
+        assert!(!thumbnail_path.exists());
+
+        // Nothing is overwritten
+        fs::write(&img_path, "other").unwrap();
+        assert!(move_wallpaper(&img_path, &thumbnail_path, &archive).is_err());
+        assert_eq!(fs::read_to_string(&new_path).unwrap(), "image");
+    }
+
+    #[test]
+    fn test_copy_wallpaper() {
+        let dir = tempdir().unwrap();
+        let archive = dir.path().join("archive");
+        fs::create_dir(&archive).unwrap();
+        let img_path = dir.path().join("a.png");
+        fs::write(&img_path, "image").unwrap();
+
+        let copy = copy_wallpaper(&img_path, &archive).unwrap();
+        assert_eq!(fs::read_to_string(&copy).unwrap(), "image");
+        assert!(img_path.exists());
+        assert!(copy_wallpaper(&img_path, &archive).is_err());
+    }
+}
@@ -0,0 +1,629 @@
+use std::{
+    env,
+    fs::File,
+    io::{BufReader, Read},
+    path::Path,
+    sync::LazyLock,
+};
+
+use anyhow::{Context, Result};
+use image::{
+    DynamicImage, ImageDecoder, ImageFormat, ImageReader, ImageResult, metadata::Orientation,
+};
+use moxcms::{CmsError, ColorProfile, DataColorSpace, Layout, TransformOptions};
+
+/// Image formats wallity can show and apply.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    Png,
+    Jpeg,
+    Webp,
+    Gif,
+    Bmp,
+    Tiff,
+    Qoi,
+    /// Decoded with `avifdec`, when it is installed.
+    Avif,
+    /// Decoded with `djxl`, when it is installed.
+    Jxl,
+    /// Rasterized with resvg, behind the `svg` feature.
+    Svg,
+}
+
+/// Size an SVG is rendered to cover when no output size applies.
+const SVG_SIZE: (u32, u32) = (1920, 1080);
+
+impl Format {
+    /// Identifies a format from the first bytes of a file.
+    pub fn detect(header: &[u8]) -> Option<Self> {
+        const JXL_CONTAINER: &[u8] = b"\0\0\0\x0cJXL \r\n\x87\n";
+
+        let format = match header {
+            [0x89, b'P', b'N', b'G', b'\r', b'\n', 0x1a, b'\n', ..] => Format::Png,
+            [0xff, 0xd8, 0xff, ..] => Format::Jpeg,
+            [b'R', b'I', b'F', b'F', _, _, _, _, rest @ ..] if rest.starts_with(b"WEBP") => {
+                Format::Webp
+            }
+            [b'G', b'I', b'F', b'8', b'7' | b'9', b'a', ..] => Format::Gif,
+            [b'I', b'I', b'*', 0, ..] | [b'M', b'M', 0, b'*', ..] => Format::Tiff,
+            [b'q', b'o', b'i', b'f', ..] => Format::Qoi,
+            [0xff, 0x0a, ..] => Format::Jxl,
+            _ if header.starts_with(JXL_CONTAINER) => Format::Jxl,
+            _ if is_avif(header) => Format::Avif,
+            _ if is_svg(header) => Format::Svg,
+            // Checked last, "BM" is the least specific signature
+            [b'B', b'M', ..] => Format::Bmp,
+            _ => return None,
+        };
+        Some(format)
+    }
+
+    pub fn from_extension(ext: &str) -> Option<Self> {
+        let format = match ext.to_ascii_lowercase().as_str() {
+            "png" => Format::Png,
+            "jpg" | "jpeg" => Format::Jpeg,
+            "webp" => Format::Webp,
+            "gif" => Format::Gif,
+            "bmp" => Format::Bmp,
+            "tif" | "tiff" => Format::Tiff,
+            "qoi" => Format::Qoi,
+            "avif" => Format::Avif,
+            "jxl" => Format::Jxl,
+            "svg" => Format::Svg,
+            _ => return None,
+        };
+        Some(format)
+    }
+
+    /// The format of the file at `path`, by its contents or, when those are
+    /// not recognized, by its extension.
+    pub fn of_file(path: &Path) -> Option<Self> {
+        // Long enough for an XML declaration and comment before an `<svg>` tag
+        let mut header = [0u8; 256];
+        let len = File::open(path)
+            .and_then(|mut file| file.read(&mut header))
+            .unwrap_or(0);
+
+        Self::detect(&header[..len]).or_else(|| {
+            path.extension()
+                .and_then(|ext| ext.to_str())
+                .and_then(Self::from_extension)
+        })
+    }
+
+    /// The name the format is usually known by.
+    pub fn name(self) -> &'static str {
+        match self {
+            Format::Png => "PNG",
+            Format::Jpeg => "JPEG",
+            Format::Webp => "WebP",
+            Format::Gif => "GIF",
+            Format::Bmp => "BMP",
+            Format::Tiff => "TIFF",
+            Format::Qoi => "QOI",
+            Format::Avif => "AVIF",
+            Format::Jxl => "JPEG XL",
+            Format::Svg => "SVG",
+        }
+    }
+
+    /// Whether the format can be decoded, by this build or an installed tool.
+    pub fn is_enabled(self) -> bool {
+        // Looked up once, file listings ask for every wallpaper
+        static INSTALLED: LazyLock<Vec<&'static str>> = LazyLock::new(|| {
+            [Format::Avif, Format::Jxl]
+                .into_iter()
+                .filter_map(Format::decoder)
+                .filter(|decoder| is_installed(decoder))
+                .collect()
+        });
+
+        match self {
+            Format::Avif | Format::Jxl => self
+                .decoder()
+                .is_some_and(|decoder| INSTALLED.contains(&decoder)),
+            Format::Svg => cfg!(feature = "svg"),
+            Format::Png
+            | Format::Jpeg
+            | Format::Webp
+            | Format::Gif
+            | Format::Bmp
+            | Format::Tiff
+            | Format::Qoi => true,
+        }
+    }
+
+    /// The matching format of the `image` crate, for formats it decodes itself.
+    fn image_format(self) -> Option<ImageFormat> {
+        match self {
+            Format::Png => Some(ImageFormat::Png),
+            Format::Jpeg => Some(ImageFormat::Jpeg),
+            Format::Webp => Some(ImageFormat::WebP),
+            Format::Gif => Some(ImageFormat::Gif),
+            Format::Bmp => Some(ImageFormat::Bmp),
+            Format::Tiff => Some(ImageFormat::Tiff),
+            Format::Qoi => Some(ImageFormat::Qoi),
+            Format::Avif | Format::Jxl | Format::Svg => None,
+        }
+    }
+
+    /// Command line decoder converting the format to PNG.
+    fn decoder(self) -> Option<&'static str> {
+        match self {
+            Format::Avif => Some("avifdec"),
+            Format::Jxl => Some("djxl"),
+            _ => None,
+        }
+    }
+}
+
+/// Whether `program` is in one of the directories of `$PATH`.
+fn is_installed(program: &str) -> bool {
+    env::var_os("PATH")
+        .is_some_and(|path| env::split_paths(&path).any(|dir| dir.join(program).is_file()))
+}
+
+/// An ISO BMFF `ftyp` box naming an AVIF brand.
+fn is_avif(header: &[u8]) -> bool {
+    if header.len() < 12 || &header[4..8] != b"ftyp" {
+        return false;
+    }
+    let box_len = u32::from_be_bytes([header[0], header[1], header[2], header[3]]) as usize;
+    let brands = &header[8..box_len.clamp(12, header.len())];
+    brands
+        .chunks_exact(4)
+        .any(|brand| brand == b"avif" || brand == b"avis")
+}
+
+/// An XML document whose first element is `<svg>`.
+fn is_svg(header: &[u8]) -> bool {
+    let text = String::from_utf8_lossy(header);
+    let text = text.trim_start_matches('\u{feff}').trim_start();
+    text.starts_with("<svg")
+        || ((text.starts_with("<?xml") || text.starts_with("<!")) && text.contains("<svg"))
+}
+
+/// Whether the file at `path` is an image this build can decode.
+pub fn is_supported(path: &Path) -> bool {
+    Format::of_file(path).is_some_and(Format::is_enabled)
+}
+
+/// Opens an image by its contents, so misnamed files still load. The image
+/// is turned upright by its EXIF orientation and converted to sRGB when it
+/// embeds a color profile.
+pub fn open_image(path: &Path) -> Result<DynamicImage> {
+    let format = Format::of_file(path)
+        .with_context(|| format!("Unrecognized image format: {}", path.display()))?;
+
+    if let Some(image_format) = format.image_format() {
+        let decode = || -> ImageResult<DynamicImage> {
+            let mut decoder = reader(path, image_format)?.into_decoder()?;
+            let orientation = decoder.orientation()?;
+            let icc_profile = decoder.icc_profile()?;
+
+            let mut img = DynamicImage::from_decoder(decoder)?;
+            if let Some(icc_profile) = icc_profile
+                && let Err(e) = to_srgb(&mut img, &icc_profile)
+            {
+                eprintln!("Ignoring color profile of {}: {}", path.display(), e);
+            }
+            img.apply_orientation(orientation);
+            Ok(img)
+        };
+        return decode().with_context(|| format!("Failed to decode image: {}", path.display()));
+    }
+    match format {
+        Format::Svg => render_svg(path, SVG_SIZE.0, SVG_SIZE.1),
+        _ => decode_external(path, format),
+    }
+}
+
+/// Width and height of an image as [`open_image`] returns it, without
+/// decoding it where possible.
+pub fn image_dimensions(path: &Path) -> Result<(u32, u32)> {
+    match Format::of_file(path).and_then(Format::image_format) {
+        Some(image_format) => {
+            let read = || -> ImageResult<(u32, u32)> {
+                let mut decoder = reader(path, image_format)?.into_decoder()?;
+                let (width, height) = decoder.dimensions();
+                Ok(match decoder.orientation()? {
+                    Orientation::Rotate90
+                    | Orientation::Rotate270
+                    | Orientation::Rotate90FlipH
+                    | Orientation::Rotate270FlipH => (height, width),
+                    _ => (width, height),
+                })
+            };
+            read().with_context(|| format!("Failed to read image: {}", path.display()))
+        }
+        None if Format::of_file(path) == Some(Format::Svg) => {
+            svg_render_size(path, SVG_SIZE.0, SVG_SIZE.1)
+        }
+        None => open_image(path).map(|img| (img.width(), img.height())),
+    }
+}
+
+/// Metadata blocks embedded in an image file, undecoded.
+#[derive(Debug, Default)]
+pub struct Embedded {
+    pub exif: Option<Vec<u8>>,
+    pub icc_profile: Option<Vec<u8>>,
+}
+
+/// The EXIF block and ICC color profile embedded in an image, without
+/// decoding it. Formats the `image` crate doesn't read have neither.
+pub fn embedded_metadata(path: &Path) -> Result<Embedded> {
+    let Some(image_format) = Format::of_file(path).and_then(Format::image_format) else {
+        return Ok(Embedded::default());
+    };
+    let read = || -> ImageResult<Embedded> {
+        let mut decoder = reader(path, image_format)?.into_decoder()?;
+        Ok(Embedded {
+            exif: decoder.exif_metadata()?,
+            icc_profile: decoder.icc_profile()?,
+        })
+    };
+    read().with_context(|| format!("Failed to read image: {}", path.display()))
+}
+
+fn reader(path: &Path, format: ImageFormat) -> ImageResult<ImageReader<BufReader<File>>> {
+    let mut reader = ImageReader::open(path)?;
+    reader.set_format(format);
+    Ok(reader)
+}
+
+/// Converts RGB pixels described by an ICC profile to sRGB in place.
+fn to_srgb(img: &mut DynamicImage, icc_profile: &[u8]) -> std::result::Result<(), CmsError> {
+    let profile = ColorProfile::new_from_slice(icc_profile)?;
+    // Gray and CMYK images are already converted to RGB by the decoder
+    if profile.color_space != DataColorSpace::Rgb {
+        return Ok(());
+    }
+    let srgb = ColorProfile::new_srgb();
+    let options = TransformOptions::default();
+
+    if img.color().has_alpha() {
+        let mut rgba = img.to_rgba8();
+        let source = rgba.as_raw().clone();
+        profile
+            .create_transform_8bit(Layout::Rgba, &srgb, Layout::Rgba, options)?
+            .transform(&source, &mut rgba)?;
+        *img = DynamicImage::ImageRgba8(rgba);
+    } else {
+        let mut rgb = img.to_rgb8();
+        let source = rgb.as_raw().clone();
+        profile
+            .create_transform_8bit(Layout::Rgb, &srgb, Layout::Rgb, options)?
+            .transform(&source, &mut rgb)?;
+        *img = DynamicImage::ImageRgb8(rgb);
+    }
+    Ok(())
+}
+
+/// Decodes through the format's command line decoder into a temporary PNG.
+fn decode_external(path: &Path, format: Format) -> Result<DynamicImage> {
+    let Some(decoder) = format.decoder() else {
+        return Err(unsupported(path, format));
+    };
+    if !format.is_enabled() {
+        anyhow::bail!(
+            "{} images need {} installed: {}",
+            format.name(),
+            decoder,
+            path.display()
+        );
+    }
+    run_decoder(decoder, path)
+}
+
+fn run_decoder(decoder: &str, path: &Path) -> Result<DynamicImage> {
+    // A fresh, private file per decode; it is removed when dropped
+    let output = tempfile::Builder::new()
+        .prefix("wallity-")
+        .suffix(".png")
+        .tempfile()
+        .context("Failed to create a temporary file")?;
+    let status = std::process::Command::new(decoder)
+        .arg(path)
+        .arg(output.path())
+        .stdout(std::process::Stdio::null())
+        .status()
+        .with_context(|| format!("Failed to run {}", decoder))?;
+    if !status.success() {
+        anyhow::bail!("{} failed to decode {}", decoder, path.display());
+    }
+
+    image::open(output.path()).with_context(|| format!("Failed to read {} output", decoder))
+}
+
+fn unsupported(path: &Path, format: Format) -> anyhow::Error {
+    anyhow::anyhow!(
+        "{:?} images are not supported by this build: {}",
+        format,
+        path.display()
+    )
+}
+
+/// Rasterizes an SVG to cover `width`x`height`, see [`crate::svg::render`].
+#[cfg(feature = "svg")]
+pub fn render_svg(path: &Path, width: u32, height: u32) -> Result<DynamicImage> {
+    crate::svg::render(path, width, height)
+}
+
+#[cfg(not(feature = "svg"))]
+pub fn render_svg(path: &Path, _width: u32, _height: u32) -> Result<DynamicImage> {
+    Err(unsupported(path, Format::Svg))
+}
+
+#[cfg(feature = "svg")]
+fn svg_render_size(path: &Path, width: u32, height: u32) -> Result<(u32, u32)> {
+    crate::svg::render_size(path, width, height)
+}
+
+#[cfg(not(feature = "svg"))]
+fn svg_render_size(path: &Path, _width: u32, _height: u32) -> Result<(u32, u32)> {
+    Err(unsupported(path, Format::Svg))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::{
+        ExtendedColorType, ImageEncoder, Rgb, RgbImage,
+        codecs::{jpeg::JpegEncoder, png::PngEncoder},
+    };
+    use std::path::PathBuf;
+    use tempfile::tempdir;
+
+    /// A 4x4 image encoded as `format`.
+    fn fixture(dir: &Path, name: &str, format: ImageFormat) -> PathBuf {
+        let path = dir.join(name);
+        let img = DynamicImage::ImageRgb8(RgbImage::from_fn(4, 4, |x, y| {
+            Rgb([(x * 60) as u8, (y * 60) as u8, 120])
+        }));
+        img.save_with_format(&path, format).unwrap();
+        path
+    }
+
+    #[test]
+    fn test_detect_fixtures() {
+        let dir = tempdir().unwrap();
+        let fixtures = [
+            ("a.png", ImageFormat::Png, Format::Png),
+            ("a.jpg", ImageFormat::Jpeg, Format::Jpeg),
+            ("a.webp", ImageFormat::WebP, Format::Webp),
+            ("a.gif", ImageFormat::Gif, Format::Gif),
+            ("a.bmp", ImageFormat::Bmp, Format::Bmp),
+            ("a.tiff", ImageFormat::Tiff, Format::Tiff),
+            ("a.qoi", ImageFormat::Qoi, Format::Qoi),
+            ("a.avif", ImageFormat::Avif, Format::Avif),
+        ];
+
+        for (name, image_format, format) in fixtures {
+            let path = fixture(dir.path(), name, image_format);
+            assert_eq!(Format::of_file(&path), Some(format), "{}", name);
+            if format.is_enabled() && format.decoder().is_none() {
+                assert_eq!(open_image(&path).unwrap().width(), 4, "{}", name);
+                assert_eq!(image_dimensions(&path).unwrap(), (4, 4), "{}", name);
+            }
+        }
+    }
+
+    /// Whether a command line tool is installed, for tests that need one.
+    fn has_tool(tool: &str) -> bool {
+        std::process::Command::new(tool)
+            .arg("--version")
+            .stdout(std::process::Stdio::null())
+            .stderr(std::process::Stdio::null())
+            .status()
+            .is_ok()
+    }
+
+    #[test]
+    fn test_decode_external() {
+        let dir = tempdir().unwrap();
+        let png = fixture(dir.path(), "a.png", ImageFormat::Png);
+        let avif = fixture(dir.path(), "a.avif", ImageFormat::Avif);
+        // There's no JPEG XL encoder in `image`, so the fixture needs cjxl
+        let jxl = dir.path().join("a.jxl");
+        let has_jxl = has_tool("cjxl")
+            && std::process::Command::new("cjxl")
+                .arg(&png)
+                .arg(&jxl)
+                .stderr(std::process::Stdio::null())
+                .status()
+                .is_ok_and(|status| status.success());
+
+        for (path, format, has_fixture) in [(avif, Format::Avif, true), (jxl, Format::Jxl, has_jxl)]
+        {
+            let decoder = format.decoder().unwrap();
+            if !has_fixture || !has_tool(decoder) {
+                eprintln!(
+                    "Skipping {} decoding, the tools are not installed",
+                    format.name()
+                );
+                continue;
+            }
+            let img = run_decoder(decoder, &path).unwrap();
+            assert_eq!((img.width(), img.height()), (4, 4), "{}", format.name());
+            if format.is_enabled() {
+                assert_eq!(open_image(&path).unwrap().width(), 4, "{}", format.name());
+            }
+        }
+    }
+
+    #[test]
+    fn test_detect_jxl() {
+        assert_eq!(Format::detect(&[0xff, 0x0a, 0xfa, 0x1f]), Some(Format::Jxl));
+        assert_eq!(
+            Format::detect(b"\0\0\0\x0cJXL \r\n\x87\n\0\0\0\x14ftypjxl "),
+            Some(Format::Jxl)
+        );
+        assert_eq!(Format::detect(b"\0\0\0\x18ftypheic"), None);
+        assert_eq!(Format::detect(b"plain text"), None);
+    }
+
+    #[test]
+    fn test_detect_svg() {
+        assert_eq!(
+            Format::detect(b"<svg xmlns=\"http://www.w3.org/2000/svg\"/>"),
+            Some(Format::Svg)
+        );
+        assert_eq!(
+            Format::detect(b"\xef\xbb\xbf<?xml version=\"1.0\"?>\n<!-- logo -->\n<svg>"),
+            Some(Format::Svg)
+        );
+        assert_eq!(Format::detect(b"<?xml version=\"1.0\"?><html/>"), None);
+    }
+
+    #[cfg(feature = "svg")]
+    #[test]
+    fn test_open_svg() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("square");
+        std::fs::write(
+            &path,
+            r#"<svg xmlns="http://www.w3.org/2000/svg" width="10" height="10"><rect width="10" height="10" fill="teal"/></svg>"#,
+        )
+        .unwrap();
+
+        assert!(is_supported(&path));
+        // Rendered large enough to cover a 1080p output
+        assert_eq!(image_dimensions(&path).unwrap(), (1920, 1920));
+        let img = open_image(&path).unwrap();
+        assert_eq!((img.width(), img.height()), (1920, 1920));
+    }
+
+    #[test]
+    fn test_misnamed_files() {
+        let dir = tempdir().unwrap();
+        let png = dir.path().join("actually-png.jpg");
+        std::fs::rename(fixture(dir.path(), "a.png", ImageFormat::Png), &png).unwrap();
+        assert_eq!(Format::of_file(&png), Some(Format::Png));
+        assert!(open_image(&png).is_ok());
+
+        let bare = dir.path().join("no-extension");
+        std::fs::rename(fixture(dir.path(), "b.gif", ImageFormat::Gif), &bare).unwrap();
+        assert!(is_supported(&bare));
+
+        // Unreadable contents fall back to the extension
+        let empty = dir.path().join("empty.png");
+        std::fs::File::create(&empty).unwrap();
+        assert_eq!(Format::of_file(&empty), Some(Format::Png));
+        assert!(open_image(&empty).is_err());
+        assert!(!is_supported(&dir.path().join("notes.txt")));
+    }
+
+    /// 32x16 JPEG with red, green, blue and white quadrants, read from the
+    /// top left, tagged with an EXIF orientation.
+    fn oriented_fixture(dir: &Path, orientation: u16) -> PathBuf {
+        let img = RgbImage::from_fn(32, 16, |x, y| match (x < 16, y < 8) {
+            (true, true) => Rgb([255, 0, 0]),
+            (false, true) => Rgb([0, 255, 0]),
+            (true, false) => Rgb([0, 0, 255]),
+            (false, false) => Rgb([255, 255, 255]),
+        });
+        // Big endian TIFF header with a single IFD entry for the orientation
+        let mut exif = b"MM\0*\0\0\0\x08\0\x01\x01\x12\0\x03\0\0\0\x01".to_vec();
+        exif.extend_from_slice(&orientation.to_be_bytes());
+        exif.extend_from_slice(&[0; 6]);
+
+        let path = dir.join(format!("orientation-{}.jpg", orientation));
+        let mut encoder = JpegEncoder::new_with_quality(File::create(&path).unwrap(), 95);
+        encoder.set_exif_metadata(exif).unwrap();
+        encoder
+            .write_image(img.as_raw(), 32, 16, ExtendedColorType::Rgb8)
+            .unwrap();
+        path
+    }
+
+    /// Name of the quadrant color nearest to the pixel.
+    fn quadrant(img: &RgbImage, x: u32, y: u32) -> &'static str {
+        match img.get_pixel(x, y).0.map(|channel| channel > 127) {
+            [true, false, false] => "red",
+            [false, true, false] => "green",
+            [false, false, true] => "blue",
+            [true, true, true] => "white",
+            _ => "other",
+        }
+    }
+
+    #[test]
+    fn test_exif_orientation() {
+        let dir = tempdir().unwrap();
+        // Orientation, upright size, top left and top right quadrants
+        let expected = [
+            (1, (32, 16), "red", "green"),
+            (2, (32, 16), "green", "red"),
+            (3, (32, 16), "white", "blue"),
+            (4, (32, 16), "blue", "white"),
+            (5, (16, 32), "red", "blue"),
+            (6, (16, 32), "blue", "red"),
+            (7, (16, 32), "white", "green"),
+            (8, (16, 32), "green", "white"),
+        ];
+
+        for (orientation, size, top_left, top_right) in expected {
+            let path = oriented_fixture(dir.path(), orientation);
+            assert_eq!(image_dimensions(&path).unwrap(), size, "{}", orientation);
+
+            let img = open_image(&path).unwrap().to_rgb8();
+            assert_eq!(img.dimensions(), size, "{}", orientation);
+            let right = img.width() - 4;
+            assert_eq!(quadrant(&img, 3, 3), top_left, "{}", orientation);
+            assert_eq!(quadrant(&img, right, 3), top_right, "{}", orientation);
+        }
+    }
+
+    #[test]
+    fn test_icc_profile_converted_to_srgb() {
+        let dir = tempdir().unwrap();
+        let img = RgbImage::from_fn(2, 1, |x, _| {
+            if x == 0 {
+                Rgb([200, 100, 50])
+            } else {
+                Rgb([128, 128, 128])
+            }
+        });
+        let write = |name: &str, profile: Option<Vec<u8>>| {
+            let path = dir.path().join(name);
+            let mut encoder = PngEncoder::new(File::create(&path).unwrap());
+            if let Some(profile) = profile {
+                encoder.set_icc_profile(profile).unwrap();
+            }
+            encoder
+                .write_image(img.as_raw(), 2, 1, ExtendedColorType::Rgb8)
+                .unwrap();
+            path
+        };
+
+        let plain = open_image(&write("plain.png", None)).unwrap().to_rgb8();
+        assert_eq!(plain, img);
+
+        let p3 = ColorProfile::new_display_p3().encode().unwrap();
+        let converted = open_image(&write("p3.png", Some(p3))).unwrap().to_rgb8();
+        // Display P3 colors are more saturated than the same values in sRGB
+        let [r, g, b] = converted.get_pixel(0, 0).0;
+        assert!(r > 200 && g < 100 && b < 50, "{:?}", [r, g, b]);
+        // Neutral gray has the same meaning in both
+        let [r, g, b] = converted.get_pixel(1, 0).0;
+        assert!(r.abs_diff(128) <= 2 && g.abs_diff(128) <= 2 && b.abs_diff(128) <= 2);
+
+        // A broken profile is ignored
+        let broken = open_image(&write("broken.png", Some(b"not a profile".to_vec())));
+        assert_eq!(broken.unwrap().to_rgb8(), img);
+    }
+
+    #[test]
+    fn test_disabled_format() {
+        if Format::Jxl.is_enabled() {
+            eprintln!("Skipping, djxl is installed");
+            return;
+        }
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("a.jxl");
+        std::fs::write(&path, [0xff, 0x0a, 0xfa, 0x1f]).unwrap();
+        assert!(!is_supported(&path));
+        assert!(open_image(&path).is_err());
+    }
+}
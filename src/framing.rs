@@ -0,0 +1,186 @@
+use image::{DynamicImage, imageops::FilterType};
+use serde::{Deserialize, Serialize};
+
+/// A rectangle as fractions of an image's width and height.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Region {
+    pub x: f32,
+    pub y: f32,
+    pub width: f32,
+    pub height: f32,
+}
+
+/// A point as fractions of an image's width and height.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct FocusPoint {
+    pub x: f32,
+    pub y: f32,
+}
+
+/// How a wallpaper is framed when it has to be cut to a different aspect
+/// ratio. Both the crop and the focus point are relative to the full image.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct Framing {
+    /// Part of the image to use; the rest is discarded.
+    pub crop: Option<Region>,
+    /// Point kept in view when filling an output crops the image further.
+    pub focus: Option<FocusPoint>,
+}
+
+impl Framing {
+    pub fn is_empty(&self) -> bool {
+        self.crop.is_none() && self.focus.is_none()
+    }
+
+    /// The crop region in pixels of a `width`x`height` image, at least one pixel in size.
+    fn crop_px(&self, width: u32, height: u32) -> (u32, u32, u32, u32) {
+        let Some(crop) = self.crop else {
+            return (0, 0, width, height);
+        };
+
+        let x = ((crop.x.clamp(0.0, 1.0) * width as f32) as u32).min(width.saturating_sub(1));
+        let y = ((crop.y.clamp(0.0, 1.0) * height as f32) as u32).min(height.saturating_sub(1));
+        let w = ((crop.width.max(0.0) * width as f32).round() as u32).clamp(1, width - x);
+        let h = ((crop.height.max(0.0) * height as f32).round() as u32).clamp(1, height - y);
+        (x, y, w, h)
+    }
+
+    /// The image reduced to its crop region.
+    pub fn crop(&self, img: &DynamicImage) -> DynamicImage {
+        if self.crop.is_none() || img.width() == 0 || img.height() == 0 {
+            return img.clone();
+        }
+        let (x, y, w, h) = self.crop_px(img.width(), img.height());
+        img.crop_imm(x, y, w, h)
+    }
+
+    /// Crops, then scales the image to cover `width`x`height`, cutting the
+    /// overflow around the focus point, or evenly from both sides without one.
+    pub fn fill(
+        &self,
+        img: &DynamicImage,
+        width: u32,
+        height: u32,
+        filter: FilterType,
+    ) -> DynamicImage {
+        if img.width() == 0 || img.height() == 0 || width == 0 || height == 0 {
+            return img.clone();
+        }
+        let (crop_x, crop_y, source_w, source_h) = self.crop_px(img.width(), img.height());
+
+        // Size of the window that is scaled up to the output, in source pixels
+        let scale = (width as f64 / source_w as f64).max(height as f64 / source_h as f64);
+        let window_w = ((width as f64 / scale).round() as u32).clamp(1, source_w);
+        let window_h = ((height as f64 / scale).round() as u32).clamp(1, source_h);
+
+        let (focus_x, focus_y) = match self.focus {
+            Some(focus) => (
+                focus.x as f64 * img.width() as f64 - crop_x as f64,
+                focus.y as f64 * img.height() as f64 - crop_y as f64,
+            ),
+            None => (source_w as f64 / 2.0, source_h as f64 / 2.0),
+        };
+        let place = |focus: f64, window: u32, source: u32| {
+            (focus - window as f64 / 2.0)
+                .round()
+                .clamp(0.0, (source - window) as f64) as u32
+        };
+        let x = crop_x + place(focus_x, window_w, source_w);
+        let y = crop_y + place(focus_y, window_h, source_h);
+
+        let window = img.crop_imm(x, y, window_w, window_h);
+        if (window_w, window_h) == (width, height) {
+            return window;
+        }
+        window.resize_exact(width, height, filter)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::{Rgb, RgbImage};
+
+    /// 100x50 with a horizontal gradient in the red channel.
+    fn gradient() -> DynamicImage {
+        DynamicImage::ImageRgb8(RgbImage::from_fn(100, 50, |x, _| {
+            Rgb([(x * 2) as u8, 0, 0])
+        }))
+    }
+
+    fn red_at(img: &DynamicImage, x: u32) -> u8 {
+        img.to_rgb8().get_pixel(x, 0).0[0]
+    }
+
+    #[test]
+    fn test_crop() {
+        let framing = Framing {
+            crop: Some(Region {
+                x: 0.5,
+                y: 0.0,
+                width: 0.5,
+                height: 0.5,
+            }),
+            focus: None,
+        };
+        let cropped = framing.crop(&gradient());
+        assert_eq!((cropped.width(), cropped.height()), (50, 25));
+        assert_eq!(red_at(&cropped, 0), 100);
+
+        // Out of range regions are clamped to the image
+        let framing = Framing {
+            crop: Some(Region {
+                x: 0.9,
+                y: -1.0,
+                width: 2.0,
+                height: 2.0,
+            }),
+            focus: None,
+        };
+        let cropped = framing.crop(&gradient());
+        assert_eq!((cropped.width(), cropped.height()), (10, 50));
+        assert!(Framing::default().is_empty());
+    }
+
+    #[test]
+    fn test_fill_follows_focus() {
+        let img = gradient();
+
+        // Without a focus point the overflow is cut evenly
+        let centered = Framing::default().fill(&img, 50, 50, FilterType::Nearest);
+        assert_eq!((centered.width(), centered.height()), (50, 50));
+        assert_eq!(red_at(&centered, 0), 50);
+
+        let right = Framing {
+            crop: None,
+            focus: Some(FocusPoint { x: 0.9, y: 0.5 }),
+        };
+        assert_eq!(
+            red_at(&right.fill(&img, 50, 50, FilterType::Nearest), 0),
+            100
+        );
+
+        // The focus point cannot push the window outside the image
+        let left = Framing {
+            crop: None,
+            focus: Some(FocusPoint { x: 0.0, y: 0.5 }),
+        };
+        assert_eq!(red_at(&left.fill(&img, 50, 50, FilterType::Nearest), 0), 0);
+    }
+
+    #[test]
+    fn test_fill_within_crop() {
+        let framing = Framing {
+            crop: Some(Region {
+                x: 0.2,
+                y: 0.0,
+                width: 0.6,
+                height: 1.0,
+            }),
+            focus: Some(FocusPoint { x: 1.0, y: 0.5 }),
+        };
+        // The window stays inside the crop even with the focus point outside it
+        let filled = framing.fill(&gradient(), 50, 50, FilterType::Nearest);
+        assert_eq!(red_at(&filled, 0), 60);
+    }
+}
@@ -0,0 +1,291 @@
+use anyhow::{Context, Result};
+use image::DynamicImage;
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::BTreeMap,
+    fs,
+    path::{Path, PathBuf},
+    process::Command,
+};
+
+use crate::{
+    config::AppConfig, format::open_image, framing::Framing, palette::luminance,
+    thumbnail::thumbnail_path,
+};
+
+/// Whether a wallpaper is predominantly light or dark.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Tone {
+    Light,
+    Dark,
+}
+
+impl Tone {
+    /// Classifies by the median of the image's luminance histogram, so a few
+    /// bright highlights do not make an otherwise dark image light.
+    pub fn classify(img: &DynamicImage) -> Self {
+        let histogram = luminance_histogram(img);
+        let total: u32 = histogram.iter().sum();
+
+        let mut seen = 0;
+        let median = histogram
+            .iter()
+            .position(|&count| {
+                seen += count;
+                seen * 2 >= total
+            })
+            .unwrap_or(0);
+
+        if median < 128 {
+            Tone::Dark
+        } else {
+            Tone::Light
+        }
+    }
+
+    pub fn from_prefer_dark(prefer_dark: bool) -> Self {
+        if prefer_dark { Tone::Dark } else { Tone::Light }
+    }
+}
+
+impl std::fmt::Display for Tone {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Tone::Light => write!(f, "light"),
+            Tone::Dark => write!(f, "dark"),
+        }
+    }
+}
+
+/// Number of pixels at each luminance level, over a downsampled copy of the image.
+fn luminance_histogram(img: &DynamicImage) -> [u32; 256] {
+    let mut histogram = [0u32; 256];
+    for pixel in img.thumbnail(128, 128).to_rgb8().pixels() {
+        let level = (luminance(pixel.0) * 255.0).round() as usize;
+        histogram[level.min(255)] += 1;
+    }
+    histogram
+}
+
+/// Derived and user-set information about a wallpaper.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct WallpaperMeta {
+    pub tone: Option<Tone>,
+    #[serde(default, skip_serializing_if = "Framing::is_empty")]
+    pub framing: Framing,
+}
+
+/// Metadata of the wallpaper library, keyed by wallpaper file name.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Library {
+    #[serde(default)]
+    pub wallpapers: BTreeMap<String, WallpaperMeta>,
+}
+
+impl Library {
+    /// Loads the library from `path`, returning an empty library if the file does not exist.
+    pub fn load(path: &Path) -> Result<Self> {
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+        let content = fs::read_to_string(path)
+            .with_context(|| format!("Failed to read library: {}", path.display()))?;
+        toml::from_str(&content)
+            .with_context(|| format!("Failed to parse library: {}", path.display()))
+    }
+
+    pub fn save(&self, path: &Path) -> Result<()> {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)
+                .with_context(|| format!("Failed to create directory: {}", parent.display()))?;
+        }
+        fs::write(path, toml::to_string_pretty(self)?)
+            .with_context(|| format!("Failed to write library: {}", path.display()))
+    }
+
+    pub fn get(&self, name: &str) -> Option<&WallpaperMeta> {
+        self.wallpapers.get(name)
+    }
+
+    pub fn tone(&self, name: &str) -> Option<Tone> {
+        self.get(name).and_then(|meta| meta.tone)
+    }
+
+    pub fn framing(&self, name: &str) -> Framing {
+        self.get(name).map(|meta| meta.framing).unwrap_or_default()
+    }
+
+    pub fn set_framing(&mut self, name: &str, framing: Framing) {
+        self.wallpapers.entry(name.to_string()).or_default().framing = framing;
+    }
+
+    /// Moves the metadata of a renamed wallpaper to its new name.
+    pub fn rename(&mut self, from: &str, to: &str) {
+        if let Some(meta) = self.wallpapers.remove(from) {
+            self.wallpapers.insert(to.to_string(), meta);
+        }
+    }
+
+    pub fn remove(&mut self, name: &str) {
+        self.wallpapers.remove(name);
+    }
+
+    /// Classifies wallpapers that have no tone yet, preferring their cached
+    /// thumbnail in `thumbnail_dir` over decoding the full image. Returns
+    /// whether anything changed.
+    pub fn classify_missing(&mut self, paths: &[PathBuf], thumbnail_dir: Option<&Path>) -> bool {
+        let mut changed = false;
+
+        for path in paths {
+            let Some(name) = file_name(path) else {
+                continue;
+            };
+            if self.tone(&name).is_some() {
+                continue;
+            }
+
+            let thumbnail = thumbnail_dir.map(|dir| thumbnail_path(dir, path));
+            let img = thumbnail
+                .and_then(|thumbnail| image::open(thumbnail).ok())
+                .map(Ok)
+                .unwrap_or_else(|| open_image(path));
+
+            match img {
+                Ok(img) => {
+                    self.wallpapers.entry(name).or_default().tone = Some(Tone::classify(&img));
+                    changed = true;
+                }
+                Err(e) => eprintln!("Failed to classify {}: {}", path.display(), e),
+            }
+        }
+
+        changed
+    }
+
+    /// Keeps the wallpapers classified as `tone`.
+    pub fn filter_tone(&self, paths: Vec<PathBuf>, tone: Tone) -> Vec<PathBuf> {
+        paths
+            .into_iter()
+            .filter(|path| file_name(path).is_some_and(|name| self.tone(&name) == Some(tone)))
+            .collect()
+    }
+}
+
+fn file_name(path: &Path) -> Option<String> {
+    path.file_name().map(|n| n.to_string_lossy().to_string())
+}
+
+/// Loads the library and classifies any of `paths` it does not know yet,
+/// saving it when new wallpapers were classified.
+pub fn load_classified(config: &AppConfig, paths: &[PathBuf]) -> Library {
+    let Some(ref library_path) = config.library_path else {
+        eprintln!("Library path not configured");
+        return Library::default();
+    };
+    let mut library = Library::load(library_path).unwrap_or_else(|e| {
+        eprintln!("Failed to load library: {:#}", e);
+        Library::default()
+    });
+
+    if library.classify_missing(paths, config.cache_path.as_deref())
+        && let Err(e) = library.save(library_path)
+    {
+        eprintln!("Failed to save library: {:#}", e);
+    }
+    library
+}
+
+/// The desktop's preferred color scheme, as reported by the XDG
+/// `color-scheme` setting.
+pub fn system_tone() -> Option<Tone> {
+    let output = Command::new("gsettings")
+        .args(["get", "org.gnome.desktop.interface", "color-scheme"])
+        .output()
+        .ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    parse_color_scheme(&String::from_utf8_lossy(&output.stdout))
+}
+
+fn parse_color_scheme(value: &str) -> Option<Tone> {
+    match value.trim().trim_matches('\'') {
+        "prefer-dark" => Some(Tone::Dark),
+        "prefer-light" | "default" => Some(Tone::Light),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::framing::FocusPoint;
+    use image::{Rgb, RgbImage};
+    use tempfile::tempdir;
+
+    fn image_with(dark_columns: u32) -> DynamicImage {
+        DynamicImage::ImageRgb8(RgbImage::from_fn(100, 10, |x, _| {
+            if x < dark_columns {
+                Rgb([15, 15, 30])
+            } else {
+                Rgb([240, 235, 220])
+            }
+        }))
+    }
+
+    #[test]
+    fn test_classify() {
+        assert_eq!(Tone::classify(&image_with(100)), Tone::Dark);
+        assert_eq!(Tone::classify(&image_with(70)), Tone::Dark);
+        assert_eq!(Tone::classify(&image_with(30)), Tone::Light);
+        assert_eq!(Tone::classify(&image_with(0)), Tone::Light);
+    }
+
+    #[test]
+    fn test_parse_color_scheme() {
+        assert_eq!(parse_color_scheme("'prefer-dark'\n"), Some(Tone::Dark));
+        assert_eq!(parse_color_scheme("'default'\n"), Some(Tone::Light));
+        assert_eq!(parse_color_scheme(""), None);
+    }
+
+    #[test]
+    fn test_classify_missing_and_filter() {
+        let dir = tempdir().unwrap();
+        let dark = dir.path().join("night.png");
+        let light = dir.path().join("day.png");
+        image_with(100).save(&dark).unwrap();
+        image_with(0).save(&light).unwrap();
+        let paths = vec![dark.clone(), light.clone(), dir.path().join("missing.png")];
+
+        let mut library = Library::default();
+        assert!(library.classify_missing(&paths, None));
+        assert_eq!(library.tone("night.png"), Some(Tone::Dark));
+        assert_eq!(library.tone("day.png"), Some(Tone::Light));
+        assert_eq!(library.tone("missing.png"), None);
+
+        // Known wallpapers are not classified again
+        assert!(!library.classify_missing(&paths[..2], None));
+
+        assert_eq!(library.filter_tone(paths.clone(), Tone::Dark), vec![dark]);
+        assert_eq!(library.filter_tone(paths, Tone::Light), vec![light]);
+
+        library.set_framing(
+            "day.png",
+            Framing {
+                crop: None,
+                focus: Some(FocusPoint { x: 0.25, y: 0.5 }),
+            },
+        );
+
+        let library_path = dir.path().join("library.toml");
+        library.save(&library_path).unwrap();
+        assert_eq!(Library::load(&library_path).unwrap(), library);
+        assert_eq!(library.framing("night.png"), Framing::default());
+        assert!(
+            !fs::read_to_string(&library_path)
+                .unwrap()
+                .contains("night.png\".framing")
+        );
+    }
+}
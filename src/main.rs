@@ -1,11 +1,32 @@
+use clap::Parser;
 use iced::{Size, window::Settings};
 
-use crate::ui::AppView;
+use crate::{cli::Cli, config::CliOverrides, ui::AppView};
 
+mod animation;
+mod apply;
+mod cli;
 mod config;
 mod constants;
+mod daemon;
+mod derivative;
 mod events;
+mod files;
+mod format;
+mod framing;
+mod library;
 mod message;
+mod metadata;
+mod outputs;
+mod palette;
+mod prescale;
+mod rotation;
+mod schedule;
+mod span;
+#[cfg(feature = "svg")]
+mod svg;
+mod tags;
+mod theme;
 mod thumbnail;
 mod ui;
 mod util;
@@ -13,9 +34,25 @@ mod wallpaper;
 mod wallpaper_image;
 
 fn main() -> iced::Result {
-    iced::application(AppView::new, AppView::update, AppView::view)
+    let cli = Cli::parse();
+    config::use_cli_overrides(CliOverrides {
+        file: cli.config,
+        set: cli.set,
+        profile: cli.profile,
+    });
+    if let Some(command) = cli.command {
+        if let Err(e) = cli::run(command) {
+            eprintln!("Error: {:#}", e);
+            std::process::exit(1);
+        }
+        return Ok(());
+    }
+
+    iced::application(AppView::boot, AppView::update, AppView::view)
         .title("Wallity - Wallpaper Manager")
         .subscription(AppView::subscription)
+        .theme(AppView::theme)
+        .style(AppView::style)
         .centered()
         .window(Settings {
             resizable: false,
@@ -8,9 +8,41 @@ pub enum Message {
     WallpaperDiscovered(crate::wallpaper_image::WallpaperImage),
     ScrolledTo(scrollable::Viewport),
     LoadVisibleThumbnails,
-    ThumbnailLoaded(usize, iced_image::Handle),
+    ThumbnailLoaded(std::path::PathBuf, iced_image::Handle),
     ImageHovered(Option<usize>),
     KeyPressed(Key),
     WallpaperSelected,
+    ApplyTo(crate::apply::ApplyTarget),
     ScrollToVisible,
+    SearchChanged(String),
+    TagEditorChanged(String),
+    TagFilterToggled(String),
+    ColorFilterToggled(crate::palette::Swatch),
+    SortByHueToggled,
+    CropCursorMoved(iced::Point),
+    CropPressed,
+    CropReleased,
+    CropImageLoaded(std::path::PathBuf, iced_image::Handle),
+    ThumbnailRegenerated(
+        std::path::PathBuf,
+        crate::palette::ColorInfo,
+        iced_image::Handle,
+    ),
+    PreviewLoaded(
+        std::path::PathBuf,
+        Vec<(iced_image::Handle, std::time::Duration)>,
+    ),
+    PreviewFrame(std::time::Instant),
+    ConfigBannerDismissed,
+    ConfigReloaded(
+        std::sync::Arc<crate::config::AppConfig>,
+        crate::config::ConfigReport,
+    ),
+    AccentLoaded([u8; 3]),
+    DetailsLoaded(Box<crate::metadata::Metadata>),
+    FileMenuOpened(usize),
+    ImagePressed(usize),
+    ModifiersChanged(iced::keyboard::Modifiers),
+    FileAction(crate::files::FileAction),
+    FileInputChanged(String),
 }
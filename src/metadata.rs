@@ -0,0 +1,229 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use chrono::{DateTime, Local};
+use exif::{Exif, In, Tag, Value};
+use moxcms::{ColorProfile, ProfileText};
+
+use crate::{
+    animation::AnimationInfo,
+    format::{Format, embedded_metadata, image_dimensions},
+    thumbnail::stored_info,
+};
+
+/// File and image details of a wallpaper, as shown in the details panel.
+/// Anything that can't be read is left out rather than failing the rest.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Metadata {
+    pub path: PathBuf,
+    pub format: Option<Format>,
+    pub dimensions: Option<(u32, u32)>,
+    pub file_size: Option<u64>,
+    pub modified: Option<DateTime<Local>>,
+    /// EXIF make and model of the camera.
+    pub camera: Option<String>,
+    /// EXIF time the photo was taken.
+    pub taken: Option<String>,
+    /// Description of the embedded ICC color profile.
+    pub color_profile: Option<String>,
+    /// Frame count and running time, for animated wallpapers, once their
+    /// thumbnail is generated.
+    pub animation: Option<AnimationInfo>,
+    pub thumbnail_path: PathBuf,
+    /// Size of the cached thumbnail, `None` until it's generated.
+    pub thumbnail_size: Option<u64>,
+}
+
+impl Metadata {
+    /// Reads the details of `img_path` from its file system entry and header,
+    /// without decoding the image. The animation is taken from the info
+    /// stored with the thumbnail, since counting frames means decoding them.
+    pub fn read(img_path: &Path, thumbnail_path: &Path) -> Self {
+        let file = fs::metadata(img_path).ok();
+        let embedded = embedded_metadata(img_path).unwrap_or_default();
+        let exif = embedded
+            .exif
+            .and_then(|exif| exif::Reader::new().read_raw(exif).ok());
+
+        Self {
+            path: img_path.to_path_buf(),
+            format: Format::of_file(img_path),
+            dimensions: image_dimensions(img_path).ok(),
+            file_size: file.as_ref().map(fs::Metadata::len),
+            modified: file
+                .and_then(|file| file.modified().ok())
+                .map(DateTime::from),
+            camera: exif.as_ref().and_then(camera),
+            taken: exif.as_ref().and_then(taken),
+            color_profile: embedded
+                .icc_profile
+                .and_then(|profile| profile_description(&profile)),
+            animation: stored_info(thumbnail_path).and_then(|info| info.animation),
+            thumbnail_path: thumbnail_path.to_path_buf(),
+            thumbnail_size: fs::metadata(thumbnail_path).map(|file| file.len()).ok(),
+        }
+    }
+}
+
+/// Formats a byte count with binary units, e.g. `4.2 MiB`.
+pub fn format_size(bytes: u64) -> String {
+    const UNITS: [&str; 4] = ["KiB", "MiB", "GiB", "TiB"];
+    if bytes < 1024 {
+        return format!("{} B", bytes);
+    }
+    let mut size = bytes as f64 / 1024.0;
+    let mut unit = 0;
+    while size >= 1024.0 && unit < UNITS.len() - 1 {
+        size /= 1024.0;
+        unit += 1;
+    }
+    format!("{:.1} {}", size, UNITS[unit])
+}
+
+fn ascii_field(exif: &Exif, tag: Tag) -> Option<String> {
+    let Value::Ascii(ref values) = exif.get_field(tag, In::PRIMARY)?.value else {
+        return None;
+    };
+    let text = String::from_utf8_lossy(values.first()?);
+    let text = text.trim_matches(|c: char| c == '\0' || c.is_whitespace());
+    (!text.is_empty()).then(|| text.to_string())
+}
+
+/// Make and model, without the make repeated when the model starts with it,
+/// as in `Canon` and `Canon EOS R5`.
+fn camera(exif: &Exif) -> Option<String> {
+    match (ascii_field(exif, Tag::Make), ascii_field(exif, Tag::Model)) {
+        (Some(make), Some(model)) if model.to_lowercase().starts_with(&make.to_lowercase()) => {
+            Some(model)
+        }
+        (Some(make), Some(model)) => Some(format!("{} {}", make, model)),
+        (make, model) => make.or(model),
+    }
+}
+
+fn taken(exif: &Exif) -> Option<String> {
+    let field = exif
+        .get_field(Tag::DateTimeOriginal, In::PRIMARY)
+        .or_else(|| exif.get_field(Tag::DateTime, In::PRIMARY))?;
+    let Value::Ascii(ref values) = field.value else {
+        return None;
+    };
+    exif::DateTime::from_ascii(values.first()?)
+        .ok()
+        .map(|time| time.to_string())
+}
+
+fn profile_description(icc_profile: &[u8]) -> Option<String> {
+    let profile = ColorProfile::new_from_slice(icc_profile).ok()?;
+    let text = match profile.description? {
+        ProfileText::PlainString(text) => text,
+        ProfileText::Localizable(texts) => texts
+            .iter()
+            .find(|text| text.language == "en")
+            .or(texts.first())?
+            .value
+            .clone(),
+        ProfileText::Description(description) => description.ascii_string,
+    };
+    let text = text.trim_matches(|c: char| c == '\0' || c.is_whitespace());
+    (!text.is_empty()).then(|| text.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{framing::Framing, thumbnail::gen_thumbnail};
+    use exif::{Field, experimental::Writer};
+    use image::{ExtendedColorType, ImageEncoder, codecs::jpeg::JpegEncoder};
+    use std::{fs::File, io::Cursor};
+    use tempfile::tempdir;
+
+    fn ascii(tag: Tag, text: &str) -> Field {
+        Field {
+            tag,
+            ifd_num: In::PRIMARY,
+            value: Value::Ascii(vec![text.as_bytes().to_vec()]),
+        }
+    }
+
+    #[test]
+    fn test_read_metadata() {
+        let dir = tempdir().unwrap();
+        let fields = [
+            ascii(Tag::Make, "Canon"),
+            ascii(Tag::Model, "Canon EOS R5"),
+            ascii(Tag::DateTimeOriginal, "2024:06:01 18:30:00"),
+        ];
+        let mut writer = Writer::new();
+        for field in &fields {
+            writer.push_field(field);
+        }
+        let mut exif = Cursor::new(Vec::new());
+        writer.write(&mut exif, false).unwrap();
+
+        let path = dir.path().join("photo.jpg");
+        let mut encoder = JpegEncoder::new(File::create(&path).unwrap());
+        encoder.set_exif_metadata(exif.into_inner()).unwrap();
+        encoder
+            .write_image(&[128; 8 * 4 * 3], 8, 4, ExtendedColorType::Rgb8)
+            .unwrap();
+
+        let thumbnail_path = dir.path().join("thumbnail.png");
+        let metadata = Metadata::read(&path, &thumbnail_path);
+        assert_eq!(metadata.format, Some(Format::Jpeg));
+        assert_eq!(metadata.dimensions, Some((8, 4)));
+        assert_eq!(metadata.file_size, Some(fs::metadata(&path).unwrap().len()));
+        assert!(metadata.modified.is_some());
+        assert_eq!(metadata.camera.as_deref(), Some("Canon EOS R5"));
+        assert_eq!(metadata.taken.as_deref(), Some("2024-06-01 18:30:00"));
+        assert_eq!(metadata.color_profile, None);
+        assert_eq!(metadata.animation, None);
+        assert_eq!(metadata.thumbnail_size, None);
+    }
+
+    #[test]
+    fn test_read_animation() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("loop.gif");
+        let frames = (0..2).map(|shade| {
+            image::Frame::from_parts(
+                image::RgbaImage::from_pixel(4, 4, image::Rgba([shade, 0, 0, 255])),
+                0,
+                0,
+                image::Delay::from_numer_denom_ms(500, 1),
+            )
+        });
+        image::codecs::gif::GifEncoder::new(File::create(&path).unwrap())
+            .encode_frames(frames)
+            .unwrap();
+
+        // Nothing is decoded before the thumbnail is generated
+        let thumbnail_path = dir.path().join("t.jpeg");
+        assert_eq!(Metadata::read(&path, &thumbnail_path).animation, None);
+
+        gen_thumbnail(&path, &thumbnail_path, &Framing::default()).unwrap();
+        let metadata = Metadata::read(&path, &thumbnail_path);
+        assert_eq!(
+            metadata.animation.map(|info| info.to_string()).as_deref(),
+            Some("2 frames, 1.0s")
+        );
+    }
+
+    #[test]
+    fn test_read_missing_file() {
+        let dir = tempdir().unwrap();
+        let metadata = Metadata::read(&dir.path().join("gone.png"), &dir.path().join("t.png"));
+        assert_eq!(metadata.file_size, None);
+        assert_eq!(metadata.dimensions, None);
+        assert_eq!(metadata.camera, None);
+    }
+
+    #[test]
+    fn test_format_size() {
+        assert_eq!(format_size(512), "512 B");
+        assert_eq!(format_size(1536), "1.5 KiB");
+        assert_eq!(format_size(4 * 1024 * 1024 + 200 * 1024), "4.2 MiB");
+    }
+}
@@ -0,0 +1,202 @@
+use std::{
+    path::{Path, PathBuf},
+    process::Command,
+};
+
+use anyhow::{Context, Result, bail};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use crate::config::AppConfig;
+
+/// A monitor from the `[[outputs]]` config section. The position and size
+/// are only needed for spanning a wallpaper across outputs.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct OutputConfig {
+    /// Output name as the compositor knows it, e.g. `DP-1`.
+    pub name: String,
+    pub x: Option<i32>,
+    pub y: Option<i32>,
+    pub width: Option<u32>,
+    pub height: Option<u32>,
+}
+
+/// Position and size of an output in the desktop layout, in pixels.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct OutputGeometry {
+    pub x: i32,
+    pub y: i32,
+    pub width: u32,
+    pub height: u32,
+}
+
+impl OutputConfig {
+    pub fn named(name: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            x: None,
+            y: None,
+            width: None,
+            height: None,
+        }
+    }
+
+    pub fn geometry(&self) -> Option<OutputGeometry> {
+        Some(OutputGeometry {
+            x: self.x?,
+            y: self.y?,
+            width: self.width.filter(|w| *w > 0)?,
+            height: self.height.filter(|h| *h > 0)?,
+        })
+    }
+}
+
+/// Outputs wallpapers are applied to: the configured outputs, or the detected
+/// ones when `detect_outputs` is enabled. Empty means a single, unnamed output.
+pub fn resolve_outputs(config: &AppConfig) -> Vec<OutputConfig> {
+    if !config.outputs.is_empty() {
+        return config.outputs.clone();
+    }
+    if config.detect_outputs == Some(true) {
+        return detect_outputs().unwrap_or_else(|e| {
+            eprintln!("Failed to detect outputs: {:#}", e);
+            Vec::new()
+        });
+    }
+    Vec::new()
+}
+
+/// Queries the compositor or wallpaper daemon for connected outputs.
+pub fn detect_outputs() -> Result<Vec<OutputConfig>> {
+    if let Ok(json) = run_query("hyprctl", &["monitors", "-j"]) {
+        return parse_hyprctl_monitors(&json);
+    }
+    if let Ok(text) = run_query("swww", &["query"]) {
+        return Ok(parse_swww_query(&text));
+    }
+    bail!("Neither `hyprctl monitors` nor `swww query` is available")
+}
+
+fn run_query(program: &str, args: &[&str]) -> Result<String> {
+    let output = Command::new(program)
+        .args(args)
+        .output()
+        .with_context(|| format!("Failed to run {}", program))?;
+    if !output.status.success() {
+        bail!("{} exited with status {}", program, output.status);
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+fn parse_hyprctl_monitors(json: &str) -> Result<Vec<OutputConfig>> {
+    #[derive(Deserialize)]
+    struct Monitor {
+        name: String,
+        x: Option<i32>,
+        y: Option<i32>,
+        width: Option<u32>,
+        height: Option<u32>,
+    }
+
+    let monitors: Vec<Monitor> =
+        serde_json::from_str(json).context("Invalid `hyprctl monitors -j` output")?;
+    Ok(monitors
+        .into_iter()
+        .map(|m| OutputConfig {
+            name: m.name,
+            x: m.x,
+            y: m.y,
+            width: m.width,
+            height: m.height,
+        })
+        .collect())
+}
+
+/// Parses lines like `DP-1: 2560x1440, scale: 1, currently displaying: ...`,
+/// with or without a leading `: ` as printed by some swww versions.
+fn parse_swww_query(text: &str) -> Vec<OutputConfig> {
+    text.lines()
+        .filter_map(|line| {
+            let line = line.trim().trim_start_matches(':').trim_start();
+            let (name, rest) = line.split_once(':')?;
+            if name.is_empty() {
+                return None;
+            }
+
+            let mut output = OutputConfig::named(name);
+            let size = rest.split(',').next().unwrap_or("").trim();
+            if let Some((width, height)) = size.split_once('x') {
+                output.width = width.parse().ok();
+                output.height = height.parse().ok();
+            }
+            Some(output)
+        })
+        .collect()
+}
+
+/// Per-output symlink next to `current_wallpaper`, e.g. `.current_wallpaper-DP-1`.
+pub fn output_link(current_wallpaper: &Path, output: &str) -> PathBuf {
+    let mut name = current_wallpaper
+        .file_name()
+        .map(|n| n.to_os_string())
+        .unwrap_or_default();
+    name.push(format!("-{}", output));
+    current_wallpaper.with_file_name(name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_hyprctl_monitors() {
+        let json = r#"[
+            {"id": 0, "name": "eDP-1", "width": 1920, "height": 1080, "x": 0, "y": 0},
+            {"id": 1, "name": "DP-2", "width": 2560, "height": 1440, "x": 1920, "y": 0}
+        ]"#;
+        let outputs = parse_hyprctl_monitors(json).unwrap();
+        assert_eq!(outputs.len(), 2);
+        assert_eq!(outputs[0].name, "eDP-1");
+        assert_eq!(
+            outputs[1].geometry(),
+            Some(OutputGeometry {
+                x: 1920,
+                y: 0,
+                width: 2560,
+                height: 1440
+            })
+        );
+        assert!(parse_hyprctl_monitors("not json").is_err());
+    }
+
+    #[test]
+    fn test_parse_swww_query() {
+        let text = "DP-1: 2560x1440, scale: 1, currently displaying: image: /a.png\n\
+                    : HDMI-A-1: 1920x1080, scale: 1, currently displaying: color: 000000\n\n";
+        let outputs = parse_swww_query(text);
+        let names: Vec<_> = outputs.iter().map(|o| o.name.as_str()).collect();
+        assert_eq!(names, vec!["DP-1", "HDMI-A-1"]);
+        assert_eq!(outputs[0].width, Some(2560));
+        assert_eq!(outputs[1].height, Some(1080));
+        // swww does not report positions
+        assert_eq!(outputs[0].geometry(), None);
+    }
+
+    #[test]
+    fn test_output_link() {
+        let current = Path::new("/home/u/.config/wallity/.current_wallpaper");
+        assert_eq!(
+            output_link(current, "DP-1"),
+            PathBuf::from("/home/u/.config/wallity/.current_wallpaper-DP-1")
+        );
+    }
+
+    #[test]
+    fn test_resolve_configured_outputs() {
+        let mut config = AppConfig::empty();
+        assert!(resolve_outputs(&config).is_empty());
+
+        config.outputs = vec![OutputConfig::named("DP-1"), OutputConfig::named("HDMI-A-1")];
+        assert_eq!(resolve_outputs(&config), config.outputs);
+    }
+}
@@ -0,0 +1,554 @@
+use std::{
+    collections::hash_map::DefaultHasher,
+    fs,
+    hash::{Hash, Hasher},
+    path::{Path, PathBuf},
+};
+
+use anyhow::{Context, Result};
+use image::DynamicImage;
+use serde::{Deserialize, Serialize};
+
+use crate::{format::open_image, util::is_newer};
+
+/// A palette as stored in the cache, with the number of colors it was
+/// extracted for. It only has fewer when the image has fewer distinct colors.
+#[derive(Serialize, Deserialize)]
+struct CachedPalette {
+    count: usize,
+    #[serde(flatten)]
+    palette: Palette,
+}
+
+/// Side length the image is downsampled to before quantizing.
+const SAMPLE_SIZE: u32 = 64;
+
+/// Colors below this HSL saturation count as neutral and have no meaningful hue.
+const NEUTRAL_SATURATION: f32 = 0.15;
+
+/// Dominant colors of an image, most common first.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Palette {
+    pub colors: Vec<[u8; 3]>,
+}
+
+impl Palette {
+    /// Extracts up to `count` colors from the image.
+    pub fn extract(img_path: &Path, count: usize) -> Result<Self> {
+        let img = open_image(img_path)?;
+        let sample = img.thumbnail(SAMPLE_SIZE, SAMPLE_SIZE).to_rgb8();
+        let pixels = sample.pixels().map(|p| p.0).collect();
+        Ok(Self {
+            colors: quantize(pixels, count),
+        })
+    }
+
+    /// Loads the palette from `cache_dir`, extracting and caching it when the
+    /// cache is missing or older than the image.
+    pub fn cached(img_path: &Path, count: usize, cache_dir: &Path) -> Result<Self> {
+        let cache_file = cache_file(img_path, count, cache_dir);
+
+        if is_newer(&cache_file, img_path)
+            && let Ok(content) = fs::read_to_string(&cache_file)
+            && let Ok(cached) = serde_json::from_str::<CachedPalette>(&content)
+            && cached.count == count
+            && cached.palette.colors.len() <= count
+        {
+            return Ok(cached.palette);
+        }
+
+        let palette = Self::extract(img_path, count)?;
+        fs::create_dir_all(cache_dir)
+            .with_context(|| format!("Failed to create directory: {}", cache_dir.display()))?;
+        let cached = CachedPalette { count, palette };
+        fs::write(&cache_file, serde_json::to_string(&cached)?)
+            .with_context(|| format!("Failed to write {}", cache_file.display()))?;
+        Ok(cached.palette)
+    }
+
+    pub fn background(&self) -> [u8; 3] {
+        self.colors
+            .iter()
+            .copied()
+            .min_by(|a, b| luminance(*a).total_cmp(&luminance(*b)))
+            .unwrap_or([0, 0, 0])
+    }
+
+    pub fn foreground(&self) -> [u8; 3] {
+        self.colors
+            .iter()
+            .copied()
+            .max_by(|a, b| luminance(*a).total_cmp(&luminance(*b)))
+            .unwrap_or([255, 255, 255])
+    }
+
+    /// Named colors written to every template: background, foreground, color0..N.
+    fn entries(&self) -> Vec<(String, [u8; 3])> {
+        let mut entries = vec![
+            (String::from("background"), self.background()),
+            (String::from("foreground"), self.foreground()),
+        ];
+        entries.extend(
+            self.colors
+                .iter()
+                .enumerate()
+                .map(|(idx, color)| (format!("color{}", idx), *color)),
+        );
+        entries
+    }
+
+    /// Writes `colors.json`, `colors.css`, `colors.sh` and `colors-hyprland.conf`
+    /// into `dir`, returning the written files.
+    pub fn export(&self, wallpaper: &Path, dir: &Path) -> Result<Vec<PathBuf>> {
+        fs::create_dir_all(dir)
+            .with_context(|| format!("Failed to create directory: {}", dir.display()))?;
+
+        let entries = self.entries();
+        let wallpaper = wallpaper.to_string_lossy();
+
+        let json = serde_json::json!({
+            "wallpaper": wallpaper,
+            "colors": entries
+                .iter()
+                .map(|(name, color)| (name.clone(), hex(*color).into()))
+                .collect::<serde_json::Map<String, serde_json::Value>>(),
+        });
+
+        let mut css = String::from(":root {\n");
+        let mut sh = format!("wallpaper='{}'\n", wallpaper.replace('\'', "'\\''"));
+        let mut hyprland = String::new();
+        for (name, color) in &entries {
+            css.push_str(&format!("  --{}: {};\n", name, hex(*color)));
+            sh.push_str(&format!("{}='{}'\n", name, hex(*color)));
+            hyprland.push_str(&format!("${} = rgb({})\n", name, &hex(*color)[1..]));
+        }
+        css.push_str("}\n");
+
+        let files = [
+            ("colors.json", serde_json::to_string_pretty(&json)? + "\n"),
+            ("colors.css", css),
+            ("colors.sh", sh),
+            ("colors-hyprland.conf", hyprland),
+        ];
+        files
+            .into_iter()
+            .map(|(name, content)| {
+                let path = dir.join(name);
+                fs::write(&path, content)
+                    .with_context(|| format!("Failed to write {}", path.display()))?;
+                Ok(path)
+            })
+            .collect()
+    }
+}
+
+/// Dominant hue and overall brightness of an image, used to filter and sort
+/// the library by color.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct ColorInfo {
+    /// Hue of the most common color, in degrees.
+    pub hue: f32,
+    /// HSL saturation of the most common color.
+    pub saturation: f32,
+    /// Average relative luminance of the whole image.
+    pub luminance: f32,
+}
+
+impl ColorInfo {
+    pub fn of(img: &DynamicImage) -> Self {
+        let sample = img.thumbnail(SAMPLE_SIZE, SAMPLE_SIZE).to_rgb8();
+        let pixels: Vec<[u8; 3]> = sample.pixels().map(|p| p.0).collect();
+
+        let luminance =
+            pixels.iter().map(|p| luminance(*p)).sum::<f32>() / pixels.len().max(1) as f32;
+        let dominant = quantize(pixels, 5).first().copied().unwrap_or([0, 0, 0]);
+        let (hue, saturation) = hue_saturation(dominant);
+
+        Self {
+            hue,
+            saturation,
+            luminance,
+        }
+    }
+
+    pub fn is_neutral(&self) -> bool {
+        self.saturation < NEUTRAL_SATURATION
+    }
+
+    /// Sort key placing colorful images in hue order, followed by neutral
+    /// ones from dark to light.
+    pub fn hue_order(&self) -> f32 {
+        if self.is_neutral() {
+            360.0 + self.luminance
+        } else {
+            self.hue
+        }
+    }
+}
+
+/// Color buckets offered in the filter row.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Swatch {
+    Red,
+    Orange,
+    Yellow,
+    Green,
+    Cyan,
+    Blue,
+    Purple,
+    Pink,
+    Dark,
+    Light,
+}
+
+impl Swatch {
+    pub const ALL: [Swatch; 10] = [
+        Swatch::Red,
+        Swatch::Orange,
+        Swatch::Yellow,
+        Swatch::Green,
+        Swatch::Cyan,
+        Swatch::Blue,
+        Swatch::Purple,
+        Swatch::Pink,
+        Swatch::Dark,
+        Swatch::Light,
+    ];
+
+    /// Color the swatch is drawn with.
+    pub fn color(&self) -> [u8; 3] {
+        match self {
+            Swatch::Red => [220, 50, 47],
+            Swatch::Orange => [240, 140, 30],
+            Swatch::Yellow => [240, 210, 50],
+            Swatch::Green => [90, 180, 70],
+            Swatch::Cyan => [40, 190, 200],
+            Swatch::Blue => [50, 100, 220],
+            Swatch::Purple => [140, 80, 200],
+            Swatch::Pink => [230, 100, 170],
+            Swatch::Dark => [20, 20, 20],
+            Swatch::Light => [235, 235, 235],
+        }
+    }
+
+    pub fn matches(&self, info: &ColorInfo) -> bool {
+        let hue_range =
+            |from: f32, to: f32| !info.is_neutral() && info.hue >= from && info.hue < to;
+        match self {
+            Swatch::Red => hue_range(345.0, 360.0) || hue_range(0.0, 15.0),
+            Swatch::Orange => hue_range(15.0, 45.0),
+            Swatch::Yellow => hue_range(45.0, 70.0),
+            Swatch::Green => hue_range(70.0, 165.0),
+            Swatch::Cyan => hue_range(165.0, 200.0),
+            Swatch::Blue => hue_range(200.0, 260.0),
+            Swatch::Purple => hue_range(260.0, 300.0),
+            Swatch::Pink => hue_range(300.0, 345.0),
+            Swatch::Dark => info.luminance < 0.3,
+            Swatch::Light => info.luminance > 0.7,
+        }
+    }
+}
+
+/// HSL hue in degrees and saturation in `0.0..=1.0`.
+pub fn hue_saturation(color: [u8; 3]) -> (f32, f32) {
+    let [r, g, b] = color.map(|c| c as f32 / 255.0);
+    let max = r.max(g).max(b);
+    let min = r.min(g).min(b);
+    let delta = max - min;
+    if delta == 0.0 {
+        return (0.0, 0.0);
+    }
+
+    let lightness = (max + min) / 2.0;
+    let saturation = delta / (1.0 - (2.0 * lightness - 1.0).abs());
+    let hue = if max == r {
+        60.0 * ((g - b) / delta).rem_euclid(6.0)
+    } else if max == g {
+        60.0 * ((b - r) / delta + 2.0)
+    } else {
+        60.0 * ((r - g) / delta + 4.0)
+    };
+
+    (hue, saturation.min(1.0))
+}
+
+pub fn hex(color: [u8; 3]) -> String {
+    format!("#{:02x}{:02x}{:02x}", color[0], color[1], color[2])
+}
+
+/// Relative luminance in `0.0..=1.0`.
+pub fn luminance(color: [u8; 3]) -> f32 {
+    (0.2126 * color[0] as f32 + 0.7152 * color[1] as f32 + 0.0722 * color[2] as f32) / 255.0
+}
+
+/// Number of k-means passes refining the median cut colors.
+const REFINE_ITERATIONS: usize = 8;
+
+/// Quantizes the pixels to at most `count` colors, most common first. Median
+/// cut picks the initial colors, which a few k-means passes then refine so
+/// unevenly sized color clusters are not split down the middle.
+fn quantize(pixels: Vec<[u8; 3]>, count: usize) -> Vec<[u8; 3]> {
+    let mut centroids = median_cut(pixels.clone(), count);
+    let mut populations = vec![0usize; centroids.len()];
+
+    for _ in 0..REFINE_ITERATIONS {
+        let mut sums = vec![[0u64; 3]; centroids.len()];
+        populations = vec![0usize; centroids.len()];
+
+        for p in &pixels {
+            let Some(nearest) =
+                (0..centroids.len()).min_by_key(|&idx| distance(*p, centroids[idx]))
+            else {
+                break;
+            };
+            populations[nearest] += 1;
+            for channel in 0..3 {
+                sums[nearest][channel] += p[channel] as u64;
+            }
+        }
+
+        let refined: Vec<[u8; 3]> = sums
+            .iter()
+            .zip(&populations)
+            .zip(&centroids)
+            .map(|((sum, &population), &centroid)| match population {
+                0 => centroid,
+                n => sum.map(|channel| (channel / n as u64) as u8),
+            })
+            .collect();
+        if refined == centroids {
+            break;
+        }
+        centroids = refined;
+    }
+
+    let mut colors: Vec<([u8; 3], usize)> = centroids
+        .into_iter()
+        .zip(populations)
+        .filter(|(_, population)| *population > 0)
+        .collect();
+    colors.sort_by_key(|(_, population)| std::cmp::Reverse(*population));
+    colors.into_iter().map(|(color, _)| color).collect()
+}
+
+/// Cache file for the `count` color palette of `img_path`. The name changes
+/// with the full path and the count, so same-named wallpapers in other
+/// directories or with other extensions don't share a palette.
+fn cache_file(img_path: &Path, count: usize, cache_dir: &Path) -> PathBuf {
+    let mut hasher = DefaultHasher::new();
+    img_path.hash(&mut hasher);
+    count.hash(&mut hasher);
+
+    let stem = img_path
+        .file_stem()
+        .map(|s| s.to_string_lossy().to_string())
+        .unwrap_or_default();
+    cache_dir.join(format!("{}-{:016x}.json", stem, hasher.finish()))
+}
+
+fn distance(a: [u8; 3], b: [u8; 3]) -> u32 {
+    (0..3)
+        .map(|channel| (a[channel] as i32 - b[channel] as i32).pow(2) as u32)
+        .sum()
+}
+
+/// Splits the pixels into `count` boxes at the median of their widest channel
+/// and returns each box's average color.
+fn median_cut(pixels: Vec<[u8; 3]>, count: usize) -> Vec<[u8; 3]> {
+    let mut boxes = vec![pixels];
+
+    while boxes.len() < count {
+        let Some((idx, channel)) = boxes
+            .iter()
+            .enumerate()
+            .filter(|(_, pixels)| pixels.len() > 1)
+            .map(|(idx, pixels)| {
+                let (channel, range) = widest_channel(pixels);
+                (idx, channel, range)
+            })
+            .filter(|(_, _, range)| *range > 0)
+            .max_by_key(|(_, _, range)| *range)
+            .map(|(idx, channel, _)| (idx, channel))
+        else {
+            break;
+        };
+
+        let mut pixels = boxes.swap_remove(idx);
+        pixels.sort_unstable_by_key(|p| p[channel]);
+        let upper = pixels.split_off(pixels.len() / 2);
+        boxes.push(pixels);
+        boxes.push(upper);
+    }
+
+    boxes.retain(|pixels| !pixels.is_empty());
+    boxes.iter().map(|pixels| average(pixels)).collect()
+}
+
+fn widest_channel(pixels: &[[u8; 3]]) -> (usize, u8) {
+    (0..3)
+        .map(|channel| {
+            let (min, max) = pixels.iter().fold((u8::MAX, u8::MIN), |(min, max), p| {
+                (min.min(p[channel]), max.max(p[channel]))
+            });
+            (channel, max - min)
+        })
+        .max_by_key(|(_, range)| *range)
+        .unwrap_or((0, 0))
+}
+
+fn average(pixels: &[[u8; 3]]) -> [u8; 3] {
+    let mut sum = [0u64; 3];
+    for p in pixels {
+        for channel in 0..3 {
+            sum[channel] += p[channel] as u64;
+        }
+    }
+    let len = pixels.len().max(1) as u64;
+    [
+        (sum[0] / len) as u8,
+        (sum[1] / len) as u8,
+        (sum[2] / len) as u8,
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::{Rgb, RgbImage};
+    use tempfile::tempdir;
+
+    /// Three quarters red, one quarter blue.
+    fn write_sample(path: &Path) {
+        let img = RgbImage::from_fn(128, 128, |x, _| {
+            if x < 96 {
+                Rgb([200, 20, 20])
+            } else {
+                Rgb([10, 10, 120])
+            }
+        });
+        img.save(path).unwrap();
+    }
+
+    #[test]
+    fn test_quantize() {
+        let mut pixels = vec![[255, 0, 0]; 30];
+        pixels.extend(vec![[0, 0, 255]; 10]);
+
+        // Median cut alone mixes the clusters; refinement separates them
+        assert_eq!(median_cut(pixels.clone(), 2).len(), 2);
+        assert_eq!(quantize(pixels, 2), vec![[255, 0, 0], [0, 0, 255]]);
+
+        // Asking for more colors than exist does not invent any
+        assert_eq!(quantize(vec![[1, 2, 3]; 8], 4), vec![[1, 2, 3]]);
+        assert!(quantize(Vec::new(), 4).is_empty());
+    }
+
+    #[test]
+    fn test_extract_and_cache() {
+        let dir = tempdir().unwrap();
+        let img_path = dir.path().join("sample.png");
+        write_sample(&img_path);
+
+        let palette = Palette::extract(&img_path, 2).unwrap();
+        assert_eq!(palette.colors.len(), 2);
+        assert_eq!(palette.colors[0], [200, 20, 20]);
+        assert_eq!(palette.background(), [10, 10, 120]);
+        assert_eq!(palette.foreground(), [200, 20, 20]);
+
+        let cache_dir = dir.path().join("palettes");
+        let cached = Palette::cached(&img_path, 2, &cache_dir).unwrap();
+        assert_eq!(cached, palette);
+        let cache = cache_file(&img_path, 2, &cache_dir);
+        assert!(cache.exists());
+
+        // A cache hit returns the stored palette without decoding the image
+        let stored = Palette {
+            colors: vec![[1, 2, 3]],
+        };
+        let entry = CachedPalette {
+            count: 2,
+            palette: stored.clone(),
+        };
+        fs::write(&cache, serde_json::to_string(&entry).unwrap()).unwrap();
+        assert_eq!(Palette::cached(&img_path, 2, &cache_dir).unwrap(), stored);
+
+        // Another count, extension or directory has its own cache file
+        assert_ne!(cache_file(&img_path, 4, &cache_dir), cache);
+        assert_ne!(
+            cache_file(&dir.path().join("sample.jpg"), 2, &cache_dir),
+            cache
+        );
+        assert_ne!(
+            cache_file(&dir.path().join("other/sample.png"), 2, &cache_dir),
+            cache
+        );
+        assert_eq!(
+            Palette::cached(&img_path, 1, &cache_dir)
+                .unwrap()
+                .colors
+                .len(),
+            1
+        );
+    }
+
+    #[test]
+    fn test_hue_saturation() {
+        assert_eq!(hue_saturation([255, 0, 0]), (0.0, 1.0));
+        assert_eq!(hue_saturation([0, 255, 0]).0, 120.0);
+        assert_eq!(hue_saturation([0, 0, 255]).0, 240.0);
+        assert_eq!(hue_saturation([255, 0, 128]).0.round(), 330.0);
+        assert_eq!(hue_saturation([90, 90, 90]), (0.0, 0.0));
+    }
+
+    #[test]
+    fn test_color_info_and_swatches() {
+        let img = DynamicImage::ImageRgb8(RgbImage::from_fn(128, 128, |x, _| {
+            if x < 96 {
+                Rgb([20, 40, 160])
+            } else {
+                Rgb([120, 120, 120])
+            }
+        }));
+        let info = ColorInfo::of(&img);
+        assert!(Swatch::Blue.matches(&info));
+        assert!(!Swatch::Red.matches(&info));
+        assert!(Swatch::Dark.matches(&info));
+        assert!(!Swatch::Light.matches(&info));
+
+        let gray = ColorInfo::of(&DynamicImage::ImageRgb8(RgbImage::from_pixel(
+            8,
+            8,
+            Rgb([220, 220, 220]),
+        )));
+        assert!(gray.is_neutral());
+        assert!(Swatch::Light.matches(&gray));
+        assert!(!Swatch::Red.matches(&gray));
+
+        // Neutral images sort after every hue
+        assert!(info.hue_order() < gray.hue_order());
+    }
+
+    #[test]
+    fn test_export_templates() {
+        let dir = tempdir().unwrap();
+        let palette = Palette {
+            colors: vec![[255, 128, 0], [16, 16, 16]],
+        };
+
+        let files = palette
+            .export(Path::new("/walls/it's.png"), dir.path())
+            .unwrap();
+        assert_eq!(files.len(), 4);
+
+        let read = |name: &str| fs::read_to_string(dir.path().join(name)).unwrap();
+        let json: serde_json::Value = serde_json::from_str(&read("colors.json")).unwrap();
+        assert_eq!(json["colors"]["color0"], "#ff8000");
+        assert_eq!(json["colors"]["background"], "#101010");
+        assert_eq!(json["wallpaper"], "/walls/it's.png");
+
+        assert!(read("colors.css").contains("  --foreground: #ff8000;\n"));
+        assert!(read("colors.sh").contains("wallpaper='/walls/it'\\''s.png'\n"));
+        assert!(read("colors.sh").contains("color1='#101010'\n"));
+        assert!(read("colors-hyprland.conf").contains("$color0 = rgb(ff8000)\n"));
+    }
+}
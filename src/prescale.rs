@@ -0,0 +1,293 @@
+use std::{
+    collections::hash_map::DefaultHasher,
+    hash::{Hash, Hasher},
+    path::{Path, PathBuf},
+};
+
+use anyhow::{Context, Result};
+use image::{DynamicImage, ImageFormat, RgbaImage, imageops, imageops::FilterType};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    format::{Format, image_dimensions, open_image, render_svg},
+    framing::Framing,
+    outputs::OutputConfig,
+    util::is_newer,
+};
+
+/// How an image is fitted to an output's resolution.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum ScaleMode {
+    /// Scale to cover the output, cropping the overflow.
+    #[default]
+    Fill,
+    /// Scale to fit inside the output, with black bars.
+    Fit,
+    /// Keep the original size, centered on a black background.
+    Center,
+}
+
+impl std::fmt::Display for ScaleMode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ScaleMode::Fill => write!(f, "fill"),
+            ScaleMode::Fit => write!(f, "fit"),
+            ScaleMode::Center => write!(f, "center"),
+        }
+    }
+}
+
+/// Renders `img` at exactly `width`x`height`, cut the way `framing` says.
+pub fn scale_to(
+    img: &DynamicImage,
+    width: u32,
+    height: u32,
+    mode: ScaleMode,
+    framing: &Framing,
+) -> DynamicImage {
+    let placed = match mode {
+        ScaleMode::Fill => return framing.fill(img, width, height, FilterType::Lanczos3),
+        ScaleMode::Fit => framing
+            .crop(img)
+            .resize(width, height, FilterType::Lanczos3),
+        ScaleMode::Center => framing.crop(img),
+    };
+
+    let mut canvas = RgbaImage::from_pixel(width, height, image::Rgba([0, 0, 0, 255]));
+    let x = (width as i64 - placed.width() as i64) / 2;
+    let y = (height as i64 - placed.height() as i64) / 2;
+    imageops::overlay(&mut canvas, &placed.to_rgba8(), x, y);
+    DynamicImage::ImageRgba8(canvas)
+}
+
+/// Cache file for `source` scaled to a size, keyed by the source path,
+/// framing, size and mode.
+fn cache_file(
+    source: &Path,
+    framing: &Framing,
+    size: (u32, u32),
+    mode: ScaleMode,
+    cache_dir: &Path,
+) -> PathBuf {
+    let mut hasher = DefaultHasher::new();
+    source.hash(&mut hasher);
+    if !framing.is_empty() {
+        format!("{:?}", framing).hash(&mut hasher);
+    }
+
+    let stem = source
+        .file_stem()
+        .map(|s| s.to_string_lossy().to_string())
+        .unwrap_or_default();
+    cache_dir.join(format!(
+        "{}-{:016x}-{}x{}-{}.png",
+        stem,
+        hasher.finish(),
+        size.0,
+        size.1,
+        mode
+    ))
+}
+
+/// Produces a copy of `img_path` matching each output's resolution, reusing
+/// cached copies that are newer than the source. Outputs without a known
+/// size, or already matching an unframed source, get the source itself,
+/// except for SVGs, which are always rasterized since few setters read them.
+pub fn prescale_outputs(
+    img_path: &Path,
+    outputs: &[OutputConfig],
+    mode: ScaleMode,
+    framing: &Framing,
+    cache_dir: &Path,
+) -> Result<Vec<(String, PathBuf)>> {
+    let mut source: Option<DynamicImage> = None;
+    let source_size = image_dimensions(img_path)?;
+    let vector = Format::of_file(img_path) == Some(Format::Svg);
+    let output_size = |output: &OutputConfig| match (output.width, output.height) {
+        (Some(width), Some(height)) if width > 0 && height > 0 => Some((width, height)),
+        _ => None,
+    };
+
+    outputs
+        .iter()
+        .map(|output| {
+            let size = match output_size(output) {
+                Some(size) => size,
+                None if vector => source_size,
+                None => return Ok((output.name.clone(), img_path.to_path_buf())),
+            };
+            if size == source_size && framing.is_empty() && !vector {
+                return Ok((output.name.clone(), img_path.to_path_buf()));
+            }
+
+            let path = cache_file(img_path, framing, size, mode, cache_dir);
+            if !is_newer(&path, img_path) {
+                let img = match source {
+                    Some(ref img) => img,
+                    // Render once, sharp enough for the largest output
+                    None if vector => {
+                        let (width, height) = outputs
+                            .iter()
+                            .filter_map(output_size)
+                            .fold(source_size, |(w, h), (ow, oh)| (w.max(ow), h.max(oh)));
+                        source.insert(render_svg(img_path, width, height)?)
+                    }
+                    None => source.insert(open_image(img_path)?),
+                };
+
+                std::fs::create_dir_all(cache_dir).with_context(|| {
+                    format!("Failed to create directory: {}", cache_dir.display())
+                })?;
+                scale_to(img, size.0, size.1, mode, framing)
+                    .save_with_format(&path, ImageFormat::Png)
+                    .with_context(|| format!("Failed to write {}", path.display()))?;
+            }
+            Ok((output.name.clone(), path))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::framing::FocusPoint;
+    use image::{Rgb, RgbImage};
+    use tempfile::tempdir;
+
+    /// 40x20, left half red and right half blue.
+    fn sample() -> DynamicImage {
+        DynamicImage::ImageRgb8(RgbImage::from_fn(40, 20, |x, _| {
+            if x < 20 {
+                Rgb([255, 0, 0])
+            } else {
+                Rgb([0, 0, 255])
+            }
+        }))
+    }
+
+    fn sized(name: &str, width: u32, height: u32) -> OutputConfig {
+        OutputConfig {
+            width: Some(width),
+            height: Some(height),
+            ..OutputConfig::named(name)
+        }
+    }
+
+    #[test]
+    fn test_scale_modes() {
+        let img = sample();
+        let none = Framing::default();
+
+        // Fill crops the sides of a wide image on a square output
+        let fill = scale_to(&img, 10, 10, ScaleMode::Fill, &none).to_rgba8();
+        assert_eq!(fill.dimensions(), (10, 10));
+        assert_eq!(fill.get_pixel(5, 0).0[3], 255);
+
+        // Fit letterboxes it
+        let fit = scale_to(&img, 20, 20, ScaleMode::Fit, &none).to_rgba8();
+        assert_eq!(fit.dimensions(), (20, 20));
+        assert_eq!(fit.get_pixel(10, 0).0, [0, 0, 0, 255]);
+        assert_eq!(fit.get_pixel(2, 10).0, [255, 0, 0, 255]);
+
+        // Center keeps the original pixels
+        let center = scale_to(&img, 60, 30, ScaleMode::Center, &none).to_rgba8();
+        assert_eq!(center.dimensions(), (60, 30));
+        assert_eq!(center.get_pixel(0, 0).0, [0, 0, 0, 255]);
+        assert_eq!(center.get_pixel(10, 5).0, [255, 0, 0, 255]);
+        assert_eq!(center.get_pixel(49, 24).0, [0, 0, 255, 255]);
+
+        // A larger image is cropped around its center
+        let cropped = scale_to(&img, 20, 10, ScaleMode::Center, &none).to_rgba8();
+        assert_eq!(cropped.get_pixel(9, 5).0, [255, 0, 0, 255]);
+        assert_eq!(cropped.get_pixel(10, 5).0, [0, 0, 255, 255]);
+
+        // Filling around a focus point on the right keeps the blue half
+        let focused = Framing {
+            crop: None,
+            focus: Some(FocusPoint { x: 0.9, y: 0.5 }),
+        };
+        let fill = scale_to(&img, 10, 10, ScaleMode::Fill, &focused).to_rgba8();
+        assert_eq!(fill.get_pixel(0, 5).0, [0, 0, 255, 255]);
+    }
+
+    #[test]
+    fn test_prescale_outputs() {
+        let dir = tempdir().unwrap();
+        let source = dir.path().join("wide.png");
+        sample().save(&source).unwrap();
+        let cache_dir = dir.path().join("scaled");
+        let none = Framing::default();
+
+        let outputs = [
+            sized("DP-1", 16, 8),
+            OutputConfig::named("DP-2"),
+            sized("DP-3", 40, 20),
+        ];
+        let files =
+            prescale_outputs(&source, &outputs, ScaleMode::Fill, &none, &cache_dir).unwrap();
+
+        assert_eq!(files[0].0, "DP-1");
+        assert_eq!(image::image_dimensions(&files[0].1).unwrap(), (16, 8));
+        assert!(files[0].1.starts_with(&cache_dir));
+        // Unknown and matching sizes use the source directly
+        assert_eq!(files[1].1, source);
+        assert_eq!(files[2].1, source);
+
+        // The cached copy is reused while the source is unchanged
+        std::fs::write(&files[0].1, b"cached").unwrap();
+        let again =
+            prescale_outputs(&source, &outputs[..1], ScaleMode::Fill, &none, &cache_dir).unwrap();
+        assert_eq!(again[0].1, files[0].1);
+        assert_eq!(std::fs::read(&files[0].1).unwrap(), b"cached");
+
+        // Other modes are cached separately
+        let fit =
+            prescale_outputs(&source, &outputs[..1], ScaleMode::Fit, &none, &cache_dir).unwrap();
+        assert_ne!(fit[0].1, files[0].1);
+
+        // So are framed copies, even at the source's own size
+        let framing = Framing {
+            crop: None,
+            focus: Some(FocusPoint { x: 0.1, y: 0.5 }),
+        };
+        let framed = prescale_outputs(
+            &source,
+            &outputs[2..],
+            ScaleMode::Fill,
+            &framing,
+            &cache_dir,
+        )
+        .unwrap();
+        assert_ne!(framed[0].1, source);
+    }
+
+    #[cfg(feature = "svg")]
+    #[test]
+    fn test_prescale_svg() {
+        let dir = tempdir().unwrap();
+        let source = dir.path().join("minimal.svg");
+        std::fs::write(
+            &source,
+            r#"<svg xmlns="http://www.w3.org/2000/svg" width="40" height="20"><rect width="40" height="20" fill="red"/></svg>"#,
+        )
+        .unwrap();
+        let cache_dir = dir.path().join("scaled");
+
+        let outputs = [sized("DP-1", 64, 36), OutputConfig::named("DP-2")];
+        let files = prescale_outputs(
+            &source,
+            &outputs,
+            ScaleMode::Fill,
+            &Framing::default(),
+            &cache_dir,
+        )
+        .unwrap();
+
+        // SVGs are rasterized even for outputs without a known size
+        assert_eq!(image::image_dimensions(&files[0].1).unwrap(), (64, 36));
+        assert_eq!(image::image_dimensions(&files[1].1).unwrap(), (2160, 1080));
+        assert!(files.iter().all(|(_, path)| path.starts_with(&cache_dir)));
+    }
+}
@@ -0,0 +1,357 @@
+use std::{
+    collections::HashSet,
+    fmt, fs,
+    path::{Path, PathBuf},
+    str::FromStr,
+};
+
+use anyhow::{Context, Result, bail};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use crate::{tags::TagStore, util::get_absolute_path, wallpaper::discover_wallpapers};
+
+/// Tag that marks a wallpaper as a favorite.
+pub const FAVORITE_TAG: &str = "favorite";
+
+/// Where the daemon picks wallpapers from.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(try_from = "String", into = "String")]
+pub enum RotationSource {
+    All,
+    Favorites,
+    Tag(String),
+    Folder(PathBuf),
+}
+
+impl FromStr for RotationSource {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.split_once(':') {
+            None if s == "all" => Ok(Self::All),
+            None if s == "favorites" => Ok(Self::Favorites),
+            Some(("tag", tag)) if !tag.is_empty() => Ok(Self::Tag(tag.to_lowercase())),
+            Some(("folder", folder)) if !folder.is_empty() => {
+                Ok(Self::Folder(PathBuf::from(folder)))
+            }
+            _ => bail!(
+                "Invalid rotation source '{}' (expected all, favorites, tag:<name> or folder:<path>)",
+                s
+            ),
+        }
+    }
+}
+
+impl TryFrom<String> for RotationSource {
+    type Error = anyhow::Error;
+
+    fn try_from(value: String) -> Result<Self> {
+        value.parse()
+    }
+}
+
+impl From<RotationSource> for String {
+    fn from(source: RotationSource) -> Self {
+        source.to_string()
+    }
+}
+
+impl fmt::Display for RotationSource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::All => write!(f, "all"),
+            Self::Favorites => write!(f, "favorites"),
+            Self::Tag(tag) => write!(f, "tag:{}", tag),
+            Self::Folder(folder) => write!(f, "folder:{}", folder.display()),
+        }
+    }
+}
+
+/// Order the daemon goes through the wallpapers in.
+#[derive(
+    Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema, clap::ValueEnum,
+)]
+#[serde(rename_all = "lowercase")]
+pub enum RotationOrder {
+    #[default]
+    Shuffle,
+    Sequential,
+}
+
+impl RotationSource {
+    /// Lists the wallpapers this source currently refers to.
+    pub fn collect(&self, wallpaper_path: Option<&Path>, tags: &TagStore) -> Vec<PathBuf> {
+        let tagged = |tag: &str| {
+            wallpaper_path
+                .map(discover_wallpapers)
+                .unwrap_or_default()
+                .into_iter()
+                .filter(|path| {
+                    path.file_name()
+                        .and_then(|name| name.to_str())
+                        .is_some_and(|name| tags.tags_for(name).any(|t| t == tag))
+                })
+                .collect()
+        };
+
+        match self {
+            Self::All => wallpaper_path.map(discover_wallpapers).unwrap_or_default(),
+            Self::Favorites => tagged(FAVORITE_TAG),
+            Self::Tag(tag) => tagged(tag),
+            Self::Folder(folder) => get_absolute_path(&folder.to_string_lossy())
+                .map(|folder| discover_wallpapers(&folder))
+                .unwrap_or_default(),
+        }
+    }
+}
+
+/// Rotation position persisted between daemon runs.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct RotationState {
+    pub source: String,
+    pub order: RotationOrder,
+    pub last: Option<PathBuf>,
+    /// Wallpapers not yet shown in the current cycle, in the order they will be shown.
+    #[serde(default)]
+    pub queue: Vec<PathBuf>,
+}
+
+impl RotationState {
+    /// Loads the state from `path`, starting fresh if it is missing or unreadable.
+    pub fn load(path: &Path) -> Self {
+        let Ok(content) = fs::read_to_string(path) else {
+            return Self::default();
+        };
+        toml::from_str(&content).unwrap_or_else(|e| {
+            eprintln!("Ignoring invalid rotation state {}: {}", path.display(), e);
+            Self::default()
+        })
+    }
+
+    pub fn save(&self, path: &Path) -> Result<()> {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)
+                .with_context(|| format!("Failed to create directory: {}", parent.display()))?;
+        }
+        fs::write(path, toml::to_string(self)?)
+            .with_context(|| format!("Failed to write rotation state: {}", path.display()))
+    }
+
+    /// Picks the next wallpaper from `pool`, only repeating once every wallpaper
+    /// in the pool has been shown.
+    pub fn next(
+        &mut self,
+        source: &RotationSource,
+        order: RotationOrder,
+        pool: &[PathBuf],
+        rng: &mut fastrand::Rng,
+    ) -> Option<PathBuf> {
+        let source = source.to_string();
+        if self.source != source || self.order != order {
+            self.source = source;
+            self.order = order;
+            self.queue.clear();
+        }
+
+        let available: HashSet<&PathBuf> = pool.iter().collect();
+        self.queue.retain(|path| available.contains(path));
+
+        if self.queue.is_empty() {
+            self.queue = pool.to_vec();
+            match order {
+                RotationOrder::Sequential => {
+                    // Continue after the last shown wallpaper rather than restarting
+                    if let Some(pos) = self
+                        .last
+                        .as_ref()
+                        .and_then(|last| self.queue.iter().position(|path| path == last))
+                    {
+                        self.queue.rotate_left(pos + 1);
+                    }
+                }
+                RotationOrder::Shuffle => {
+                    rng.shuffle(&mut self.queue);
+                    // Avoid showing the same wallpaper twice in a row across cycles
+                    if self.queue.len() > 1 && self.queue.first() == self.last.as_ref() {
+                        let last = self.queue.len() - 1;
+                        self.queue.swap(0, last);
+                    }
+                }
+            }
+        }
+
+        if self.queue.is_empty() {
+            return None;
+        }
+        let next = self.queue.remove(0);
+        self.last = Some(next.clone());
+        Some(next)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::BTreeSet;
+    use tempfile::tempdir;
+
+    fn pool(names: &[&str]) -> Vec<PathBuf> {
+        names.iter().map(PathBuf::from).collect()
+    }
+
+    #[test]
+    fn test_parse_source() {
+        assert_eq!(
+            "all".parse::<RotationSource>().unwrap(),
+            RotationSource::All
+        );
+        assert_eq!(
+            "favorites".parse::<RotationSource>().unwrap(),
+            RotationSource::Favorites
+        );
+        assert_eq!(
+            "tag:Dark".parse::<RotationSource>().unwrap(),
+            RotationSource::Tag("dark".to_string())
+        );
+        assert_eq!(
+            "folder:/tmp/walls".parse::<RotationSource>().unwrap(),
+            RotationSource::Folder(PathBuf::from("/tmp/walls"))
+        );
+        assert!("tag:".parse::<RotationSource>().is_err());
+        assert!("everything".parse::<RotationSource>().is_err());
+    }
+
+    #[test]
+    fn test_shuffle_exhausts_pool_before_repeating() {
+        let pool = pool(&["a", "b", "c", "d"]);
+        let mut state = RotationState::default();
+        let mut rng = fastrand::Rng::with_seed(7);
+
+        for _ in 0..3 {
+            let cycle: BTreeSet<_> = (0..pool.len())
+                .map(|_| {
+                    state
+                        .next(
+                            &RotationSource::All,
+                            RotationOrder::Shuffle,
+                            &pool,
+                            &mut rng,
+                        )
+                        .unwrap()
+                })
+                .collect();
+            assert_eq!(cycle.len(), pool.len());
+        }
+    }
+
+    #[test]
+    fn test_shuffle_never_repeats_across_cycles() {
+        let pool = pool(&["a", "b"]);
+        let mut state = RotationState::default();
+        let mut rng = fastrand::Rng::with_seed(1);
+
+        let mut previous = None;
+        for _ in 0..20 {
+            let next = state.next(
+                &RotationSource::All,
+                RotationOrder::Shuffle,
+                &pool,
+                &mut rng,
+            );
+            assert_ne!(next, previous);
+            previous = next;
+        }
+    }
+
+    #[test]
+    fn test_sequential_order_and_pool_changes() {
+        let mut state = RotationState::default();
+        let mut rng = fastrand::Rng::with_seed(0);
+        let source = RotationSource::All;
+        let order = RotationOrder::Sequential;
+
+        let initial = pool(&["a", "b", "c"]);
+        assert_eq!(
+            state.next(&source, order, &initial, &mut rng),
+            Some("a".into())
+        );
+        assert_eq!(
+            state.next(&source, order, &initial, &mut rng),
+            Some("b".into())
+        );
+
+        // Removed wallpapers are skipped, new ones join the next cycle after the last shown
+        let changed = pool(&["b", "d", "e"]);
+        assert_eq!(
+            state.next(&source, order, &changed, &mut rng),
+            Some("d".into())
+        );
+        assert_eq!(
+            state.next(&source, order, &changed, &mut rng),
+            Some("e".into())
+        );
+        assert_eq!(
+            state.next(&source, order, &changed, &mut rng),
+            Some("b".into())
+        );
+
+        assert_eq!(state.next(&source, order, &[], &mut rng), None);
+    }
+
+    #[test]
+    fn test_state_persists_across_restarts() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("state/daemon.toml");
+        let pool = pool(&["a", "b", "c"]);
+        let source = RotationSource::Tag("dark".to_string());
+        let mut rng = fastrand::Rng::with_seed(3);
+
+        let mut state = RotationState::load(&path);
+        let first = state.next(&source, RotationOrder::Shuffle, &pool, &mut rng);
+        state.save(&path).unwrap();
+
+        let mut restored = RotationState::load(&path);
+        assert_eq!(restored, state);
+        let rest: BTreeSet<_> = (0..2)
+            .map(|_| restored.next(&source, RotationOrder::Shuffle, &pool, &mut rng))
+            .collect();
+        assert!(!rest.contains(&first));
+
+        // Switching source starts a new cycle
+        restored.next(
+            &RotationSource::All,
+            RotationOrder::Shuffle,
+            &pool,
+            &mut rng,
+        );
+        assert_eq!(restored.source, "all");
+        assert_eq!(restored.queue.len(), 2);
+    }
+
+    #[test]
+    fn test_collect_tagged() {
+        let dir = tempdir().unwrap();
+        for name in ["a.png", "b.png", "c.png"] {
+            std::fs::File::create(dir.path().join(name)).unwrap();
+        }
+        let mut tags = TagStore::default();
+        tags.set_tags("a.png", BTreeSet::from([FAVORITE_TAG.to_string()]));
+        tags.set_tags("c.png", BTreeSet::from(["dark".to_string()]));
+
+        let all = RotationSource::All.collect(Some(dir.path()), &tags);
+        assert_eq!(all.len(), 3);
+        assert_eq!(
+            RotationSource::Favorites.collect(Some(dir.path()), &tags),
+            vec![dir.path().join("a.png")]
+        );
+        assert_eq!(
+            RotationSource::Tag("dark".to_string()).collect(Some(dir.path()), &tags),
+            vec![dir.path().join("c.png")]
+        );
+        assert_eq!(
+            RotationSource::Folder(dir.path().to_path_buf()).collect(None, &tags),
+            all
+        );
+    }
+}
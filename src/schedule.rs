@@ -0,0 +1,608 @@
+use std::path::PathBuf;
+
+use anyhow::{Context, Result, bail};
+use chrono::{Datelike, DurationRound, NaiveDateTime, NaiveTime, TimeDelta, Timelike, Weekday};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use crate::rotation::RotationSource;
+
+/// Source of the current time, replaceable in tests.
+pub trait Clock {
+    fn now(&self) -> NaiveDateTime;
+}
+
+pub struct LocalClock;
+
+impl Clock for LocalClock {
+    fn now(&self) -> NaiveDateTime {
+        chrono::Local::now().naive_local()
+    }
+}
+
+/// A `[[schedule]]` entry from the config file.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+#[serde(try_from = "RawScheduleRule", into = "RawScheduleRule")]
+pub struct ScheduleRule {
+    pub name: Option<String>,
+    pub when: RuleTime,
+    pub target: ScheduleTarget,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum RuleTime {
+    /// Active from `from` until `to`, wrapping past midnight when `to <= from`.
+    Range {
+        from: NaiveTime,
+        to: NaiveTime,
+        days: Option<Vec<Weekday>>,
+    },
+    Cron(CronExpr),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ScheduleTarget {
+    Wallpaper(PathBuf),
+    Folder(PathBuf),
+    Tag(String),
+}
+
+impl ScheduleTarget {
+    /// Rotation source for folder and tag targets; single wallpapers are not rotated.
+    pub fn source(&self) -> Option<RotationSource> {
+        match self {
+            Self::Wallpaper(_) => None,
+            Self::Folder(folder) => Some(RotationSource::Folder(folder.clone())),
+            Self::Tag(tag) => Some(RotationSource::Tag(tag.clone())),
+        }
+    }
+}
+
+/// A rule active between `from` and `to` or at the times `cron` matches,
+/// showing a `wallpaper`, or one from a `folder` or with a `tag`.
+#[derive(Serialize, Deserialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
+struct RawScheduleRule {
+    name: Option<String>,
+    /// Start time, e.g. `06:00`.
+    from: Option<String>,
+    /// End time, wrapping past midnight when earlier than `from`.
+    to: Option<String>,
+    /// Weekdays the time range applies on, e.g. `["mon", "tue"]`.
+    days: Option<Vec<String>>,
+    /// Five-field cron expression, e.g. `* 12-17 * * 6,0`.
+    cron: Option<String>,
+    wallpaper: Option<PathBuf>,
+    folder: Option<PathBuf>,
+    tag: Option<String>,
+}
+
+impl TryFrom<RawScheduleRule> for ScheduleRule {
+    type Error = anyhow::Error;
+
+    fn try_from(raw: RawScheduleRule) -> Result<Self> {
+        let when = match (raw.from, raw.to, raw.cron) {
+            (Some(from), Some(to), None) => RuleTime::Range {
+                from: parse_time(&from)?,
+                to: parse_time(&to)?,
+                days: raw
+                    .days
+                    .map(|days| days.iter().map(|day| parse_weekday(day)).collect())
+                    .transpose()?,
+            },
+            (None, None, Some(cron)) => {
+                if raw.days.is_some() {
+                    bail!("'days' cannot be combined with 'cron'");
+                }
+                RuleTime::Cron(cron.parse()?)
+            }
+            _ => bail!("schedule rule needs either 'from' and 'to', or 'cron'"),
+        };
+
+        let target = match (raw.wallpaper, raw.folder, raw.tag) {
+            (Some(wallpaper), None, None) => ScheduleTarget::Wallpaper(wallpaper),
+            (None, Some(folder), None) => ScheduleTarget::Folder(folder),
+            (None, None, Some(tag)) => ScheduleTarget::Tag(tag.to_lowercase()),
+            _ => bail!("schedule rule needs exactly one of 'wallpaper', 'folder' or 'tag'"),
+        };
+
+        Ok(Self {
+            name: raw.name,
+            when,
+            target,
+        })
+    }
+}
+
+impl From<ScheduleRule> for RawScheduleRule {
+    fn from(rule: ScheduleRule) -> Self {
+        let mut raw = RawScheduleRule {
+            name: rule.name,
+            from: None,
+            to: None,
+            days: None,
+            cron: None,
+            wallpaper: None,
+            folder: None,
+            tag: None,
+        };
+        match rule.when {
+            RuleTime::Range { from, to, days } => {
+                raw.from = Some(from.format("%H:%M").to_string());
+                raw.to = Some(to.format("%H:%M").to_string());
+                raw.days = days.map(|days| {
+                    days.iter()
+                        .map(|day| day.to_string().to_lowercase())
+                        .collect()
+                });
+            }
+            RuleTime::Cron(cron) => raw.cron = Some(cron.to_string()),
+        }
+        match rule.target {
+            ScheduleTarget::Wallpaper(wallpaper) => raw.wallpaper = Some(wallpaper),
+            ScheduleTarget::Folder(folder) => raw.folder = Some(folder),
+            ScheduleTarget::Tag(tag) => raw.tag = Some(tag),
+        }
+        raw
+    }
+}
+
+impl ScheduleRule {
+    pub fn matches(&self, now: NaiveDateTime) -> bool {
+        match self.when {
+            RuleTime::Range { from, to, ref days } => {
+                let time = now.time();
+                let in_range = if from < to {
+                    time >= from && time < to
+                } else {
+                    time >= from || time < to
+                };
+                in_range
+                    && days
+                        .as_ref()
+                        .is_none_or(|days| days.contains(&now.weekday()))
+            }
+            RuleTime::Cron(ref cron) => cron.matches(now),
+        }
+    }
+
+    fn is_cron(&self) -> bool {
+        matches!(self.when, RuleTime::Cron(_))
+    }
+
+    pub fn label(&self, idx: usize) -> String {
+        self.name
+            .clone()
+            .unwrap_or_else(|| format!("schedule #{}", idx + 1))
+    }
+}
+
+/// Most minutes looked back over for cron rules that fired between two
+/// checks, e.g. while the machine was suspended.
+const MAX_CATCH_UP: TimeDelta = TimeDelta::days(1);
+
+/// Tracks the active rule from one check to the next. A range rule is active
+/// while its time range lasts. A cron rule fires at the minutes it matches
+/// and stays active until another rule fires or a range rule starts.
+#[derive(Debug, Default)]
+pub struct Scheduler {
+    last_check: Option<NaiveDateTime>,
+    /// The first range rule active at the last check.
+    range: Option<usize>,
+    active: Option<usize>,
+}
+
+impl Scheduler {
+    /// Returns the index of the rule active at `now`. Cron rules that matched
+    /// any minute since the previous check count as fired, so a late check
+    /// doesn't miss them.
+    pub fn active(&mut self, rules: &[ScheduleRule], now: NaiveDateTime) -> Option<usize> {
+        let range = rules
+            .iter()
+            .position(|rule| !rule.is_cron() && rule.matches(now));
+        let started = range.filter(|_| range != self.range);
+        let fired = self.fired(rules, now);
+
+        self.active = match (fired, started) {
+            // The first listed wins when both happen at once
+            (Some(fired), Some(started)) => Some(fired.min(started)),
+            (Some(idx), None) | (None, Some(idx)) => Some(idx),
+            // Ending a range rule leaves whichever range is left, if any
+            (None, None) if self.active == self.range => range,
+            (None, None) => self.active,
+        };
+        self.range = range;
+        self.last_check = Some(now);
+        self.active
+    }
+
+    /// The cron rule that matched most recently in the minutes since the
+    /// previous check, or in the current minute on the first check.
+    fn fired(&self, rules: &[ScheduleRule], now: NaiveDateTime) -> Option<usize> {
+        let minute = |time: NaiveDateTime| time.duration_trunc(TimeDelta::minutes(1)).ok();
+        let mut current = minute(now)?;
+        let since = match self.last_check {
+            Some(last) => minute(last)?,
+            None => current - TimeDelta::minutes(1),
+        }
+        .max(current - MAX_CATCH_UP);
+
+        while current > since {
+            if let Some(idx) = rules
+                .iter()
+                .position(|rule| rule.is_cron() && rule.matches(current))
+            {
+                return Some(idx);
+            }
+            current -= TimeDelta::minutes(1);
+        }
+        None
+    }
+}
+
+fn parse_time(input: &str) -> Result<NaiveTime> {
+    NaiveTime::parse_from_str(input, "%H:%M")
+        .with_context(|| format!("Invalid time '{}' (expected HH:MM)", input))
+}
+
+fn parse_weekday(input: &str) -> Result<Weekday> {
+    input
+        .parse()
+        .map_err(|_| anyhow::anyhow!("Invalid day '{}' (expected mon, tue, ...)", input))
+}
+
+/// A five-field cron expression (`minute hour day-of-month month day-of-week`),
+/// matched against the current minute.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CronExpr {
+    /// The expression as written, for showing the config.
+    source: String,
+    minute: CronField,
+    hour: CronField,
+    day: CronField,
+    month: CronField,
+    weekday: CronField,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct CronField {
+    allowed: u64,
+    any: bool,
+}
+
+impl CronField {
+    fn parse(input: &str, min: u32, max: u32) -> Result<Self> {
+        let mut allowed = 0u64;
+        for part in input.split(',') {
+            let (range, step) = match part.split_once('/') {
+                Some((range, step)) => (
+                    range,
+                    step.parse::<u32>()
+                        .ok()
+                        .filter(|step| *step > 0)
+                        .with_context(|| format!("Invalid cron step '{}'", step))?,
+                ),
+                None => (part, 1),
+            };
+            let (start, end) = match range {
+                "*" => (min, max),
+                _ => match range.split_once('-') {
+                    Some((start, end)) => (parse_cron_value(start)?, parse_cron_value(end)?),
+                    None => {
+                        let value = parse_cron_value(range)?;
+                        (value, if step > 1 { max } else { value })
+                    }
+                },
+            };
+            if start < min || end > max || start > end {
+                bail!("Cron value '{}' out of range {}-{}", part, min, max);
+            }
+            for value in (start..=end).step_by(step as usize) {
+                allowed |= 1 << value;
+            }
+        }
+
+        Ok(Self {
+            allowed,
+            any: input == "*",
+        })
+    }
+
+    fn contains(&self, value: u32) -> bool {
+        self.allowed & (1 << value) != 0
+    }
+}
+
+fn parse_cron_value(input: &str) -> Result<u32> {
+    input
+        .parse()
+        .with_context(|| format!("Invalid cron value '{}'", input))
+}
+
+impl std::str::FromStr for CronExpr {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let fields: Vec<&str> = s.split_whitespace().collect();
+        let [minute, hour, day, month, weekday] = fields[..] else {
+            bail!("Invalid cron expression '{}' (expected 5 fields)", s);
+        };
+
+        let mut weekday = CronField::parse(weekday, 0, 7)?;
+        // Both 0 and 7 mean Sunday
+        if weekday.contains(7) {
+            weekday.allowed |= 1;
+        }
+
+        Ok(Self {
+            source: fields.join(" "),
+            minute: CronField::parse(minute, 0, 59)?,
+            hour: CronField::parse(hour, 0, 23)?,
+            day: CronField::parse(day, 1, 31)?,
+            month: CronField::parse(month, 1, 12)?,
+            weekday,
+        })
+    }
+}
+
+impl std::fmt::Display for CronExpr {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.source)
+    }
+}
+
+impl CronExpr {
+    pub fn matches(&self, now: NaiveDateTime) -> bool {
+        let day_matches = self.day.contains(now.day());
+        let weekday_matches = self.weekday.contains(now.weekday().num_days_from_sunday());
+        // Like cron, a restricted day-of-month and day-of-week match if either does
+        let date_matches = match (self.day.any, self.weekday.any) {
+            (false, false) => day_matches || weekday_matches,
+            _ => day_matches && weekday_matches,
+        };
+
+        self.minute.contains(now.minute())
+            && self.hour.contains(now.hour())
+            && self.month.contains(now.month())
+            && date_matches
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::NaiveDate;
+
+    struct FixedClock(NaiveDateTime);
+
+    impl Clock for FixedClock {
+        fn now(&self) -> NaiveDateTime {
+            self.0
+        }
+    }
+
+    /// 2025-06-02 is a Monday.
+    fn at(day: u32, hour: u32, minute: u32) -> FixedClock {
+        FixedClock(
+            NaiveDate::from_ymd_opt(2025, 6, day)
+                .unwrap()
+                .and_hms_opt(hour, minute, 0)
+                .unwrap(),
+        )
+    }
+
+    /// The rule active at a first check at `clock`.
+    fn active(rules: &[ScheduleRule], clock: FixedClock) -> Option<usize> {
+        Scheduler::default().active(rules, clock.now())
+    }
+
+    fn parse_rules(config: &str) -> Result<Vec<ScheduleRule>, toml::de::Error> {
+        #[derive(Deserialize)]
+        struct Config {
+            schedule: Vec<ScheduleRule>,
+        }
+        toml::from_str::<Config>(config).map(|config| config.schedule)
+    }
+
+    fn rules(config: &str) -> Vec<ScheduleRule> {
+        parse_rules(config).unwrap()
+    }
+
+    const DAY_PARTS: &str = r#"
+        [[schedule]]
+        name = "morning"
+        from = "06:00"
+        to = "12:00"
+        tag = "morning"
+
+        [[schedule]]
+        name = "day"
+        from = "12:00"
+        to = "18:00"
+        folder = "~/Pictures/day"
+
+        [[schedule]]
+        name = "evening"
+        from = "18:00"
+        to = "22:00"
+        wallpaper = "~/Pictures/sunset.jpg"
+
+        [[schedule]]
+        name = "night"
+        from = "22:00"
+        to = "06:00"
+        tag = "night"
+    "#;
+
+    #[test]
+    fn test_time_ranges() {
+        let rules = rules(DAY_PARTS);
+        let name = |clock: FixedClock| active(&rules, clock).map(|idx| rules[idx].label(idx));
+
+        assert_eq!(name(at(2, 6, 0)).as_deref(), Some("morning"));
+        assert_eq!(name(at(2, 11, 59)).as_deref(), Some("morning"));
+        assert_eq!(name(at(2, 12, 0)).as_deref(), Some("day"));
+        assert_eq!(name(at(2, 21, 30)).as_deref(), Some("evening"));
+        assert_eq!(name(at(2, 23, 0)).as_deref(), Some("night"));
+        assert_eq!(name(at(3, 2, 0)).as_deref(), Some("night"));
+
+        assert_eq!(
+            rules[0].target.source(),
+            Some(RotationSource::Tag("morning".to_string()))
+        );
+        assert_eq!(rules[2].target.source(), None);
+    }
+
+    #[test]
+    fn test_days_and_priority() {
+        let rules = rules(
+            r#"
+            [[schedule]]
+            from = "09:00"
+            to = "17:00"
+            days = ["mon", "tue", "wed", "thu", "fri"]
+            tag = "work"
+
+            [[schedule]]
+            from = "00:00"
+            to = "00:00"
+            tag = "any"
+            "#,
+        );
+
+        // Monday during work hours hits the first rule
+        assert_eq!(active(&rules, at(2, 10, 0)), Some(0));
+        // Saturday falls through to the catch-all
+        assert_eq!(active(&rules, at(7, 10, 0)), Some(1));
+        assert_eq!(active(&rules, at(2, 18, 0)), Some(1));
+        assert_eq!(active(&[], at(2, 18, 0)), None);
+    }
+
+    #[test]
+    fn test_cron_rules() {
+        let weekday_mornings: CronExpr = "* 6-11 * * 1-5".parse().unwrap();
+        assert!(weekday_mornings.matches(at(2, 6, 0).now()));
+        assert!(!weekday_mornings.matches(at(2, 12, 0).now()));
+        assert!(!weekday_mornings.matches(at(8, 7, 0).now()));
+
+        let sundays: CronExpr = "*/15 * * * 7".parse().unwrap();
+        assert!(sundays.matches(at(8, 13, 30).now()));
+        assert!(!sundays.matches(at(8, 13, 31).now()));
+
+        // Restricted day-of-month or day-of-week, like cron
+        let first_or_monday: CronExpr = "* * 1 * 1".parse().unwrap();
+        assert!(first_or_monday.matches(at(1, 0, 0).now()));
+        assert!(first_or_monday.matches(at(2, 0, 0).now()));
+        assert!(!first_or_monday.matches(at(3, 0, 0).now()));
+
+        let rules = rules(
+            r#"
+            [[schedule]]
+            cron = "* 20-23 * * *"
+            tag = "night"
+            "#,
+        );
+        assert_eq!(active(&rules, at(2, 21, 0)), Some(0));
+        assert_eq!(active(&rules, at(2, 9, 0)), None);
+    }
+
+    #[test]
+    fn test_cron_rules_stay_active() {
+        let rules = rules(
+            r#"
+            [[schedule]]
+            cron = "0 6 * * *"
+            wallpaper = "~/Pictures/sunrise.jpg"
+
+            [[schedule]]
+            from = "12:00"
+            to = "18:00"
+            tag = "day"
+
+            [[schedule]]
+            cron = "30 14 * * *"
+            tag = "break"
+            "#,
+        );
+        let with_seconds = |hour, minute, second| {
+            NaiveDate::from_ymd_opt(2025, 6, 2)
+                .unwrap()
+                .and_hms_opt(hour, minute, second)
+                .unwrap()
+        };
+
+        // Fired at 06:00, still active half an hour later
+        let mut scheduler = Scheduler::default();
+        assert_eq!(scheduler.active(&rules, at(2, 5, 59).now()), None);
+        assert_eq!(scheduler.active(&rules, with_seconds(6, 0, 59)), Some(0));
+        assert_eq!(scheduler.active(&rules, at(2, 6, 2).now()), Some(0));
+        assert_eq!(scheduler.active(&rules, at(2, 6, 30).now()), Some(0));
+
+        // A check that comes late still sees the minute it skipped
+        let mut scheduler = Scheduler::default();
+        assert_eq!(scheduler.active(&rules, with_seconds(5, 59, 59)), None);
+        assert_eq!(scheduler.active(&rules, at(2, 6, 2).now()), Some(0));
+
+        // A range rule takes over when it starts, and a cron rule firing
+        // during it takes over in turn and outlasts it
+        assert_eq!(scheduler.active(&rules, at(2, 12, 0).now()), Some(1));
+        assert_eq!(scheduler.active(&rules, at(2, 14, 30).now()), Some(2));
+        assert_eq!(scheduler.active(&rules, at(2, 15, 0).now()), Some(2));
+        assert_eq!(scheduler.active(&rules, at(2, 19, 0).now()), Some(2));
+
+        // Without a cron rule, the end of a range leaves nothing active
+        let mut scheduler = Scheduler::default();
+        assert_eq!(scheduler.active(&rules, at(2, 17, 0).now()), Some(1));
+        assert_eq!(scheduler.active(&rules, at(2, 18, 0).now()), None);
+    }
+
+    #[test]
+    fn test_serialize_rules() {
+        #[derive(Serialize)]
+        struct Config<'a> {
+            schedule: &'a [ScheduleRule],
+        }
+
+        let mut original = rules(DAY_PARTS);
+        original.extend(rules(
+            r#"
+            [[schedule]]
+            from = "09:00"
+            to = "17:00"
+            days = ["mon", "fri"]
+            tag = "work"
+
+            [[schedule]]
+            cron = "*/15  6-11 * * 1-5"
+            tag = "work"
+            "#,
+        ));
+
+        let written = toml::to_string(&Config {
+            schedule: &original,
+        })
+        .unwrap();
+        assert!(
+            written.contains("cron = \"*/15 6-11 * * 1-5\""),
+            "{}",
+            written
+        );
+        assert_eq!(rules(&written), original);
+    }
+
+    #[test]
+    fn test_invalid_rules() {
+        let parse = |rule: &str| parse_rules(&format!("[[schedule]]\n{}", rule));
+
+        assert!(parse("from = \"06:00\"\ntag = \"a\"").is_err());
+        assert!(parse("from = \"6am\"\nto = \"12:00\"\ntag = \"a\"").is_err());
+        assert!(parse("from = \"06:00\"\nto = \"12:00\"").is_err());
+        assert!(parse("from = \"06:00\"\nto = \"12:00\"\ntag = \"a\"\nfolder = \"b\"").is_err());
+        assert!(
+            parse("from = \"06:00\"\nto = \"12:00\"\ndays = [\"someday\"]\ntag = \"a\"").is_err()
+        );
+        assert!(parse("cron = \"* * *\"\ntag = \"a\"").is_err());
+        assert!(parse("cron = \"61 * * * *\"\ntag = \"a\"").is_err());
+        assert!(parse("cron = \"* * * * *\"\ntag = \"a\"").is_ok());
+    }
+}
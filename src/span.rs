@@ -0,0 +1,242 @@
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result, bail};
+use image::{ImageFormat, imageops::FilterType};
+
+use crate::format::open_image;
+use crate::outputs::{OutputConfig, OutputGeometry};
+
+/// A region of the source image, in source pixels.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CropRect {
+    pub x: u32,
+    pub y: u32,
+    pub width: u32,
+    pub height: u32,
+}
+
+/// Computes which part of a `source`-sized image each output shows when the
+/// image is scaled to cover the whole layout.
+///
+/// `bezel` pixels are inserted between neighbouring outputs, so the part of the
+/// image hidden behind the monitor frames is skipped rather than squeezed out.
+pub fn crop_rects(source: (u32, u32), outputs: &[OutputGeometry], bezel: u32) -> Vec<CropRect> {
+    if outputs.is_empty() || source.0 == 0 || source.1 == 0 {
+        return Vec::new();
+    }
+
+    let layout = with_bezels(outputs, bezel);
+    let min_x = layout.iter().map(|o| o.0).min().unwrap_or(0);
+    let min_y = layout.iter().map(|o| o.1).min().unwrap_or(0);
+    let max_x = layout.iter().map(|o| o.0 + o.2 as i64).max().unwrap_or(0);
+    let max_y = layout.iter().map(|o| o.1 + o.3 as i64).max().unwrap_or(0);
+    let (canvas_w, canvas_h) = ((max_x - min_x) as f64, (max_y - min_y) as f64);
+
+    // Scale the source to cover the canvas, centering the overflow
+    let scale = (canvas_w / source.0 as f64).max(canvas_h / source.1 as f64);
+    let offset_x = (source.0 as f64 * scale - canvas_w) / 2.0;
+    let offset_y = (source.1 as f64 * scale - canvas_h) / 2.0;
+
+    layout
+        .iter()
+        .map(|&(x, y, width, height)| {
+            let left = ((x - min_x) as f64 + offset_x) / scale;
+            let top = ((y - min_y) as f64 + offset_y) / scale;
+            let right = left + width as f64 / scale;
+            let bottom = top + height as f64 / scale;
+
+            let x = (left.round() as u32).min(source.0 - 1);
+            let y = (top.round() as u32).min(source.1 - 1);
+            CropRect {
+                x,
+                y,
+                width: ((right.round() as u32).min(source.0) - x).max(1),
+                height: ((bottom.round() as u32).min(source.1) - y).max(1),
+            }
+        })
+        .collect()
+}
+
+/// Shifts outputs right/down by one bezel for every column/row boundary before them.
+fn with_bezels(outputs: &[OutputGeometry], bezel: u32) -> Vec<(i64, i64, u32, u32)> {
+    let shift = |pos: i32, starts: &[i32]| {
+        let boundaries = starts.iter().filter(|&&start| start < pos).count();
+        pos as i64 + boundaries as i64 * bezel as i64
+    };
+
+    let mut columns: Vec<i32> = outputs.iter().map(|o| o.x).collect();
+    let mut rows: Vec<i32> = outputs.iter().map(|o| o.y).collect();
+    columns.sort_unstable();
+    columns.dedup();
+    rows.sort_unstable();
+    rows.dedup();
+
+    outputs
+        .iter()
+        .map(|o| (shift(o.x, &columns), shift(o.y, &rows), o.width, o.height))
+        .collect()
+}
+
+/// Splits `img_path` into one image per output, written to `cache_dir`.
+/// Returns each output name with its image.
+pub fn span_wallpaper(
+    img_path: &Path,
+    outputs: &[OutputConfig],
+    bezel: u32,
+    cache_dir: &Path,
+) -> Result<Vec<(String, PathBuf)>> {
+    let geometries = outputs
+        .iter()
+        .map(|o| {
+            o.geometry()
+                .with_context(|| format!("Output {} has no x, y, width and height", o.name))
+        })
+        .collect::<Result<Vec<_>>>()?;
+    if geometries.len() < 2 {
+        bail!("Spanning needs at least two outputs");
+    }
+
+    let img = open_image(img_path)?;
+    let rects = crop_rects((img.width(), img.height()), &geometries, bezel);
+
+    std::fs::create_dir_all(cache_dir)
+        .with_context(|| format!("Failed to create directory: {}", cache_dir.display()))?;
+    let stem = img_path
+        .file_stem()
+        .map(|s| s.to_string_lossy().to_string())
+        .unwrap_or_default();
+
+    outputs
+        .iter()
+        .zip(geometries.iter().zip(rects))
+        .map(|(output, (geometry, rect))| {
+            let piece = img
+                .crop_imm(rect.x, rect.y, rect.width, rect.height)
+                .resize_exact(geometry.width, geometry.height, FilterType::Lanczos3);
+            let path = cache_dir.join(format!("{}-{}.png", stem, output.name));
+            piece
+                .save_with_format(&path, ImageFormat::Png)
+                .with_context(|| format!("Failed to write {}", path.display()))?;
+            Ok((output.name.clone(), path))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::RgbImage;
+    use tempfile::tempdir;
+
+    fn output(x: i32, y: i32, width: u32, height: u32) -> OutputGeometry {
+        OutputGeometry {
+            x,
+            y,
+            width,
+            height,
+        }
+    }
+
+    fn rect(x: u32, y: u32, width: u32, height: u32) -> CropRect {
+        CropRect {
+            x,
+            y,
+            width,
+            height,
+        }
+    }
+
+    #[test]
+    fn test_side_by_side() {
+        let outputs = [output(0, 0, 100, 50), output(100, 0, 100, 50)];
+
+        assert_eq!(
+            crop_rects((200, 50), &outputs, 0),
+            vec![rect(0, 0, 100, 50), rect(100, 0, 100, 50)]
+        );
+        // Twice the resolution maps to twice the crop
+        assert_eq!(
+            crop_rects((400, 100), &outputs, 0),
+            vec![rect(0, 0, 200, 100), rect(200, 0, 200, 100)]
+        );
+    }
+
+    #[test]
+    fn test_side_by_side_with_bezel() {
+        let outputs = [output(0, 0, 100, 50), output(100, 0, 100, 50)];
+
+        // The 10px behind the bezels is skipped
+        assert_eq!(
+            crop_rects((210, 50), &outputs, 10),
+            vec![rect(0, 0, 100, 50), rect(110, 0, 100, 50)]
+        );
+    }
+
+    #[test]
+    fn test_stacked_with_bezel() {
+        let outputs = [output(0, 0, 100, 50), output(0, 50, 100, 50)];
+
+        assert_eq!(
+            crop_rects((100, 100), &outputs, 0),
+            vec![rect(0, 0, 100, 50), rect(0, 50, 100, 50)]
+        );
+        assert_eq!(
+            crop_rects((200, 220), &outputs, 10),
+            vec![rect(0, 0, 200, 100), rect(0, 120, 200, 100)]
+        );
+    }
+
+    #[test]
+    fn test_wider_source_is_centered() {
+        let outputs = [output(0, 0, 100, 50), output(100, 0, 100, 50)];
+
+        assert_eq!(
+            crop_rects((300, 50), &outputs, 0),
+            vec![rect(50, 0, 100, 50), rect(150, 0, 100, 50)]
+        );
+    }
+
+    #[test]
+    fn test_mixed_sizes_and_negative_positions() {
+        // A portrait monitor left of a landscape one, offset into negative coordinates
+        let outputs = [output(-50, 0, 50, 100), output(0, 25, 100, 50)];
+
+        assert_eq!(
+            crop_rects((150, 100), &outputs, 0),
+            vec![rect(0, 0, 50, 100), rect(50, 25, 100, 50)]
+        );
+    }
+
+    #[test]
+    fn test_span_wallpaper_writes_pieces() {
+        let dir = tempdir().unwrap();
+        let source = dir.path().join("wide.png");
+        RgbImage::new(400, 100).save(&source).unwrap();
+
+        let outputs = [
+            OutputConfig {
+                x: Some(0),
+                y: Some(0),
+                width: Some(100),
+                height: Some(50),
+                ..OutputConfig::named("DP-1")
+            },
+            OutputConfig {
+                x: Some(100),
+                y: Some(0),
+                width: Some(100),
+                height: Some(50),
+                ..OutputConfig::named("DP-2")
+            },
+        ];
+
+        let pieces = span_wallpaper(&source, &outputs, 0, &dir.path().join("span")).unwrap();
+        assert_eq!(pieces.len(), 2);
+        assert_eq!(pieces[1].0, "DP-2");
+        let piece = image::open(&pieces[1].1).unwrap();
+        assert_eq!((piece.width(), piece.height()), (100, 50));
+
+        let missing_geometry = [OutputConfig::named("DP-1"), OutputConfig::named("DP-2")];
+        assert!(span_wallpaper(&source, &missing_geometry, 0, dir.path()).is_err());
+    }
+}
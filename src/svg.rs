@@ -0,0 +1,100 @@
+use std::{path::Path, sync::Arc};
+
+use anyhow::{Context, Result};
+use image::{DynamicImage, RgbaImage};
+use once_cell::sync::Lazy;
+use resvg::{
+    tiny_skia::{Pixmap, Transform},
+    usvg::{self, fontdb},
+};
+
+/// System fonts for text in SVGs, loaded once since scanning them is slow.
+static FONTS: Lazy<Arc<fontdb::Database>> = Lazy::new(|| {
+    let mut fonts = fontdb::Database::new();
+    fonts.load_system_fonts();
+    Arc::new(fonts)
+});
+
+fn parse(path: &Path) -> Result<usvg::Tree> {
+    let data = std::fs::read(path).with_context(|| format!("Failed to read {}", path.display()))?;
+    let options = usvg::Options {
+        resources_dir: path.parent().map(Path::to_path_buf),
+        fontdb: FONTS.clone(),
+        ..usvg::Options::default()
+    };
+    usvg::Tree::from_data(&data, &options)
+        .with_context(|| format!("Failed to parse SVG: {}", path.display()))
+}
+
+/// Scale factor and pixel size to just cover `width`x`height`.
+fn cover(tree: &usvg::Tree, width: u32, height: u32) -> (f32, u32, u32) {
+    let size = tree.size();
+    let scale = (width as f32 / size.width()).max(height as f32 / size.height());
+    (
+        scale,
+        ((size.width() * scale).round() as u32).max(1),
+        ((size.height() * scale).round() as u32).max(1),
+    )
+}
+
+/// Size of the image [`render`] produces for the same arguments.
+pub fn render_size(path: &Path, width: u32, height: u32) -> Result<(u32, u32)> {
+    let (_, pixels_w, pixels_h) = cover(&parse(path)?, width, height);
+    Ok((pixels_w, pixels_h))
+}
+
+/// Rasterizes the SVG, scaled up or down to just cover `width`x`height` while
+/// keeping its aspect ratio.
+pub fn render(path: &Path, width: u32, height: u32) -> Result<DynamicImage> {
+    let tree = parse(path)?;
+    let (scale, pixels_w, pixels_h) = cover(&tree, width, height);
+
+    let mut pixmap = Pixmap::new(pixels_w, pixels_h)
+        .with_context(|| format!("Invalid SVG size: {}x{}", pixels_w, pixels_h))?;
+    resvg::render(
+        &tree,
+        Transform::from_scale(scale, scale),
+        &mut pixmap.as_mut(),
+    );
+
+    let data = pixmap
+        .pixels()
+        .iter()
+        .flat_map(|pixel| {
+            let color = pixel.demultiply();
+            [color.red(), color.green(), color.blue(), color.alpha()]
+        })
+        .collect();
+    let img = RgbaImage::from_raw(pixels_w, pixels_h, data)
+        .context("Rendered SVG has an unexpected size")?;
+    Ok(DynamicImage::ImageRgba8(img))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    const STRIPES: &str = r##"<svg xmlns="http://www.w3.org/2000/svg" width="40" height="20">
+  <rect x="0" y="0" width="20" height="20" fill="#ff0000"/>
+  <rect x="20" y="0" width="20" height="20" fill="#0000ff"/>
+</svg>"##;
+
+    #[test]
+    fn test_render() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("stripes.svg");
+        std::fs::write(&path, STRIPES).unwrap();
+
+        assert_eq!(render_size(&path, 160, 160).unwrap(), (320, 160));
+
+        // Rendered sharp at a larger size, covering the requested area
+        let img = render(&path, 160, 160).unwrap().to_rgba8();
+        assert_eq!(img.dimensions(), (320, 160));
+        assert_eq!(img.get_pixel(10, 80).0, [255, 0, 0, 255]);
+        assert_eq!(img.get_pixel(310, 80).0, [0, 0, 255, 255]);
+
+        std::fs::write(&path, "<svg").unwrap();
+        assert!(render(&path, 10, 10).is_err());
+    }
+}
@@ -0,0 +1,193 @@
+use anyhow::{Context, Result, bail};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fs,
+    path::Path,
+};
+
+/// Tags assigned to wallpapers, keyed by wallpaper file name.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct TagStore {
+    #[serde(default)]
+    pub wallpapers: BTreeMap<String, BTreeSet<String>>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum TagFormat {
+    Toml,
+    Json,
+}
+
+impl TagFormat {
+    fn from_path(path: &Path) -> Result<Self> {
+        match path.extension().and_then(|e| e.to_str()) {
+            Some(ext) if ext.eq_ignore_ascii_case("toml") => Ok(Self::Toml),
+            Some(ext) if ext.eq_ignore_ascii_case("json") => Ok(Self::Json),
+            _ => bail!(
+                "Unsupported tag database format: {} (expected .toml or .json)",
+                path.display()
+            ),
+        }
+    }
+}
+
+impl TagStore {
+    /// Loads the store from `path`, returning an empty store if the file does not exist.
+    pub fn load(path: &Path) -> Result<Self> {
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+        Self::read(path)
+    }
+
+    pub fn save(&self, path: &Path) -> Result<()> {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)
+                .with_context(|| format!("Failed to create directory: {}", parent.display()))?;
+        }
+        self.export(path)
+    }
+
+    /// Merges tags from a TOML or JSON file into this store.
+    pub fn import(&mut self, path: &Path) -> Result<usize> {
+        let other = Self::read(path)?;
+        let count = other.wallpapers.len();
+        for (name, tags) in other.wallpapers {
+            self.wallpapers.entry(name).or_default().extend(tags);
+        }
+        Ok(count)
+    }
+
+    /// Writes the store to a TOML or JSON file, chosen by extension.
+    pub fn export(&self, path: &Path) -> Result<()> {
+        let content = match TagFormat::from_path(path)? {
+            TagFormat::Toml => toml::to_string_pretty(self)?,
+            TagFormat::Json => serde_json::to_string_pretty(self)?,
+        };
+        fs::write(path, content)
+            .with_context(|| format!("Failed to write tag database: {}", path.display()))
+    }
+
+    fn read(path: &Path) -> Result<Self> {
+        let format = TagFormat::from_path(path)?;
+        let content = fs::read_to_string(path)
+            .with_context(|| format!("Failed to read tag database: {}", path.display()))?;
+        let store = match format {
+            TagFormat::Toml => toml::from_str(&content)
+                .with_context(|| format!("Failed to parse tag database: {}", path.display()))?,
+            TagFormat::Json => serde_json::from_str(&content)
+                .with_context(|| format!("Failed to parse tag database: {}", path.display()))?,
+        };
+        Ok(store)
+    }
+
+    pub fn tags_for(&self, name: &str) -> impl Iterator<Item = &String> {
+        self.wallpapers.get(name).into_iter().flatten()
+    }
+
+    /// Replaces the tags of a wallpaper; an empty set removes its entry.
+    pub fn set_tags(&mut self, name: &str, tags: BTreeSet<String>) {
+        if tags.is_empty() {
+            self.wallpapers.remove(name);
+        } else {
+            self.wallpapers.insert(name.to_string(), tags);
+        }
+    }
+
+    /// Moves the tags of a renamed wallpaper to its new name.
+    pub fn rename(&mut self, from: &str, to: &str) {
+        if let Some(tags) = self.wallpapers.remove(from) {
+            self.wallpapers.insert(to.to_string(), tags);
+        }
+    }
+
+    pub fn has_all(&self, name: &str, tags: &BTreeSet<String>) -> bool {
+        if tags.is_empty() {
+            return true;
+        }
+        self.wallpapers
+            .get(name)
+            .is_some_and(|assigned| tags.is_subset(assigned))
+    }
+
+    /// Number of wallpapers carrying each tag.
+    pub fn counts(&self) -> BTreeMap<String, usize> {
+        let mut counts = BTreeMap::new();
+        for tag in self.wallpapers.values().flatten() {
+            *counts.entry(tag.clone()).or_insert(0) += 1;
+        }
+        counts
+    }
+}
+
+/// Parses a comma or whitespace separated list of tags into normalized, lowercase tags.
+pub fn parse_tags(input: &str) -> BTreeSet<String> {
+    input
+        .split(|c: char| c == ',' || c.is_whitespace())
+        .map(|tag| tag.trim().to_lowercase())
+        .filter(|tag| !tag.is_empty())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    fn sample_store() -> TagStore {
+        let mut store = TagStore::default();
+        store.set_tags("a.jpg", parse_tags("dark, minimal"));
+        store.set_tags("b.png", parse_tags("dark work"));
+        store
+    }
+
+    #[test]
+    fn test_parse_tags() {
+        let tags = parse_tags(" Dark,minimal  work,, ");
+        assert_eq!(tags.len(), 3);
+        assert!(tags.contains("dark"));
+        assert!(tags.contains("minimal"));
+        assert!(tags.contains("work"));
+        assert!(parse_tags("  ").is_empty());
+    }
+
+    #[test]
+    fn test_counts_and_filter() {
+        let mut store = sample_store();
+        let counts = store.counts();
+        assert_eq!(counts.get("dark"), Some(&2));
+        assert_eq!(counts.get("minimal"), Some(&1));
+
+        assert!(store.has_all("a.jpg", &parse_tags("dark minimal")));
+        assert!(!store.has_all("b.png", &parse_tags("dark minimal")));
+        assert!(store.has_all("c.jpg", &BTreeSet::new()));
+
+        store.set_tags("a.jpg", BTreeSet::new());
+        assert!(!store.wallpapers.contains_key("a.jpg"));
+    }
+
+    #[test]
+    fn test_export_import_roundtrip() {
+        let dir = tempdir().unwrap();
+        let store = sample_store();
+
+        for file in ["tags.toml", "tags.json"] {
+            let path = dir.path().join(file);
+            store.export(&path).unwrap();
+
+            let mut imported = TagStore::default();
+            assert_eq!(imported.import(&path).unwrap(), 2);
+            assert_eq!(imported, store);
+        }
+
+        assert!(store.export(&dir.path().join("tags.yaml")).is_err());
+    }
+
+    #[test]
+    fn test_load_missing_file() {
+        let dir = tempdir().unwrap();
+        let store = TagStore::load(&dir.path().join("missing.toml")).unwrap();
+        assert!(store.wallpapers.is_empty());
+    }
+}
@@ -0,0 +1,273 @@
+use std::fmt;
+
+use anyhow::{Result, bail};
+use iced::Color;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    config::ThemeConfig,
+    palette::{Palette, hex, hue_saturation, luminance},
+};
+
+pub const DEFAULT_ACCENT: HexColor = HexColor([0xff, 0x72, 0x00]);
+pub const DEFAULT_PLACEHOLDER: HexColor = HexColor([0xf0, 0xf0, 0xf0]);
+pub const DEFAULT_BORDER_WIDTH: f32 = 3.0;
+pub const DEFAULT_BORDER_RADIUS: f32 = 0.0;
+pub const DEFAULT_FONT_SIZE: f32 = 16.0;
+
+/// A color written as `#rrggbb` in the config.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(try_from = "String", into = "String")]
+pub struct HexColor(pub [u8; 3]);
+
+impl HexColor {
+    pub fn color(self) -> Color {
+        let [r, g, b] = self.0;
+        Color::from_rgb8(r, g, b)
+    }
+}
+
+impl TryFrom<String> for HexColor {
+    type Error = anyhow::Error;
+
+    fn try_from(value: String) -> Result<Self> {
+        let digits = value.strip_prefix('#').unwrap_or(&value);
+        if digits.len() != 6 || !digits.chars().all(|c| c.is_ascii_hexdigit()) {
+            bail!("Invalid color '{}' (expected #rrggbb)", value);
+        }
+        let channel = |idx: usize| u8::from_str_radix(&digits[idx..idx + 2], 16);
+        Ok(Self([channel(0)?, channel(2)?, channel(4)?]))
+    }
+}
+
+impl From<HexColor> for String {
+    fn from(color: HexColor) -> Self {
+        hex(color.0)
+    }
+}
+
+/// The color of the selection and other highlights.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(try_from = "String", into = "String")]
+pub enum Accent {
+    Color(HexColor),
+    /// Taken from the palette of the applied wallpaper.
+    Wallpaper,
+}
+
+impl TryFrom<String> for Accent {
+    type Error = anyhow::Error;
+
+    fn try_from(value: String) -> Result<Self> {
+        if value == "wallpaper" {
+            return Ok(Self::Wallpaper);
+        }
+        HexColor::try_from(value)
+            .map(Self::Color)
+            .map_err(|_| anyhow::anyhow!("Invalid accent (expected #rrggbb or wallpaper)"))
+    }
+}
+
+impl From<Accent> for String {
+    fn from(accent: Accent) -> Self {
+        match accent {
+            Accent::Color(color) => color.into(),
+            Accent::Wallpaper => String::from("wallpaper"),
+        }
+    }
+}
+
+/// One of iced's built-in themes, named in kebab case, e.g. `catppuccin-mocha`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+#[serde(try_from = "String", into = "String")]
+pub struct BaseTheme(pub iced::Theme);
+
+impl fmt::Display for BaseTheme {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0.to_string().to_lowercase().replace(' ', "-"))
+    }
+}
+
+impl TryFrom<String> for BaseTheme {
+    type Error = anyhow::Error;
+
+    fn try_from(value: String) -> Result<Self> {
+        iced::Theme::ALL
+            .iter()
+            .map(|theme| Self(theme.clone()))
+            .find(|theme| theme.to_string() == value)
+            .ok_or_else(|| {
+                anyhow::anyhow!(
+                    "Unknown theme '{}' (expected e.g. dark, light or nord)",
+                    value
+                )
+            })
+    }
+}
+
+impl From<BaseTheme> for String {
+    fn from(theme: BaseTheme) -> Self {
+        theme.to_string()
+    }
+}
+
+/// The `[theme]` section with its defaults filled in, as the browser draws it.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Theme {
+    pub accent: Color,
+    pub background: Option<Color>,
+    pub placeholder: HexColor,
+    pub border_width: f32,
+    pub border_radius: f32,
+    pub font_size: f32,
+    /// The base theme with the accent and background applied, `None` to
+    /// follow the system.
+    iced: Option<iced::Theme>,
+}
+
+impl Theme {
+    /// `wallpaper_accent` is used when the accent comes from the wallpaper,
+    /// until then the default accent is.
+    pub fn new(config: &ThemeConfig, wallpaper_accent: Option<[u8; 3]>) -> Self {
+        let accent = match config.accent {
+            Some(Accent::Color(color)) => color,
+            Some(Accent::Wallpaper) => wallpaper_accent.map_or(DEFAULT_ACCENT, HexColor),
+            None => DEFAULT_ACCENT,
+        }
+        .color();
+        let background = config.background.map(HexColor::color);
+
+        let iced = config.base.as_ref().map(|base| {
+            let mut palette = base.0.palette();
+            palette.primary = accent;
+            if let Some(background) = background {
+                palette.background = background;
+            }
+            iced::Theme::custom(base.to_string(), palette)
+        });
+
+        Self {
+            accent,
+            background,
+            placeholder: config.placeholder.unwrap_or(DEFAULT_PLACEHOLDER),
+            border_width: config.border_width.unwrap_or(DEFAULT_BORDER_WIDTH),
+            border_radius: config.border_radius.unwrap_or(DEFAULT_BORDER_RADIUS),
+            font_size: config.font_size.unwrap_or(DEFAULT_FONT_SIZE),
+            iced,
+        }
+    }
+
+    pub fn iced(&self) -> Option<iced::Theme> {
+        self.iced.clone()
+    }
+
+    /// Size of titles and the footer.
+    pub fn heading_size(&self) -> f32 {
+        self.font_size
+    }
+
+    /// Size of list entries and buttons.
+    pub fn body_size(&self) -> f32 {
+        (self.font_size - 2.0).max(1.0)
+    }
+
+    /// Size of hints and details.
+    pub fn caption_size(&self) -> f32 {
+        (self.font_size - 4.0).max(1.0)
+    }
+}
+
+/// The most colorful of the palette's colors that is neither too dark nor too
+/// light to stand out, or its most common color if none is.
+pub fn accent_of(palette: &Palette) -> Option<[u8; 3]> {
+    palette
+        .colors
+        .iter()
+        .copied()
+        .filter(|&color| (0.15..=0.85).contains(&luminance(color)))
+        .max_by(|&a, &b| hue_saturation(a).1.total_cmp(&hue_saturation(b).1))
+        .or_else(|| palette.colors.first().copied())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_colors() {
+        assert_eq!(
+            HexColor::try_from("#1e1e2e".to_string()).unwrap(),
+            HexColor([0x1e, 0x1e, 0x2e])
+        );
+        assert_eq!(String::from(HexColor([255, 114, 0])), "#ff7200");
+        assert!(HexColor::try_from("#fff".to_string()).is_err());
+        assert!(HexColor::try_from("orange".to_string()).is_err());
+
+        assert_eq!(
+            Accent::try_from("wallpaper".to_string()).unwrap(),
+            Accent::Wallpaper
+        );
+        assert_eq!(
+            Accent::try_from("#ff7200".to_string()).unwrap(),
+            Accent::Color(DEFAULT_ACCENT)
+        );
+    }
+
+    #[test]
+    fn test_parse_base_theme() {
+        let theme = BaseTheme::try_from("catppuccin-mocha".to_string()).unwrap();
+        assert_eq!(theme.0, iced::Theme::CatppuccinMocha);
+        assert_eq!(theme.to_string(), "catppuccin-mocha");
+        assert!(BaseTheme::try_from("Dark".to_string()).is_err());
+    }
+
+    #[test]
+    fn test_theme_defaults() {
+        let theme = Theme::new(&ThemeConfig::default(), None);
+        assert_eq!(theme.accent, DEFAULT_ACCENT.color());
+        assert_eq!(theme.border_width, DEFAULT_BORDER_WIDTH);
+        assert_eq!(theme.body_size(), 14.0);
+        assert!(theme.iced().is_none());
+    }
+
+    #[test]
+    fn test_theme_from_config() {
+        let config = ThemeConfig {
+            base: Some(BaseTheme(iced::Theme::Nord)),
+            accent: Some(Accent::Wallpaper),
+            background: Some(HexColor([0x10, 0x10, 0x10])),
+            ..ThemeConfig::default()
+        };
+
+        // The default accent stands in until the wallpaper's is known
+        let theme = Theme::new(&config, None);
+        assert_eq!(theme.accent, DEFAULT_ACCENT.color());
+
+        let theme = Theme::new(&config, Some([0x20, 0x80, 0xd0]));
+        assert_eq!(theme.accent, Color::from_rgb8(0x20, 0x80, 0xd0));
+        let palette = theme.iced().unwrap().palette();
+        assert_eq!(palette.primary, theme.accent);
+        assert_eq!(palette.background, Color::from_rgb8(0x10, 0x10, 0x10));
+        assert_eq!(palette.text, iced::Theme::Nord.palette().text);
+    }
+
+    #[test]
+    fn test_accent_of() {
+        let palette = Palette {
+            colors: vec![
+                [10, 10, 10],
+                [120, 120, 120],
+                [200, 60, 40],
+                [250, 250, 250],
+            ],
+        };
+        assert_eq!(accent_of(&palette), Some([200, 60, 40]));
+
+        let palette = Palette {
+            colors: vec![[0, 0, 0], [255, 255, 255]],
+        };
+        assert_eq!(accent_of(&palette), Some([0, 0, 0]));
+        assert_eq!(accent_of(&Palette { colors: Vec::new() }), None);
+    }
+}
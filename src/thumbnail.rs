@@ -1,22 +1,141 @@
 use std::{
     collections::HashSet,
     fs::{self},
-    path::Path,
+    path::{Path, PathBuf},
 };
 
-use crate::config::CONFIG;
+use crate::{
+    animation::{AnimationInfo, animation_info},
+    config::CONFIG,
+    format::open_image,
+    framing::Framing,
+    palette::ColorInfo,
+};
+
+use anyhow::Context;
+use image::{ImageFormat, imageops::FilterType};
+use serde::{Deserialize, Serialize};
 
-use image::ImageFormat;
+/// Version of the way thumbnails are made. Bumping it regenerates every
+/// cached thumbnail, e.g. after they started following EXIF orientation or
+/// were named by the whole file name.
+const CACHE_VERSION: u32 = 3;
 
-pub fn gen_thumbnail(input: &Path, output: &Path) -> anyhow::Result<()> {
-    let img = image::open(input)?;
-    let thumb = img.thumbnail(320, 150);
+/// File in the thumbnail directory holding the `CACHE_VERSION` its
+/// thumbnails were made with.
+const VERSION_FILE: &str = "version";
+
+/// What is learned about a wallpaper while its thumbnail is generated. It is
+/// stored next to the thumbnail, so later scans don't decode anything.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct ThumbnailInfo {
+    pub color: ColorInfo,
+    /// Frame count and running time, for animated wallpapers.
+    pub animation: Option<AnimationInfo>,
+}
+
+/// Writes the thumbnail along with its info, and returns the info. The
+/// thumbnail shows the wallpaper the way `framing` cuts it; with a focus
+/// point it is filled around that point, as on an output.
+pub fn gen_thumbnail(
+    input: &Path,
+    output: &Path,
+    framing: &Framing,
+) -> anyhow::Result<ThumbnailInfo> {
+    let img = open_image(input)?;
+    let thumb = if framing.focus.is_some() {
+        framing.fill(&img, 320, 150, FilterType::Triangle)
+    } else {
+        framing.crop(&img).thumbnail(320, 150)
+    };
     thumb.save_with_format(output, ImageFormat::Jpeg)?;
-    Ok(())
+
+    let info = ThumbnailInfo {
+        color: ColorInfo::of(&thumb),
+        animation: animation_info(input),
+    };
+    save_info(output, &info)?;
+    Ok(info)
+}
+
+/// The thumbnail of `img_path` in `dir`. It is named by the whole file name,
+/// so `sunset.png` and `sunset.svg` get one each.
+pub fn thumbnail_path(dir: &Path, img_path: &Path) -> PathBuf {
+    let name = img_path.file_name().unwrap_or_default().to_string_lossy();
+    dir.join(format!("{}.jpeg", name))
+}
+
+/// The file storing the info of `thumbnail`.
+pub fn info_path(thumbnail: &Path) -> PathBuf {
+    thumbnail.with_extension("json")
+}
+
+/// Info of the already generated thumbnail of `input`. Thumbnails from before
+/// the info was stored get it computed once and saved.
+pub fn thumbnail_info(input: &Path, thumbnail: &Path) -> Option<ThumbnailInfo> {
+    if let Some(info) = stored_info(thumbnail) {
+        return Some(info);
+    }
+
+    let info = ThumbnailInfo {
+        color: ColorInfo::of(&image::open(thumbnail).ok()?),
+        animation: animation_info(input),
+    };
+    if let Err(e) = save_info(thumbnail, &info) {
+        eprintln!("{:#}", e);
+    }
+    Some(info)
+}
+
+/// Info stored next to `thumbnail`, without computing it when there's none.
+pub fn stored_info(thumbnail: &Path) -> Option<ThumbnailInfo> {
+    let content = fs::read_to_string(info_path(thumbnail)).ok()?;
+    serde_json::from_str(&content).ok()
+}
+
+fn save_info(thumbnail: &Path, info: &ThumbnailInfo) -> anyhow::Result<()> {
+    let path = info_path(thumbnail);
+    fs::write(&path, serde_json::to_string(info)?)
+        .with_context(|| format!("Failed to write {}", path.display()))
+}
+
+/// Removes the thumbnails in `dir` that were made by an older version, so
+/// they get generated again.
+pub fn clear_outdated(dir: &Path) {
+    let version_file = dir.join(VERSION_FILE);
+    let version = fs::read_to_string(&version_file)
+        .ok()
+        .and_then(|version| version.trim().parse::<u32>().ok());
+    if version == Some(CACHE_VERSION) {
+        return;
+    }
+
+    if let Ok(entries) = fs::read_dir(dir) {
+        for entry in entries.flatten() {
+            let path = entry.path();
+            if matches!(
+                path.extension().and_then(|e| e.to_str()),
+                Some("jpeg" | "json")
+            ) && let Err(e) = fs::remove_file(&path)
+            {
+                eprintln!(
+                    "Failed to remove outdated thumbnail {}: {}",
+                    path.display(),
+                    e
+                );
+            }
+        }
+    }
+    if let Err(e) = fs::create_dir_all(dir)
+        .and_then(|()| fs::write(&version_file, format!("{}\n", CACHE_VERSION)))
+    {
+        eprintln!("Failed to write {}: {}", version_file.display(), e);
+    }
 }
 
 pub fn list_thumbnails() -> HashSet<String> {
-    let Some(ref path) = CONFIG.cache_path else {
+    let config = CONFIG.load();
+    let Some(ref path) = config.cache_path else {
         return HashSet::new();
     };
 
@@ -44,9 +163,10 @@ fn list_thumbnails_from_path(path: &Path) -> HashSet<String> {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use tempfile::tempdir;
+    use crate::framing::{FocusPoint, Region};
+    use image::{ImageFormat, RgbImage};
     use std::fs::File;
-    use image::{RgbImage, ImageFormat};
+    use tempfile::tempdir;
 
     #[test]
     fn test_gen_thumbnail() {
@@ -58,9 +178,33 @@ mod tests {
         let img = RgbImage::new(100, 100);
         img.save_with_format(&input_path, ImageFormat::Png).unwrap();
 
-        let result = gen_thumbnail(&input_path, &output_path);
+        let result = gen_thumbnail(&input_path, &output_path, &Framing::default());
         assert!(result.is_ok());
+        let result = result.ok();
         assert!(output_path.exists());
+        assert!(info_path(&output_path).exists());
+        assert_eq!(thumbnail_info(&input_path, &output_path), result);
+
+        // The stored info is read back rather than computed from the thumbnail
+        let stored = ThumbnailInfo {
+            color: ColorInfo {
+                hue: 120.0,
+                saturation: 0.5,
+                luminance: 0.5,
+            },
+            animation: None,
+        };
+        fs::write(
+            info_path(&output_path),
+            serde_json::to_string(&stored).unwrap(),
+        )
+        .unwrap();
+        assert_eq!(thumbnail_info(&input_path, &output_path), Some(stored));
+
+        // and computed once for thumbnails from before it was stored
+        fs::remove_file(info_path(&output_path)).unwrap();
+        assert_eq!(thumbnail_info(&input_path, &output_path), result);
+        assert!(info_path(&output_path).exists());
 
         // Verify it's a valid image
         let thumb = image::open(&output_path).unwrap();
@@ -69,17 +213,73 @@ mod tests {
         assert_eq!(thumb.height(), 150);
     }
 
+    #[test]
+    fn test_gen_thumbnail_with_framing() {
+        let dir = tempdir().unwrap();
+        let input_path = dir.path().join("input.png");
+        let output_path = dir.path().join("output.jpeg");
+        RgbImage::new(400, 100)
+            .save_with_format(&input_path, ImageFormat::Png)
+            .unwrap();
+
+        let cropped = Framing {
+            crop: Some(Region {
+                x: 0.0,
+                y: 0.0,
+                width: 0.25,
+                height: 1.0,
+            }),
+            focus: None,
+        };
+        gen_thumbnail(&input_path, &output_path, &cropped).unwrap();
+        let thumb = image::open(&output_path).unwrap();
+        assert_eq!((thumb.width(), thumb.height()), (150, 150));
+
+        let focused = Framing {
+            crop: None,
+            focus: Some(FocusPoint { x: 0.1, y: 0.5 }),
+        };
+        gen_thumbnail(&input_path, &output_path, &focused).unwrap();
+        let thumb = image::open(&output_path).unwrap();
+        assert_eq!((thumb.width(), thumb.height()), (320, 150));
+    }
+
+    #[test]
+    fn test_clear_outdated() {
+        let dir = tempdir().unwrap();
+        File::create(dir.path().join("old.jpeg")).unwrap();
+        File::create(dir.path().join("old.json")).unwrap();
+        File::create(dir.path().join("notes.txt")).unwrap();
+
+        // Thumbnails from before the cache was versioned are removed
+        clear_outdated(dir.path());
+        assert!(!dir.path().join("old.jpeg").exists());
+        assert!(!dir.path().join("old.json").exists());
+        assert!(dir.path().join("notes.txt").exists());
+
+        // Current ones are kept
+        File::create(dir.path().join("new.jpeg")).unwrap();
+        clear_outdated(dir.path());
+        assert!(dir.path().join("new.jpeg").exists());
+
+        fs::write(dir.path().join(VERSION_FILE), "1").unwrap();
+        clear_outdated(dir.path());
+        assert!(!dir.path().join("new.jpeg").exists());
+    }
+
     #[test]
     fn test_list_thumbnails_from_path() {
         let dir = tempdir().unwrap();
-        File::create(dir.path().join("thumb1.jpeg")).unwrap();
-        File::create(dir.path().join("thumb2.jpeg")).unwrap();
+        for img_path in ["a/sunset.png", "b/sunset.svg"] {
+            File::create(thumbnail_path(dir.path(), Path::new(img_path))).unwrap();
+        }
         File::create(dir.path().join("not_a_thumb.txt")).unwrap();
 
+        // Wallpapers sharing a stem have a thumbnail each, listed by file name
         let thumbnails = list_thumbnails_from_path(dir.path());
         assert_eq!(thumbnails.len(), 2);
-        assert!(thumbnails.contains("thumb1"));
-        assert!(thumbnails.contains("thumb2"));
+        assert!(thumbnails.contains("sunset.png"));
+        assert!(thumbnails.contains("sunset.svg"));
         assert!(!thumbnails.contains("not_a_thumb"));
     }
 }
@@ -0,0 +1,80 @@
+use std::path::PathBuf;
+
+use iced::{Task, widget::image as iced_image};
+
+use crate::{
+    message::Message,
+    palette::Palette,
+    theme::{Accent, Theme, accent_of},
+};
+
+use super::AppView;
+
+impl AppView {
+    /// The iced theme the window is drawn with, `None` to follow the system.
+    pub fn theme(&self) -> Option<iced::Theme> {
+        self.theme.iced()
+    }
+
+    pub fn style(&self, theme: &iced::Theme) -> iced::theme::Style {
+        let mut style = iced::theme::default(theme);
+        if let Some(background) = self.theme.background {
+            style.background_color = background;
+        }
+        style
+    }
+
+    /// Rebuilds the theme from the current config, e.g. after a reload or
+    /// once the wallpaper's accent is known.
+    pub(super) fn update_theme(&mut self) {
+        self.theme = Theme::new(&self.config.theme, self.wallpaper_accent);
+        self.placeholder_handle = placeholder_handle(&self.theme);
+    }
+
+    /// The wallpaper applied last, as linked from `current_wallpaper`.
+    pub(super) fn applied_wallpaper(&self) -> Option<PathBuf> {
+        self.config
+            .current_wallpaper
+            .clone()
+            .filter(|path| path.exists())
+    }
+
+    /// Takes the accent from the palette of `img_path`, if the config asks
+    /// for the wallpaper's accent.
+    pub(super) fn load_wallpaper_accent(&self, img_path: Option<PathBuf>) -> Task<Message> {
+        let Some(img_path) =
+            img_path.filter(|_| self.config.theme.accent == Some(Accent::Wallpaper))
+        else {
+            return Task::none();
+        };
+        let count = self.config.palette.colors.unwrap_or(8).max(1);
+        let cache_dir = self.config.cache_subdir("palettes");
+
+        Task::perform(
+            async move {
+                // Shares the colors `apply` exports, so they're only extracted once
+                match &cache_dir {
+                    Some(cache_dir) => Palette::cached(&img_path, count, cache_dir),
+                    None => Palette::extract(&img_path, count),
+                }
+                .map_err(|e| eprintln!("Failed to read colors of {}: {:#}", img_path.display(), e))
+                .ok()
+                .and_then(|palette| accent_of(&palette))
+            },
+            |accent| accent.map(Message::AccentLoaded),
+        )
+        .and_then(Task::done)
+    }
+
+    pub(super) fn accent_loaded(&mut self, accent: [u8; 3]) -> Task<Message> {
+        self.wallpaper_accent = Some(accent);
+        self.update_theme();
+        Task::none()
+    }
+}
+
+/// Single pixel image shown stretched in place of thumbnails still loading.
+pub(super) fn placeholder_handle(theme: &Theme) -> iced_image::Handle {
+    let [r, g, b] = theme.placeholder.0;
+    iced_image::Handle::from_rgba(1, 1, vec![r, g, b, 255])
+}
@@ -0,0 +1,107 @@
+use iced::{
+    Alignment, Border, Color, Element, Length,
+    widget::{button, column, container, row, text},
+};
+
+use crate::{
+    config::{ConfigReport, Diagnostic, Severity},
+    message::Message,
+};
+
+use super::AppView;
+
+/// Diagnostics listed in the banner before the rest are summarized.
+const MAX_SHOWN: usize = 4;
+
+impl AppView {
+    /// Problems with the config file across the top of the window, until dismissed.
+    pub(super) fn config_banner(&self) -> Option<Element<'_, Message>> {
+        let report = self.config_report.as_ref()?;
+        let has_errors = report.has_errors();
+
+        let mut lines = column![text(summary(report)).size(self.theme.body_size())].spacing(2);
+        for diagnostic in report.diagnostics.iter().take(MAX_SHOWN) {
+            lines = lines.push(diagnostic_line(diagnostic, self.theme.caption_size()));
+        }
+        if report.diagnostics.len() > MAX_SHOWN {
+            lines = lines.push(
+                text(format!(
+                    "...and {} more, run `wallity config check` to see them all",
+                    report.diagnostics.len() - MAX_SHOWN
+                ))
+                .size(self.theme.caption_size()),
+            );
+        }
+
+        let severity = if has_errors {
+            Severity::Error
+        } else {
+            Severity::Warning
+        };
+        Some(
+            container(
+                row![
+                    container(lines).width(Length::Fill),
+                    button(text("Dismiss").size(self.theme.caption_size()))
+                        .style(button::text)
+                        .on_press(Message::ConfigBannerDismissed),
+                ]
+                .align_y(Alignment::Center),
+            )
+            .width(Length::Fill)
+            .padding([6, 10])
+            .style(move |theme| {
+                let accent = severity_color(theme, severity);
+                container::Style {
+                    text_color: Some(theme.palette().text),
+                    background: Some(accent.scale_alpha(0.25).into()),
+                    border: Border {
+                        color: accent,
+                        width: 1.0,
+                        radius: 0.0.into(),
+                    },
+                    ..container::Style::default()
+                }
+            })
+            .into(),
+        )
+    }
+}
+
+fn severity_color(theme: &iced::Theme, severity: Severity) -> Color {
+    match severity {
+        Severity::Error => theme.palette().danger,
+        Severity::Warning => theme.palette().warning,
+    }
+}
+
+fn diagnostic_line(diagnostic: &Diagnostic, size: f32) -> Element<'_, Message> {
+    let location = diagnostic
+        .location
+        .map(|(line, column)| format!("{}:{} ", line, column))
+        .unwrap_or_default();
+    let severity = diagnostic.severity;
+    row![
+        text(format!("{}{}:", location, severity))
+            .size(size)
+            .style(move |theme| text::Style {
+                color: Some(severity_color(theme, severity)),
+            }),
+        text(&diagnostic.message).size(size),
+    ]
+    .spacing(6)
+    .into()
+}
+
+fn summary(report: &ConfigReport) -> String {
+    let path = report.path.display();
+    if report.has_errors() {
+        format!("{} can't be used until its errors are fixed", path)
+    } else {
+        format!(
+            "{} setting(s) in {}, the environment or --set are ignored",
+            report.count(Severity::Warning),
+            path
+        )
+    }
+}
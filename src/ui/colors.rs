@@ -0,0 +1,78 @@
+use iced::{
+    Border, Color, Element, Length, Task,
+    widget::{button, container, row, text},
+};
+
+use crate::{message::Message, palette::Swatch};
+
+use super::AppView;
+
+impl AppView {
+    pub(super) fn toggle_color_filter(&mut self, swatch: Swatch) -> Task<Message> {
+        self.color_filter = if self.color_filter == Some(swatch) {
+            None
+        } else {
+            Some(swatch)
+        };
+        self.refilter()
+    }
+
+    pub(super) fn toggle_sort_by_hue(&mut self) -> Task<Message> {
+        self.sort_by_hue = !self.sort_by_hue;
+        self.refilter()
+    }
+
+    /// Hiding the swatch row also drops its filter, so nothing stays filtered
+    /// out without a visible reason.
+    pub(super) fn toggle_swatches(&mut self) -> Task<Message> {
+        self.show_swatches = !self.show_swatches;
+        if !self.show_swatches && self.color_filter.take().is_some() {
+            return self.refilter();
+        }
+        Task::none()
+    }
+
+    pub(super) fn color_filter_row(&self) -> Element<'_, Message> {
+        let mut swatches = row![].spacing(6);
+        let (accent, border_width) = (self.theme.accent, self.theme.border_width);
+
+        for swatch in Swatch::ALL {
+            let [r, g, b] = swatch.color();
+            let selected = self.color_filter == Some(swatch);
+            swatches = swatches.push(
+                button(container(text("")).width(22).height(22))
+                    .padding(0)
+                    .style(move |theme: &iced::Theme, _status| button::Style {
+                        background: Some(Color::from_rgb8(r, g, b).into()),
+                        border: Border {
+                            color: if selected {
+                                accent
+                            } else {
+                                theme.extended_palette().background.strong.color
+                            },
+                            width: if selected { border_width } else { 1.0 },
+                            radius: 4.0.into(),
+                        },
+                        ..button::Style::default()
+                    })
+                    .on_press(Message::ColorFilterToggled(swatch)),
+            );
+        }
+
+        let sort_style = if self.sort_by_hue {
+            button::primary
+        } else {
+            button::text
+        };
+        swatches = swatches.push(
+            button(text("Sort by hue").size(self.theme.body_size()))
+                .style(sort_style)
+                .on_press(Message::SortByHueToggled),
+        );
+
+        container(swatches)
+            .width(Length::Fill)
+            .padding([5, 10])
+            .into()
+    }
+}
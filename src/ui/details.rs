@@ -0,0 +1,129 @@
+use iced::{
+    Element, Length, Task,
+    widget::{Column, column, container, scrollable, text},
+};
+
+use crate::{
+    message::Message,
+    metadata::{Metadata, format_size},
+};
+
+use super::AppView;
+
+impl AppView {
+    pub(super) fn toggle_details(&mut self) -> Task<Message> {
+        self.show_details = !self.show_details;
+        self.load_details()
+    }
+
+    /// Reads the selected wallpaper's details in the background, unless the
+    /// panel is hidden or they were already asked for.
+    pub(super) fn load_details(&mut self) -> Task<Message> {
+        if !self.show_details {
+            return Task::none();
+        }
+        let Some(image) = self.selected_image() else {
+            return Task::none();
+        };
+        if self
+            .details
+            .as_ref()
+            .is_some_and(|(path, _)| *path == image.img_path)
+        {
+            return Task::none();
+        }
+
+        let img_path = image.img_path.clone();
+        let thumbnail_path = image.thumbnail_path.clone();
+        self.details = Some((img_path.clone(), None));
+        Task::perform(
+            async move { Metadata::read(&img_path, &thumbnail_path) },
+            |metadata| Message::DetailsLoaded(Box::new(metadata)),
+        )
+    }
+
+    pub(super) fn details_loaded(&mut self, metadata: Metadata) -> Task<Message> {
+        // Details of a wallpaper that is no longer selected are dropped
+        if let Some((ref path, ref mut details)) = self.details
+            && *path == metadata.path
+        {
+            *details = Some(metadata);
+        }
+        Task::none()
+    }
+
+    pub(super) fn details_panel(&self) -> Element<'_, Message> {
+        let mut lines = column![text("Details").size(self.theme.heading_size())].spacing(8);
+
+        let loaded = self.details.as_ref().and_then(|(path, details)| {
+            details.as_ref().filter(|_| {
+                self.selected_image()
+                    .is_some_and(|img| img.img_path == *path)
+            })
+        });
+        let Some(metadata) = loaded else {
+            let status = if self.selected_image().is_some() {
+                "Reading..."
+            } else {
+                "Nothing selected"
+            };
+            return panel(lines.push(text(status).size(self.theme.body_size())));
+        };
+
+        let mut fields = vec![("Path", metadata.path.display().to_string())];
+        if let Some(format) = metadata.format {
+            fields.push(("Format", format.name().to_string()));
+        }
+        if let Some((width, height)) = metadata.dimensions {
+            fields.push(("Dimensions", format!("{} × {}", width, height)));
+        }
+        if let Some(size) = metadata.file_size {
+            fields.push(("File size", format_size(size)));
+        }
+        if let Some(modified) = metadata.modified {
+            fields.push(("Modified", modified.format("%Y-%m-%d %H:%M").to_string()));
+        }
+        if let Some(ref camera) = metadata.camera {
+            fields.push(("Camera", camera.clone()));
+        }
+        if let Some(ref taken) = metadata.taken {
+            fields.push(("Taken", taken.clone()));
+        }
+        if let Some(ref profile) = metadata.color_profile {
+            fields.push(("Color profile", profile.clone()));
+        }
+        if let Some(animation) = metadata.animation {
+            fields.push(("Animation", animation.to_string()));
+        }
+        let thumbnail = match metadata.thumbnail_size {
+            Some(size) => format!(
+                "{} ({})",
+                metadata.thumbnail_path.display(),
+                format_size(size)
+            ),
+            None => format!("{} (not generated yet)", metadata.thumbnail_path.display()),
+        };
+        fields.push(("Thumbnail", thumbnail));
+
+        for (label, value) in fields {
+            lines = lines.push(
+                column![
+                    text(label)
+                        .size(self.theme.caption_size())
+                        .style(text::secondary),
+                    text(value).size(self.theme.body_size()),
+                ]
+                .spacing(2),
+            );
+        }
+        panel(lines)
+    }
+}
+
+fn panel<'a>(lines: Column<'a, Message>) -> Element<'a, Message> {
+    container(scrollable(lines))
+        .width(Length::Fixed(240.0))
+        .height(Length::Fill)
+        .padding(10)
+        .into()
+}
@@ -0,0 +1,385 @@
+use std::path::{Path, PathBuf};
+
+use iced::{
+    Element, Length, Task, clipboard,
+    keyboard::key,
+    widget::{button, column, container, operation, text, text_input},
+};
+
+use crate::{
+    files::{
+        FileAction, copy_wallpaper, move_wallpaper, rename_wallpaper, reveal, trash_wallpaper,
+    },
+    message::Message,
+    util::get_absolute_path,
+};
+
+use super::{AppView, Mode};
+
+pub(super) const FILE_INPUT_ID: &str = "file-input";
+
+/// Text typed into the rename or move dialog, and why the last attempt failed.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct FileInput {
+    pub(crate) value: String,
+    pub(crate) error: Option<String>,
+}
+
+impl FileInput {
+    fn new(value: String) -> Self {
+        Self { value, error: None }
+    }
+}
+
+impl AppView {
+    /// Selects the wallpaper at `pos` and lists what can be done with it.
+    pub(super) fn open_file_menu(&mut self, pos: usize) -> Task<Message> {
+        if self.image_idx(pos).is_none() {
+            return Task::none();
+        }
+        self.selected_idx = pos;
+        self.mode = Mode::FileMenu(0);
+        Task::none()
+    }
+
+    pub(super) fn handle_file_menu_key(&mut self, key: key::Key) -> Task<Message> {
+        let Mode::FileMenu(ref mut choice) = self.mode else {
+            return Task::none();
+        };
+        match key.as_ref() {
+            key::Key::Named(key::Named::ArrowUp) | key::Key::Character("k") => {
+                *choice = choice.saturating_sub(1);
+                Task::none()
+            }
+            key::Key::Named(key::Named::ArrowDown) | key::Key::Character("j") => {
+                *choice = (*choice + 1).min(FileAction::ALL.len() - 1);
+                Task::none()
+            }
+            key::Key::Named(key::Named::Enter) => {
+                Task::done(Message::FileAction(FileAction::ALL[*choice]))
+            }
+            key::Key::Named(key::Named::Escape) => {
+                self.mode = Mode::Browse;
+                Task::none()
+            }
+            _ => Task::none(),
+        }
+    }
+
+    /// Starts `action` on the marked wallpapers, or the one under the cursor
+    /// for actions on a single file.
+    pub(super) fn file_action(&mut self, action: FileAction) -> Task<Message> {
+        self.mode = Mode::Browse;
+        let targets = if action.is_bulk() {
+            self.targets()
+        } else {
+            self.image_idx(self.selected_idx).into_iter().collect()
+        };
+        let Some(&first) = targets.first() else {
+            return Task::none();
+        };
+        let image = &self.images[first];
+
+        match action {
+            FileAction::Rename => {
+                self.mode = Mode::Rename(FileInput::new(image.name.clone()));
+                operation::focus(FILE_INPUT_ID)
+            }
+            FileAction::Move | FileAction::Copy => {
+                let dir = image
+                    .img_path
+                    .parent()
+                    .map(|dir| format!("{}/", dir.display()))
+                    .unwrap_or_default();
+                self.mode = if action == FileAction::Move {
+                    Mode::Move(FileInput::new(dir))
+                } else {
+                    Mode::Copy(FileInput::new(dir))
+                };
+                operation::focus(FILE_INPUT_ID)
+            }
+            FileAction::Trash => {
+                let mut trashed = Vec::new();
+                for idx in targets {
+                    let image = &self.images[idx];
+                    match trash_wallpaper(&image.img_path, &image.thumbnail_path) {
+                        Ok(()) => trashed.push(idx),
+                        Err(e) => eprintln!("{:#}", e),
+                    }
+                }
+                self.clear_selection();
+                self.remove_images(trashed, true)
+            }
+            FileAction::CopyPath => {
+                let paths: Vec<String> = targets
+                    .iter()
+                    .map(|&idx| self.images[idx].img_path.display().to_string())
+                    .collect();
+                self.clear_selection();
+                clipboard::write(paths.join("\n"))
+            }
+            FileAction::Reveal => {
+                if let Err(e) = reveal(&image.img_path) {
+                    eprintln!("{:#}", e);
+                }
+                Task::none()
+            }
+        }
+    }
+
+    pub(super) fn file_input_changed(&mut self, value: String) -> Task<Message> {
+        if let Mode::Rename(ref mut input) | Mode::Move(ref mut input) | Mode::Copy(ref mut input) =
+            self.mode
+        {
+            *input = FileInput::new(value);
+        }
+        Task::none()
+    }
+
+    /// Renames, moves or copies the wallpapers as typed, keeping the dialog
+    /// open with the errors if any of them fail.
+    pub(super) fn confirm_file_input(&mut self) -> Task<Message> {
+        let (action, value) = match self.mode {
+            Mode::Rename(ref input) => (FileAction::Rename, input.value.clone()),
+            Mode::Move(ref input) => (FileAction::Move, input.value.clone()),
+            Mode::Copy(ref input) => (FileAction::Copy, input.value.clone()),
+            _ => return Task::none(),
+        };
+
+        let (task, errors) = if action == FileAction::Rename {
+            self.rename_selected(&value)
+        } else {
+            match get_absolute_path(value.trim()) {
+                Ok(dir) => self.transfer_targets(action, &dir),
+                Err(e) => (Task::none(), vec![format!("{:#}", e)]),
+            }
+        };
+
+        if errors.is_empty() {
+            self.mode = Mode::Browse;
+        } else if let Mode::Rename(ref mut input)
+        | Mode::Move(ref mut input)
+        | Mode::Copy(ref mut input) = self.mode
+        {
+            input.error = Some(errors.join("\n"));
+        }
+        task
+    }
+
+    fn rename_selected(&mut self, name: &str) -> (Task<Message>, Vec<String>) {
+        let Some(idx) = self.image_idx(self.selected_idx) else {
+            return (Task::none(), Vec::new());
+        };
+        let image = &self.images[idx];
+        match rename_wallpaper(&image.img_path, &image.thumbnail_path, name) {
+            Ok((img_path, thumbnail_path)) => {
+                self.rename_image(idx, img_path, thumbnail_path);
+                (Task::done(Message::LoadVisibleThumbnails), Vec::new())
+            }
+            Err(e) => (Task::none(), vec![format!("{:#}", e)]),
+        }
+    }
+
+    /// Moves or copies the targeted wallpapers into `dir`. Ones that fail are
+    /// left marked, so the rest can be tried again.
+    fn transfer_targets(&mut self, action: FileAction, dir: &Path) -> (Task<Message>, Vec<String>) {
+        let mut done = Vec::new();
+        let mut errors = Vec::new();
+        for idx in self.targets() {
+            let image = &self.images[idx];
+            // Moving into its own directory leaves it where it is
+            if action == FileAction::Move && image.img_path.parent() == Some(dir) {
+                continue;
+            }
+            let result = if action == FileAction::Move {
+                move_wallpaper(&image.img_path, &image.thumbnail_path, dir).map(|_| ())
+            } else {
+                copy_wallpaper(&image.img_path, dir).map(|_| ())
+            };
+            match result {
+                Ok(()) => done.push(idx),
+                Err(e) => errors.push(format!("{:#}", e)),
+            }
+        }
+
+        self.visual = None;
+        if errors.is_empty() {
+            self.clear_selection();
+        } else {
+            for &idx in &done {
+                self.marked.remove(&self.images[idx].img_path);
+            }
+        }
+        let task = if action == FileAction::Move {
+            self.remove_images(done, false)
+        } else {
+            Task::none()
+        };
+        (task, errors)
+    }
+
+    /// Points the wallpaper at its new file, carrying its tags and library
+    /// entry over to the new name.
+    fn rename_image(&mut self, idx: usize, img_path: PathBuf, thumbnail_path: PathBuf) {
+        let image = &mut self.images[idx];
+        let old_name = std::mem::take(&mut image.name);
+        image.name = img_path
+            .file_name()
+            .map(|name| name.to_string_lossy().to_string())
+            .unwrap_or_default();
+        let old_path = std::mem::replace(&mut image.img_path, img_path.clone());
+        image.thumbnail_path = thumbnail_path;
+        // A thumbnail loading under the old path is dropped when it arrives
+        image.is_loading = false;
+        if self.marked.remove(&old_path) {
+            self.marked.insert(img_path.clone());
+        }
+        if self.selection_anchor.as_ref() == Some(&old_path) {
+            self.selection_anchor = Some(img_path);
+        }
+        let image = &self.images[idx];
+
+        let new_name = image.name.clone();
+        if self.tags.tags_for(&old_name).next().is_some() {
+            self.tags.rename(&old_name, &new_name);
+            self.save_tags();
+        }
+        if self.library.get(&old_name).is_some() {
+            self.library.rename(&old_name, &new_name);
+            self.save_library();
+        }
+        self.details = None;
+    }
+
+    /// Drops wallpapers that left the wallpaper directory from the grid,
+    /// keeping the scroll position. With `forget`, for trashed ones, their
+    /// tags and library entries go too; moved ones keep them for wherever
+    /// they are listed next.
+    pub(super) fn remove_images(&mut self, mut indices: Vec<usize>, forget: bool) -> Task<Message> {
+        if indices.is_empty() {
+            return Task::none();
+        }
+        // From the back, so the indices still to go stay valid
+        indices.sort_unstable_by(|a, b| b.cmp(a));
+        let (mut tags_changed, mut library_changed) = (false, false);
+
+        for idx in indices {
+            let image = self.images.remove(idx);
+            if let Some(ref mut filtered) = self.filtered {
+                filtered.retain(|&shown| shown != idx);
+                for shown in filtered.iter_mut().filter(|shown| **shown > idx) {
+                    *shown -= 1;
+                }
+            }
+
+            if forget && self.tags.tags_for(&image.name).next().is_some() {
+                self.tags.set_tags(&image.name, Default::default());
+                tags_changed = true;
+            }
+            if forget && self.library.get(&image.name).is_some() {
+                self.library.remove(&image.name);
+                library_changed = true;
+            }
+            self.marked.remove(&image.img_path);
+            if self.selection_anchor.as_ref() == Some(&image.img_path) {
+                self.selection_anchor = None;
+            }
+            if self
+                .details
+                .as_ref()
+                .is_some_and(|(path, _)| *path == image.img_path)
+            {
+                self.details = None;
+            }
+        }
+        self.selected_idx = self.selected_idx.min(self.shown_len().saturating_sub(1));
+
+        if tags_changed {
+            self.save_tags();
+            let counts = self.tags.counts();
+            self.tag_filter.retain(|tag| counts.contains_key(tag));
+        }
+        if library_changed {
+            self.save_library();
+        }
+        Task::done(Message::LoadVisibleThumbnails)
+    }
+
+    pub(super) fn file_menu(&self, choice: usize) -> Element<'_, Message> {
+        let name = self
+            .selected_image()
+            .map(|img| img.name.as_str())
+            .unwrap_or("");
+        let title = match self.marked.len() {
+            0 => name.to_string(),
+            count => format!("{} marked wallpapers", count),
+        };
+        let mut entries = column![text(title).size(self.theme.heading_size())].spacing(4);
+
+        for (idx, action) in FileAction::ALL.into_iter().enumerate() {
+            let style = if idx == choice {
+                button::primary
+            } else {
+                button::text
+            };
+            entries = entries.push(
+                button(text(action.label()).size(self.theme.body_size()))
+                    .width(Length::Fill)
+                    .style(style)
+                    .on_press(Message::FileAction(action)),
+            );
+        }
+
+        container(
+            entries.push(text("Enter to choose, Esc to cancel").size(self.theme.caption_size())),
+        )
+        .width(Length::Fixed(300.0))
+        .padding(16)
+        .style(container::rounded_box)
+        .into()
+    }
+
+    pub(super) fn file_dialog<'a>(&'a self, input: &'a FileInput) -> Element<'a, Message> {
+        let name = self
+            .selected_image()
+            .map(|img| img.name.as_str())
+            .unwrap_or("");
+        let targets = match self.targets().len() {
+            1 => name.to_string(),
+            count => format!("{} wallpapers", count),
+        };
+        let (title, hint) = match self.mode {
+            Mode::Move(_) => (
+                format!("Move {} to", targets),
+                "Enter to move, Esc to cancel",
+            ),
+            Mode::Copy(_) => (
+                format!("Copy {} to", targets),
+                "Enter to copy, Esc to cancel",
+            ),
+            _ => (format!("Rename {}", name), "Enter to rename, Esc to cancel"),
+        };
+
+        let mut lines = column![
+            text(title).size(self.theme.heading_size()),
+            text_input("", &input.value)
+                .id(FILE_INPUT_ID)
+                .on_input(Message::FileInputChanged)
+                .padding(8),
+        ]
+        .spacing(8);
+        if let Some(ref error) = input.error {
+            lines = lines.push(
+                text(error)
+                    .size(self.theme.caption_size())
+                    .style(text::danger),
+            );
+        }
+
+        container(lines.push(text(hint).size(self.theme.caption_size())))
+            .width(Length::Fixed(420.0))
+            .padding(16)
+            .style(container::rounded_box)
+            .into()
+    }
+}
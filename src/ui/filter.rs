@@ -0,0 +1,108 @@
+use std::path::Path;
+
+use iced::{
+    Task,
+    widget::operation::{self, AbsoluteOffset},
+};
+
+use crate::{message::Message, wallpaper_image::WallpaperImage};
+
+use super::AppView;
+
+impl AppView {
+    /// Number of wallpapers currently shown in the grid.
+    pub(crate) fn shown_len(&self) -> usize {
+        match self.filtered {
+            Some(ref indices) => indices.len(),
+            None => self.images.len(),
+        }
+    }
+
+    /// Maps a grid position to an index into `images`.
+    pub(crate) fn image_idx(&self, pos: usize) -> Option<usize> {
+        match self.filtered {
+            Some(ref indices) => indices.get(pos).copied(),
+            None => (pos < self.images.len()).then_some(pos),
+        }
+    }
+
+    /// Index into `images` of the wallpaper at `path`. Background tasks refer
+    /// to wallpapers by path, since indices shift when wallpapers are removed.
+    pub(crate) fn index_of(&self, path: &Path) -> Option<usize> {
+        self.images.iter().position(|image| image.img_path == path)
+    }
+
+    pub(crate) fn selected_image(&self) -> Option<&WallpaperImage> {
+        self.image_idx(self.selected_idx)
+            .and_then(|idx| self.images.get(idx))
+    }
+
+    pub(super) fn is_filtering(&self) -> bool {
+        !self.search.is_empty() || !self.tag_filter.is_empty() || self.color_filter.is_some()
+    }
+
+    pub(super) fn matches_filter(&self, image: &WallpaperImage) -> bool {
+        let search = self.search.to_lowercase();
+        image.name.to_lowercase().contains(&search)
+            && self.tags.has_all(&image.name, &self.tag_filter)
+            && self
+                .color_filter
+                .is_none_or(|swatch| image.color.is_some_and(|color| swatch.matches(&color)))
+    }
+
+    /// Adds a newly discovered image to the shown wallpapers, keeping the hue
+    /// order when sorting. Returns its grid position, if shown.
+    pub(super) fn show_discovered(&mut self, idx: usize) -> Option<usize> {
+        let matches = self.matches_filter(&self.images[idx]);
+        let Some(ref mut filtered) = self.filtered else {
+            return Some(idx);
+        };
+        if !matches {
+            return None;
+        }
+
+        let pos = if self.sort_by_hue {
+            let order = hue_order(&self.images[idx]);
+            filtered.partition_point(|&i| hue_order(&self.images[i]) <= order)
+        } else {
+            filtered.len()
+        };
+        filtered.insert(pos, idx);
+        Some(pos)
+    }
+
+    /// Recomputes the shown wallpapers and resets the grid to the top.
+    pub(super) fn refilter(&mut self) -> Task<Message> {
+        self.filtered = if self.is_filtering() || self.sort_by_hue {
+            let mut indices: Vec<usize> = self
+                .images
+                .iter()
+                .enumerate()
+                .filter(|(_, image)| self.matches_filter(image))
+                .map(|(idx, _)| idx)
+                .collect();
+            if self.sort_by_hue {
+                indices.sort_by(|&a, &b| {
+                    hue_order(&self.images[a]).total_cmp(&hue_order(&self.images[b]))
+                });
+            }
+            Some(indices)
+        } else {
+            None
+        };
+
+        self.selected_idx = 0;
+        self.scroll_offset = 0.0;
+        self.visible_range = (0, 20);
+
+        Task::batch([
+            operation::scroll_to("scrollable-id", AbsoluteOffset { x: 0.0, y: 0.0 }),
+            Task::done(Message::LoadVisibleThumbnails),
+        ])
+    }
+}
+
+/// Images without color information sort last.
+fn hue_order(image: &WallpaperImage) -> f32 {
+    image.color.map(|c| c.hue_order()).unwrap_or(f32::MAX)
+}
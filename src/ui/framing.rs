@@ -0,0 +1,310 @@
+use iced::{
+    Border, Color, ContentFit, Element, Length, Point, Task,
+    keyboard::key,
+    widget::{Image, Space, column, container, image as iced_image, mouse_area, row, stack, text},
+};
+
+use std::path::PathBuf;
+
+use image::imageops::FilterType;
+
+use crate::{
+    constants::{PREVIEW_HEIGHT, PREVIEW_WIDTH},
+    format::{image_dimensions, open_image},
+    framing::{FocusPoint, Framing, Region},
+    message::Message,
+    thumbnail::gen_thumbnail,
+};
+
+use super::{AppView, Mode};
+
+/// A press and release closer than this many pixels is a click, which sets
+/// the focus point, rather than a drag selecting a crop.
+const CLICK_DISTANCE: f32 = 4.0;
+
+/// State of the crop and focus point editor.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct CropEditor {
+    pub(crate) framing: Framing,
+    /// Size the image is shown at, in logical pixels.
+    pub(crate) size: (f32, f32),
+    /// The decoded image, `None` while it is decoded.
+    pub(crate) image: Option<iced_image::Handle>,
+    cursor: Option<Point>,
+    drag_start: Option<Point>,
+}
+
+impl CropEditor {
+    pub(crate) fn new(framing: Framing, image_size: (u32, u32)) -> Self {
+        let scale = (PREVIEW_WIDTH / image_size.0.max(1) as f32)
+            .min(PREVIEW_HEIGHT / image_size.1.max(1) as f32);
+        Self {
+            framing,
+            size: (image_size.0 as f32 * scale, image_size.1 as f32 * scale),
+            image: None,
+            cursor: None,
+            drag_start: None,
+        }
+    }
+
+    pub(crate) fn cursor_moved(&mut self, position: Point) {
+        self.cursor = Some(position);
+    }
+
+    pub(crate) fn pressed(&mut self) {
+        self.drag_start = self.cursor;
+    }
+
+    /// Finishes a click, setting the focus point, or a drag, setting the crop.
+    pub(crate) fn released(&mut self) {
+        let (Some(start), Some(end)) = (self.drag_start.take(), self.cursor) else {
+            return;
+        };
+
+        if start.distance(end) < CLICK_DISTANCE {
+            let (x, y) = self.normalize(end);
+            self.framing.focus = Some(FocusPoint { x, y });
+        } else {
+            let (x0, y0) = self.normalize(start);
+            let (x1, y1) = self.normalize(end);
+            self.framing.crop = Some(Region {
+                x: x0.min(x1),
+                y: y0.min(y1),
+                width: (x1 - x0).abs(),
+                height: (y1 - y0).abs(),
+            });
+        }
+    }
+
+    /// Converts a position on the preview to fractions of the image size.
+    fn normalize(&self, position: Point) -> (f32, f32) {
+        (
+            (position.x / self.size.0).clamp(0.0, 1.0),
+            (position.y / self.size.1).clamp(0.0, 1.0),
+        )
+    }
+
+    /// The rectangle to draw: the one being dragged, or else the saved crop.
+    fn shown_crop(&self) -> Option<(f32, f32, f32, f32)> {
+        if let (Some(start), Some(end)) = (self.drag_start, self.cursor) {
+            return Some((
+                start.x.min(end.x),
+                start.y.min(end.y),
+                (end.x - start.x).abs(),
+                (end.y - start.y).abs(),
+            ));
+        }
+        self.framing.crop.map(|crop| {
+            (
+                crop.x * self.size.0,
+                crop.y * self.size.1,
+                crop.width * self.size.0,
+                crop.height * self.size.1,
+            )
+        })
+    }
+}
+
+impl AppView {
+    pub(super) fn open_crop_editor(&mut self) -> Task<Message> {
+        let Some(image) = self.selected_image() else {
+            return Task::none();
+        };
+
+        let size = match image_dimensions(&image.img_path) {
+            Ok(size) => size,
+            Err(e) => {
+                eprintln!("{:#}", e);
+                return Task::none();
+            }
+        };
+        let framing = self.library.framing(&image.name);
+        let img_path = image.img_path.clone();
+        self.mode = Mode::Crop(CropEditor::new(framing, size));
+
+        // Decoded like the preview, since iced can't read every format
+        // wallpapers come in, such as SVG
+        Task::perform(
+            async move {
+                let img = open_image(&img_path)
+                    .map_err(|e| eprintln!("Failed to decode {}: {:#}", img_path.display(), e))
+                    .ok()?
+                    .resize(
+                        PREVIEW_WIDTH as u32,
+                        PREVIEW_HEIGHT as u32,
+                        FilterType::Triangle,
+                    )
+                    .to_rgba8();
+                let (width, height) = img.dimensions();
+                let handle = iced_image::Handle::from_rgba(width, height, img.into_raw());
+                Some(Message::CropImageLoaded(img_path, handle))
+            },
+            |message| message,
+        )
+        .and_then(Task::done)
+    }
+
+    pub(super) fn crop_image_loaded(
+        &mut self,
+        img_path: PathBuf,
+        handle: iced_image::Handle,
+    ) -> Task<Message> {
+        // The editor may have been closed while the image was decoded
+        let selected = self.selected_image().map(|image| image.img_path.clone());
+        if let Mode::Crop(ref mut editor) = self.mode
+            && selected == Some(img_path)
+        {
+            editor.image = Some(handle);
+        }
+        Task::none()
+    }
+
+    pub(super) fn handle_crop_key(&mut self, key: key::Key) -> Task<Message> {
+        match key.as_ref() {
+            key::Key::Named(key::Named::Enter) => self.save_crop_editor(),
+            key::Key::Named(key::Named::Escape) => {
+                self.mode = Mode::Browse;
+                Task::none()
+            }
+            key::Key::Character("r") => {
+                if let Mode::Crop(ref mut editor) = self.mode {
+                    editor.framing = Framing::default();
+                }
+                Task::none()
+            }
+            _ => Task::none(),
+        }
+    }
+
+    /// Stores the framing and regenerates the thumbnail to match it.
+    fn save_crop_editor(&mut self) -> Task<Message> {
+        let Mode::Crop(ref editor) = self.mode else {
+            return Task::none();
+        };
+        let framing = editor.framing;
+        self.mode = Mode::Browse;
+
+        let Some(idx) = self.image_idx(self.selected_idx) else {
+            return Task::none();
+        };
+        let image = &self.images[idx];
+        self.library.set_framing(&image.name, framing);
+        self.save_library();
+
+        let img_path = image.img_path.clone();
+        let thumbnail_path = image.thumbnail_path.clone();
+        Task::perform(
+            async move {
+                let color = gen_thumbnail(&img_path, &thumbnail_path, &framing)
+                    .map_err(|e| eprintln!("Failed to regenerate thumbnail: {:#}", e))
+                    .ok()?
+                    .color;
+                let bytes = std::fs::read(&thumbnail_path).ok()?;
+                Some((img_path, color, bytes))
+            },
+            move |result| {
+                result.map(|(img_path, color, bytes)| {
+                    let handle = iced_image::Handle::from_bytes(bytes);
+                    Message::ThumbnailRegenerated(img_path, color, handle)
+                })
+            },
+        )
+        .and_then(Task::done)
+    }
+
+    pub(super) fn save_library(&self) {
+        let Some(ref library_path) = self.library_path else {
+            eprintln!("Library path not configured");
+            return;
+        };
+        if let Err(e) = self.library.save(library_path) {
+            eprintln!("Failed to save library: {:#}", e);
+        }
+    }
+
+    pub(super) fn crop_editor<'a>(&'a self, editor: &'a CropEditor) -> Element<'a, Message> {
+        let Some(image) = self.selected_image() else {
+            return text("").into();
+        };
+        let (width, height) = editor.size;
+        let highlight = self.theme.accent;
+
+        let handle = editor
+            .image
+            .clone()
+            .or_else(|| image.thumbnail_handle.clone())
+            .unwrap_or_else(|| self.placeholder_handle.clone());
+        let mut preview = stack![
+            Image::new(handle)
+                .width(width)
+                .height(height)
+                .content_fit(ContentFit::Fill)
+        ];
+        if let Some((x, y, w, h)) = editor.shown_crop() {
+            preview = preview.push(positioned(
+                x,
+                y,
+                container(Space::new().width(w).height(h)).style(move |_theme| container::Style {
+                    border: Border {
+                        color: highlight,
+                        width: 2.0,
+                        radius: 0.0.into(),
+                    },
+                    background: Some(Color::from_rgba(1.0, 1.0, 1.0, 0.1).into()),
+                    ..container::Style::default()
+                }),
+            ));
+        }
+        if let Some(focus) = editor.framing.focus {
+            preview = preview.push(positioned(
+                focus.x * width - 6.0,
+                focus.y * height - 6.0,
+                container(Space::new().width(12).height(12)).style(move |_theme| {
+                    container::Style {
+                        border: Border {
+                            color: Color::WHITE,
+                            width: 2.0,
+                            radius: 6.0.into(),
+                        },
+                        background: Some(highlight.into()),
+                        ..container::Style::default()
+                    }
+                }),
+            ));
+        }
+
+        let preview = mouse_area(preview)
+            .on_move(Message::CropCursorMoved)
+            .on_press(Message::CropPressed)
+            .on_release(Message::CropReleased);
+
+        container(
+            column![
+                text(format!("Framing {}", image.name)).size(self.theme.heading_size()),
+                preview,
+                text("Drag to crop, click to set the focus point. Enter to save, r to reset, Esc to cancel")
+                    .size(self.theme.caption_size()),
+            ]
+            .spacing(8),
+        )
+        .padding(16)
+        .style(container::rounded_box)
+        .into()
+    }
+}
+
+/// Places `content` at `x`, `y` from the top left corner of its parent.
+fn positioned<'a>(
+    x: f32,
+    y: f32,
+    content: impl Into<Element<'a, Message>>,
+) -> Element<'a, Message> {
+    column![
+        Space::new().height(Length::Fixed(y.max(0.0))),
+        row![
+            Space::new().width(Length::Fixed(x.max(0.0))),
+            content.into()
+        ],
+    ]
+    .into()
+}
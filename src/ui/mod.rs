@@ -1,68 +1,201 @@
-#[cfg(unix)]
-use std::os::unix::fs::symlink;
-use std::process::Command;
+use std::{collections::BTreeSet, path::PathBuf, sync::Arc};
 
 use iced::{
     Alignment, Border, Color, ContentFit, Element, Length, Pixels, Subscription, Task, exit,
     keyboard::{self, key},
     widget::{
-        Image, column, container, grid, image as iced_image, mouse_area,
+        Image, center, column, container, grid, image as iced_image, mouse_area, opaque,
         operation::{self, AbsoluteOffset},
-        scrollable, text,
+        row, scrollable, stack, text, text_input,
     },
+    window,
 };
 
 use crate::{
-    config::CONFIG,
+    config::{AppConfig, CONFIG, ConfigReport, config_report},
     constants::{IMAGES_PER_ROW, ROW_HEIGHT, THUMBNAIL_HEIGHT, THUMBNAIL_WIDTH, VIEWPORT_HEIGHT},
-    events::wallpaper_stream,
+    events::{config_stream, wallpaper_stream},
+    files::FileAction,
+    library::Library,
     message::Message,
+    metadata::Metadata,
+    outputs::{OutputConfig, resolve_outputs},
+    palette::Swatch,
+    tags::TagStore,
+    theme::Theme,
     wallpaper_image::WallpaperImage,
 };
 
-use self::navigation::Direction;
+use self::{
+    files::FileInput, framing::CropEditor, navigation::Direction, preview::Preview,
+    selection::Visual,
+};
 
+mod appearance;
+mod banner;
+mod colors;
+mod details;
+mod files;
+mod filter;
+mod framing;
 mod navigation;
+mod outputs;
+mod preview;
+mod reload;
+mod selection;
+mod tags;
 #[cfg(test)]
 mod tests;
 
+const SEARCH_INPUT_ID: &str = "search-input";
+
+/// What keyboard input is currently directed at.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum Mode {
+    Browse,
+    Search,
+    TagEditor(String),
+    /// Choosing which output to apply the selected wallpaper to.
+    OutputPicker(usize),
+    /// Setting the crop and focus point of the selected wallpaper.
+    Crop(CropEditor),
+    /// Showing the selected wallpaper large.
+    Preview(Preview),
+    /// Choosing what to do with the selected wallpaper's file.
+    FileMenu(usize),
+    Rename(FileInput),
+    /// Typing the directory to move the targeted wallpapers to.
+    Move(FileInput),
+    /// Typing the directory to copy the targeted wallpapers to.
+    Copy(FileInput),
+}
+
 pub struct AppView {
     pub(crate) images: Vec<WallpaperImage>,
     pub(crate) selected_idx: usize,
     visible_range: (usize, usize),
     placeholder_handle: iced_image::Handle,
     scroll_offset: f32,
+    pub(crate) mode: Mode,
+    pub(crate) search: String,
+    pub(crate) tags: TagStore,
+    pub(crate) tags_path: Option<PathBuf>,
+    pub(crate) tag_filter: BTreeSet<String>,
+    pub(crate) library: Library,
+    pub(crate) library_path: Option<PathBuf>,
+    /// Indices into `images` in display order, `None` when no filter is active.
+    pub(crate) filtered: Option<Vec<usize>>,
+    pub(crate) outputs: Vec<OutputConfig>,
+    show_sidebar: bool,
+    pub(crate) color_filter: Option<Swatch>,
+    pub(crate) sort_by_hue: bool,
+    show_swatches: bool,
+    show_details: bool,
+    /// The wallpaper the details panel was last asked for, and its details
+    /// once they are read.
+    pub(crate) details: Option<(PathBuf, Option<Metadata>)>,
+    /// Wallpapers marked for bulk actions, by path so they stay marked while
+    /// filtered out of view.
+    pub(crate) marked: BTreeSet<PathBuf>,
+    pub(crate) visual: Option<Visual>,
+    /// Where Shift+click starts marking from.
+    selection_anchor: Option<PathBuf>,
+    pub(crate) modifiers: keyboard::Modifiers,
+    /// Problems with the config file, `None` once dismissed or if there are none.
+    pub(crate) config_report: Option<ConfigReport>,
+    /// The config as of the last reload.
+    pub(crate) config: Arc<AppConfig>,
+    config_path: PathBuf,
+    pub(crate) theme: Theme,
+    /// Accent taken from the applied wallpaper, for `accent = "wallpaper"`.
+    pub(crate) wallpaper_accent: Option<[u8; 3]>,
 }
 
 impl AppView {
     pub fn new() -> Self {
+        let config = CONFIG.load_full();
+        let tags_path = config.tags_path.clone();
+        let tags = reload::load_tags(tags_path.as_deref());
+        let library_path = config.library_path.clone();
+        let library = reload::load_library(library_path.as_deref());
+        let theme = Theme::new(&config.theme, None);
+
         Self {
             images: Vec::new(),
             visible_range: (0, 20),
-            placeholder_handle: iced_image::Handle::from_rgba(1, 1, vec![240, 240, 240, 255]),
+            placeholder_handle: appearance::placeholder_handle(&theme),
             selected_idx: 0,
             scroll_offset: 0.0,
+            mode: Mode::Browse,
+            search: String::new(),
+            tags,
+            tags_path,
+            tag_filter: BTreeSet::new(),
+            library,
+            library_path,
+            filtered: None,
+            outputs: resolve_outputs(&config),
+            show_sidebar: false,
+            color_filter: None,
+            sort_by_hue: false,
+            show_swatches: false,
+            show_details: false,
+            details: None,
+            marked: BTreeSet::new(),
+            visual: None,
+            selection_anchor: None,
+            modifiers: keyboard::Modifiers::default(),
+            config_report: Some(config_report().clone()).filter(|report| !report.is_empty()),
+            config_path: config_report().path.clone(),
+            config,
+            theme,
+            wallpaper_accent: None,
         }
     }
 
+    /// Like [`AppView::new`], also reading the accent of the wallpaper
+    /// applied last if the theme wants it.
+    pub fn boot() -> (Self, Task<Message>) {
+        let view = Self::new();
+        let task = view.load_wallpaper_accent(view.applied_wallpaper());
+        (view, task)
+    }
+
     pub fn subscription(&self) -> Subscription<Message> {
+        let playing = matches!(self.mode, Mode::Preview(ref preview) if preview.is_playing());
+
         Subscription::batch([
-            wallpaper_stream(),
+            wallpaper_stream((
+                self.config.wallpaper_path.clone(),
+                self.config.cache_path.clone(),
+            )),
+            config_stream(self.config_path.clone()),
             keyboard::listen().filter_map(|event| match event {
                 keyboard::Event::KeyReleased { key, .. } => Some(Message::KeyPressed(key)),
+                keyboard::Event::ModifiersChanged(modifiers) => {
+                    Some(Message::ModifiersChanged(modifiers))
+                }
                 _ => None,
             }),
+            if playing {
+                window::frames().map(Message::PreviewFrame)
+            } else {
+                Subscription::none()
+            },
         ])
     }
 
     pub fn view(&self) -> Element<'_, Message> {
         if self.images.is_empty() {
-            return container(text("Loading wallpapers..."))
+            let loading = container(text("Loading wallpapers..."))
                 .width(Length::Fill)
                 .height(Length::Fill)
                 .center_x(Length::Fill)
-                .center_y(Length::Fill)
-                .into();
+                .center_y(Length::Fill);
+            return match self.config_banner() {
+                Some(banner) => column![banner, loading].into(),
+                None => loading.into(),
+            };
         }
 
         let mut g = grid![]
@@ -70,7 +203,8 @@ impl AppView {
             .spacing(Pixels(5.0))
             .height(Length::Shrink);
 
-        let total_rows = self.images.len().div_ceil(IMAGES_PER_ROW);
+        let shown_len = self.shown_len();
+        let total_rows = shown_len.div_ceil(IMAGES_PER_ROW);
         let start_row = self.visible_range.0 / IMAGES_PER_ROW;
         let end_row = self
             .visible_range
@@ -78,11 +212,13 @@ impl AppView {
             .div_ceil(IMAGES_PER_ROW)
             .min(total_rows);
 
-        let start_idx = start_row * IMAGES_PER_ROW;
-        let end_idx = (end_row * IMAGES_PER_ROW).min(self.images.len());
+        let start_pos = start_row * IMAGES_PER_ROW;
+        let end_pos = (end_row * IMAGES_PER_ROW).min(shown_len);
 
-        for idx in start_idx..end_idx {
-            let img_data = &self.images[idx];
+        for pos in start_pos..end_pos {
+            let Some(img_data) = self.image_idx(pos).map(|idx| &self.images[idx]) else {
+                continue;
+            };
             let img_widget = if let Some(ref handle) = img_data.thumbnail_handle
                 && img_data.is_visible
             {
@@ -97,17 +233,31 @@ impl AppView {
                     .content_fit(ContentFit::Fill)
             };
 
+            let img_widget: Element<'_, Message> = if img_data.animated {
+                stack![img_widget, animated_badge()].into()
+            } else {
+                img_widget.into()
+            };
+
             let container_widget = container(img_widget)
                 .width(THUMBNAIL_WIDTH)
                 .height(THUMBNAIL_HEIGHT)
                 .padding([5, 5]);
 
-            let styled_container = if self.selected_idx == idx {
-                container_widget.style(|_theme| container::Style {
+            let selected = self.selected_idx == pos;
+            let marked = self.is_marked(pos);
+            let styled_container = if selected || marked {
+                container_widget.style(move |_theme| container::Style {
+                    // Marked wallpapers are framed by a tint of the accent
+                    background: marked.then(|| self.theme.accent.scale_alpha(0.5).into()),
                     border: Border {
-                        color: Color::from_rgb(1.0, 0.447, 0.0),
-                        width: 3.0,
-                        radius: 0.0.into(),
+                        color: if selected {
+                            self.theme.accent
+                        } else {
+                            Color::TRANSPARENT
+                        },
+                        width: self.theme.border_width,
+                        radius: self.theme.border_radius.into(),
                     },
                     ..container::Style::default()
                 })
@@ -116,9 +266,10 @@ impl AppView {
             };
 
             let final_widget = mouse_area(styled_container)
-                .on_enter(Message::ImageHovered(Some(idx)))
+                .on_enter(Message::ImageHovered(Some(pos)))
                 .on_exit(Message::ImageHovered(None))
-                .on_press(Message::WallpaperSelected);
+                .on_press(Message::ImagePressed(pos))
+                .on_right_press(Message::FileMenuOpened(pos));
 
             g = g.push(final_widget);
         }
@@ -132,35 +283,100 @@ impl AppView {
             .on_scroll(Message::ScrolledTo)
             .id("scrollable-id");
 
-        let selected_wallpaper_name = self
-            .images
-            .get(self.selected_idx)
-            .map(|img| img.name.as_str())
-            .unwrap_or("");
-
-        let footer = container(
-            text(selected_wallpaper_name)
-                .size(16)
-                .color(Color::from_rgb(0.8, 0.8, 0.8)),
-        )
+        let footer_text = match self.selected_image() {
+            Some(img) => {
+                let tags = self.tags.tags_for(&img.name).cloned().collect::<Vec<_>>();
+                if tags.is_empty() {
+                    img.name.clone()
+                } else {
+                    format!("{}  [{}]", img.name, tags.join(", "))
+                }
+            }
+            None if self.is_filtering() => String::from("No matching wallpapers"),
+            None => String::new(),
+        };
+        let footer_text = match (self.visual.is_some(), self.marked.len()) {
+            (true, count) => format!("-- VISUAL -- {} marked  {}", count, footer_text),
+            (false, 0) => footer_text,
+            (false, count) => format!("{} marked  {}", count, footer_text),
+        };
+
+        let footer = container(text(footer_text).size(self.theme.heading_size()).style(
+            |theme: &iced::Theme| text::Style {
+                color: Some(theme.palette().text.scale_alpha(0.8)),
+            },
+        ))
         .width(Length::Fill)
         .padding(10)
         .align_x(Alignment::Center);
 
-        column![
-            container(scroll).width(Length::Fill).height(Length::Fill),
-            footer
-        ]
-        .into()
+        let mut main = column![];
+        if self.show_swatches {
+            main = main.push(self.color_filter_row());
+        }
+        if self.mode == Mode::Search || !self.search.is_empty() {
+            main = main.push(
+                container(
+                    text_input("Search wallpapers...", &self.search)
+                        .id(SEARCH_INPUT_ID)
+                        .on_input_maybe(
+                            (self.mode == Mode::Search).then_some(Message::SearchChanged),
+                        )
+                        .padding(8),
+                )
+                .padding([5, 10]),
+            );
+        }
+        main = main.push(container(scroll).width(Length::Fill).height(Length::Fill));
+
+        let mut body = row![];
+        if self.show_sidebar {
+            body = body.push(self.tag_sidebar());
+        }
+        body = body.push(main);
+        if self.show_details {
+            body = body.push(self.details_panel());
+        }
+
+        let page = column![].push(self.config_banner()).push(body).push(footer);
+
+        match self.mode {
+            Mode::TagEditor(ref input) => {
+                stack![page, opaque(center(self.tag_editor(input)))].into()
+            }
+            Mode::OutputPicker(choice) => {
+                stack![page, opaque(center(self.output_picker(choice)))].into()
+            }
+            Mode::Crop(ref editor) => stack![page, opaque(center(self.crop_editor(editor)))].into(),
+            Mode::Preview(ref preview) => {
+                stack![page, opaque(center(self.preview(preview)))].into()
+            }
+            Mode::FileMenu(choice) => stack![page, opaque(center(self.file_menu(choice)))].into(),
+            Mode::Rename(ref input) | Mode::Move(ref input) | Mode::Copy(ref input) => {
+                stack![page, opaque(center(self.file_dialog(input)))].into()
+            }
+            _ => page.into(),
+        }
     }
 
     pub fn update(&mut self, message: Message) -> Task<Message> {
+        let task = self.handle_message(message);
+        // Whatever moved the cursor, visual mode and the details panel follow it
+        self.extend_visual();
+        Task::batch([task, self.load_details()])
+    }
+
+    fn handle_message(&mut self, message: Message) -> Task<Message> {
         match message {
             Message::WallpaperDiscovered(image) => {
                 self.images.push(image);
 
                 let idx = self.images.len() - 1;
-                if idx >= self.visible_range.0 && idx < self.visible_range.1 {
+                let Some(pos) = self.show_discovered(idx) else {
+                    return Task::none();
+                };
+
+                if pos >= self.visible_range.0 && pos < self.visible_range.1 {
                     return Task::done(Message::LoadVisibleThumbnails);
                 }
 
@@ -174,21 +390,27 @@ impl AppView {
                 let start_row = (scroll_offset / ROW_HEIGHT).floor() as usize;
                 let end_row = ((scroll_offset + viewport_height) / ROW_HEIGHT).ceil() as usize;
 
+                let shown_len = self.shown_len();
                 let start_idx = start_row * IMAGES_PER_ROW;
-                let end_idx = ((end_row + 1) * IMAGES_PER_ROW).min(self.images.len());
+                let end_idx = ((end_row + 1) * IMAGES_PER_ROW).min(shown_len);
 
                 let buffer = 10;
                 let new_range = (
                     start_idx.saturating_sub(buffer),
-                    (end_idx + buffer).min(self.images.len()),
+                    (end_idx + buffer).min(shown_len),
                 );
 
                 if new_range != self.visible_range {
                     self.visible_range = new_range;
                     let unload_distance = buffer + 10;
-                    for (idx, img_data) in self.images.iter_mut().enumerate() {
-                        if (idx < self.visible_range.0.saturating_sub(unload_distance)
-                            || idx > self.visible_range.1 + unload_distance)
+                    for pos in 0..shown_len {
+                        if pos >= self.visible_range.0.saturating_sub(unload_distance)
+                            && pos <= self.visible_range.1 + unload_distance
+                        {
+                            continue;
+                        }
+                        if let Some(idx) = self.image_idx(pos)
+                            && let Some(img_data) = self.images.get_mut(idx)
                             && img_data.thumbnail_handle.is_some()
                         {
                             img_data.thumbnail_handle = None;
@@ -204,26 +426,31 @@ impl AppView {
             Message::LoadVisibleThumbnails => {
                 let mut tasks = Vec::new();
 
-                for idx in self.visible_range.0..self.visible_range.1 {
+                for pos in self.visible_range.0..self.visible_range.1 {
+                    let Some(idx) = self.image_idx(pos) else {
+                        continue;
+                    };
                     if let Some(img_data) = self.images.get_mut(idx)
                         && !img_data.is_visible
                         && !img_data.is_loading
                         && img_data.thumbnail_handle.is_none()
                     {
                         img_data.is_loading = true;
+                        let img_path = img_data.img_path.clone();
                         let thumbnail_path = img_data.thumbnail_path.clone();
 
                         tasks.push(Task::perform(
                             async move { iced_image::Handle::from_path(thumbnail_path) },
-                            move |handle| Message::ThumbnailLoaded(idx, handle),
+                            move |handle| Message::ThumbnailLoaded(img_path.clone(), handle),
                         ));
                     }
                 }
 
                 Task::batch(tasks)
             }
-            Message::ThumbnailLoaded(idx, handle) => {
-                if let Some(img_data) = self.images.get_mut(idx) {
+            Message::ThumbnailLoaded(img_path, handle) => {
+                if let Some(idx) = self.index_of(&img_path) {
+                    let img_data = &mut self.images[idx];
                     img_data.thumbnail_handle = Some(handle);
                     img_data.is_visible = true;
                     img_data.is_loading = false;
@@ -236,6 +463,7 @@ impl AppView {
                 }
                 Task::none()
             }
+            Message::KeyPressed(key) if self.mode != Mode::Browse => self.handle_mode_key(key),
             Message::KeyPressed(key) => match key {
                 key::Key::Named(named) => match named {
                     key::Named::ArrowUp => self.move_selection(Direction::Up),
@@ -243,6 +471,10 @@ impl AppView {
                     key::Named::ArrowLeft => self.move_selection(Direction::Left),
                     key::Named::ArrowRight => self.move_selection(Direction::Right),
                     key::Named::Enter => Task::done(Message::WallpaperSelected),
+                    key::Named::Space => self.open_preview(),
+                    key::Named::Delete => self.file_action(FileAction::Trash),
+                    key::Named::F2 => self.file_action(FileAction::Rename),
+                    key::Named::Escape if self.clear_selection() => Task::none(),
                     key::Named::Escape => exit(),
                     _ => Task::none(),
                 },
@@ -251,57 +483,38 @@ impl AppView {
                     "j" => self.move_selection(Direction::Down),
                     "k" => self.move_selection(Direction::Up),
                     "l" => self.move_selection(Direction::Right),
+                    "/" => {
+                        self.mode = Mode::Search;
+                        operation::focus(SEARCH_INPUT_ID)
+                    }
+                    "t" => self.open_tag_editor(),
+                    "e" => self.open_crop_editor(),
+                    "f" => self.toggle_favorite(),
+                    "b" => {
+                        self.show_sidebar = !self.show_sidebar;
+                        Task::none()
+                    }
+                    "c" => self.toggle_swatches(),
+                    "i" => self.toggle_details(),
+                    "r" => self.file_action(FileAction::Rename),
+                    "m" => self.file_action(FileAction::Move),
+                    "C" => self.file_action(FileAction::Copy),
+                    "v" => self.toggle_visual(),
+                    "y" => self.file_action(FileAction::CopyPath),
+                    "o" => self.file_action(FileAction::Reveal),
+                    "s" => self.toggle_sort_by_hue(),
                     _ => Task::none(),
                 },
                 _ => Task::none(),
             },
-            Message::WallpaperSelected => {
-                if let Some(img_data) = self.images.get(self.selected_idx) {
-                    let current_wallpaper = CONFIG.current_wallpaper.clone();
-                    let post_script = CONFIG.post_script.clone();
-
-                    if let Some(current_wallpaper) = current_wallpaper {
-                        let img_path = img_data.img_path.clone();
-
-                        std::thread::spawn(move || {
-                            let _ = std::fs::remove_file(&current_wallpaper);
-
-                            #[cfg(unix)]
-                            if let Err(e) = symlink(&img_path, &current_wallpaper) {
-                                eprintln!("Failed to create symlink: {}", e);
-                                return;
-                            }
-
-                            if let Some(post_script) = post_script {
-                                if post_script.is_empty() {
-                                    return;
-                                }
-                                match Command::new("sh").arg("-c").arg(&post_script).status() {
-                                    Ok(status) if !status.success() => {
-                                        eprintln!(
-                                            "Post script exited with non-zero status: {}",
-                                            status
-                                        );
-                                    }
-                                    Err(e) => {
-                                        eprintln!("Failed to execute post script: {}", e);
-                                    }
-                                    _ => {}
-                                }
-                            }
-                        });
-                    } else {
-                        eprintln!("Current wallpaper path not configured");
-                    }
-                }
-                Task::none()
-            }
+            Message::WallpaperSelected => self.select_wallpaper(),
+            Message::ApplyTo(target) => self.apply_to(target),
             Message::ScrollToVisible => {
                 let start_row = (self.scroll_offset / ROW_HEIGHT).floor() as usize;
                 let end_row = ((self.scroll_offset + VIEWPORT_HEIGHT) / ROW_HEIGHT).ceil() as usize;
 
                 let actual_visible_start = start_row * IMAGES_PER_ROW;
-                let actual_visible_end = ((end_row + 1) * IMAGES_PER_ROW).min(self.images.len());
+                let actual_visible_end = ((end_row + 1) * IMAGES_PER_ROW).min(self.shown_len());
 
                 if self.selected_idx >= actual_visible_end
                     || self.selected_idx < actual_visible_start
@@ -319,6 +532,143 @@ impl AppView {
 
                 Task::none()
             }
+            Message::SearchChanged(query) => {
+                self.search = query;
+                self.refilter()
+            }
+            Message::TagEditorChanged(input) => {
+                if let Mode::TagEditor(ref mut current) = self.mode {
+                    *current = input;
+                }
+                Task::none()
+            }
+            Message::TagFilterToggled(tag) => self.toggle_tag_filter(tag),
+            Message::ColorFilterToggled(swatch) => self.toggle_color_filter(swatch),
+            Message::SortByHueToggled => self.toggle_sort_by_hue(),
+            Message::CropCursorMoved(position) => {
+                if let Mode::Crop(ref mut editor) = self.mode {
+                    editor.cursor_moved(position);
+                }
+                Task::none()
+            }
+            Message::CropPressed => {
+                if let Mode::Crop(ref mut editor) = self.mode {
+                    editor.pressed();
+                }
+                Task::none()
+            }
+            Message::CropReleased => {
+                if let Mode::Crop(ref mut editor) = self.mode {
+                    editor.released();
+                }
+                Task::none()
+            }
+            Message::CropImageLoaded(img_path, handle) => self.crop_image_loaded(img_path, handle),
+            Message::ThumbnailRegenerated(img_path, color, handle) => {
+                if let Some(idx) = self.index_of(&img_path) {
+                    self.images[idx].color = Some(color);
+                }
+                Task::done(Message::ThumbnailLoaded(img_path, handle))
+            }
+            Message::PreviewLoaded(img_path, frames) => self.preview_loaded(img_path, frames),
+            Message::ConfigBannerDismissed => {
+                self.config_report = None;
+                Task::none()
+            }
+            Message::ConfigReloaded(config, report) => self.config_reloaded(config, report),
+            Message::AccentLoaded(accent) => self.accent_loaded(accent),
+            Message::DetailsLoaded(metadata) => self.details_loaded(*metadata),
+            Message::FileMenuOpened(pos) => self.open_file_menu(pos),
+            Message::ImagePressed(pos) => self.image_pressed(pos),
+            Message::ModifiersChanged(modifiers) => {
+                self.modifiers = modifiers;
+                Task::none()
+            }
+            Message::FileAction(action) => self.file_action(action),
+            Message::FileInputChanged(value) => self.file_input_changed(value),
+            Message::PreviewFrame(now) => {
+                if let Mode::Preview(ref mut preview) = self.mode {
+                    preview.tick(now);
+                }
+                Task::none()
+            }
         }
     }
+
+    /// Handles keys while a text input owns the keyboard.
+    fn handle_mode_key(&mut self, key: key::Key) -> Task<Message> {
+        match self.mode {
+            Mode::OutputPicker(_) => return self.handle_output_picker_key(key),
+            Mode::Crop(_) => return self.handle_crop_key(key),
+            Mode::Preview(_) => return self.handle_preview_key(key),
+            Mode::FileMenu(_) => return self.handle_file_menu_key(key),
+            _ => {}
+        }
+        let key::Key::Named(named) = key else {
+            return Task::none();
+        };
+
+        match (&self.mode, named) {
+            (Mode::Search, key::Named::Enter) => {
+                self.mode = Mode::Browse;
+                Task::none()
+            }
+            (Mode::Search, key::Named::Escape) => {
+                self.mode = Mode::Browse;
+                self.search.clear();
+                self.refilter()
+            }
+            (Mode::TagEditor(_), key::Named::Enter) => self.save_tag_editor(),
+            (Mode::TagEditor(_), key::Named::Escape) => {
+                self.mode = Mode::Browse;
+                Task::none()
+            }
+            (Mode::Rename(_) | Mode::Move(_) | Mode::Copy(_), key::Named::Enter) => {
+                self.confirm_file_input()
+            }
+            (Mode::Rename(_) | Mode::Move(_) | Mode::Copy(_), key::Named::Escape) => {
+                self.mode = Mode::Browse;
+                Task::none()
+            }
+            _ => Task::none(),
+        }
+    }
+
+    fn handle_output_picker_key(&mut self, key: key::Key) -> Task<Message> {
+        match key.as_ref() {
+            key::Key::Named(key::Named::ArrowUp) | key::Key::Character("k") => {
+                self.move_output_choice(false);
+                Task::none()
+            }
+            key::Key::Named(key::Named::ArrowDown) | key::Key::Character("j") => {
+                self.move_output_choice(true);
+                Task::none()
+            }
+            key::Key::Named(key::Named::Enter) => self.confirm_output_choice(),
+            key::Key::Named(key::Named::Escape) => {
+                self.mode = Mode::Browse;
+                Task::none()
+            }
+            _ => Task::none(),
+        }
+    }
+}
+
+/// Label over the top left corner of an animated wallpaper's thumbnail.
+fn animated_badge<'a>() -> Element<'a, Message> {
+    container(
+        container(text("animated").size(11))
+            .padding([2, 6])
+            .style(|theme: &iced::Theme| container::Style {
+                text_color: Some(theme.palette().text),
+                background: Some(theme.palette().background.scale_alpha(0.7).into()),
+                border: Border {
+                    radius: 3.0.into(),
+                    ..Border::default()
+                },
+                ..container::Style::default()
+            }),
+    )
+    .padding(4)
+    .into()
 }
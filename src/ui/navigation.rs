@@ -13,9 +13,9 @@ pub(super) enum Direction {
 
 impl AppView {
     pub(super) fn move_selection(&mut self, direction: Direction) -> Task<Message> {
-        let len = self.images.len();
+        let len = self.shown_len();
 
-        if self.images.is_empty() {
+        if len == 0 {
             return Task::none();
         }
 
@@ -0,0 +1,110 @@
+use iced::{
+    Element, Length, Task,
+    widget::{button, column, container, text},
+};
+
+use crate::{
+    apply::{ApplyTarget, apply_wallpaper},
+    message::Message,
+};
+
+use super::{AppView, Mode};
+
+impl AppView {
+    /// Entries of the output picker: all outputs, each output, then spanning
+    /// when every output has a known geometry.
+    fn output_choices(&self) -> Vec<ApplyTarget> {
+        let mut choices = vec![ApplyTarget::All];
+        choices.extend(
+            self.outputs
+                .iter()
+                .map(|o| ApplyTarget::Output(o.name.clone())),
+        );
+        if self.outputs.iter().all(|o| o.geometry().is_some()) {
+            choices.push(ApplyTarget::Span);
+        }
+        choices
+    }
+
+    pub(super) fn select_wallpaper(&mut self) -> Task<Message> {
+        if self.selected_image().is_none() {
+            return Task::none();
+        }
+
+        if self.outputs.len() > 1 {
+            self.mode = Mode::OutputPicker(0);
+            Task::none()
+        } else {
+            Task::done(Message::ApplyTo(ApplyTarget::All))
+        }
+    }
+
+    pub(super) fn move_output_choice(&mut self, forward: bool) {
+        let count = self.output_choices().len();
+        if let Mode::OutputPicker(ref mut choice) = self.mode {
+            *choice = if forward {
+                (*choice + 1).min(count - 1)
+            } else {
+                choice.saturating_sub(1)
+            };
+        }
+    }
+
+    pub(super) fn confirm_output_choice(&mut self) -> Task<Message> {
+        let Mode::OutputPicker(choice) = self.mode else {
+            return Task::none();
+        };
+        match self.output_choices().get(choice) {
+            Some(target) => Task::done(Message::ApplyTo(target.clone())),
+            None => Task::none(),
+        }
+    }
+
+    pub(super) fn apply_to(&mut self, target: ApplyTarget) -> Task<Message> {
+        self.mode = Mode::Browse;
+
+        let Some(img_data) = self.selected_image() else {
+            return Task::none();
+        };
+        let img_path = img_data.img_path.clone();
+        let accent = self.load_wallpaper_accent(Some(img_path.clone()));
+
+        std::thread::spawn(move || {
+            if let Err(e) = apply_wallpaper(&img_path, &target) {
+                eprintln!("Failed to apply wallpaper: {:#}", e);
+            }
+        });
+        accent
+    }
+
+    pub(super) fn output_picker(&self, choice: usize) -> Element<'_, Message> {
+        let mut entries = column![text("Apply to").size(self.theme.heading_size())].spacing(4);
+
+        for (idx, target) in self.output_choices().into_iter().enumerate() {
+            let label = match target {
+                ApplyTarget::All => String::from("All outputs"),
+                ApplyTarget::Output(ref name) => name.clone(),
+                ApplyTarget::Span => String::from("Span across outputs"),
+            };
+            let style = if idx == choice {
+                button::primary
+            } else {
+                button::text
+            };
+            entries = entries.push(
+                button(text(label).size(self.theme.body_size()))
+                    .width(Length::Fill)
+                    .style(style)
+                    .on_press(Message::ApplyTo(target)),
+            );
+        }
+
+        container(
+            entries.push(text("Enter to apply, Esc to cancel").size(self.theme.caption_size())),
+        )
+        .width(Length::Fixed(300.0))
+        .padding(16)
+        .style(container::rounded_box)
+        .into()
+    }
+}
@@ -0,0 +1,190 @@
+use std::{
+    path::PathBuf,
+    time::{Duration, Instant},
+};
+
+use iced::{
+    ContentFit, Element, Task,
+    keyboard::key,
+    widget::{Image, column, container, image as iced_image, text},
+};
+use image::imageops::FilterType;
+
+use crate::{
+    animation::{AnimationInfo, Frame, decode_frames},
+    constants::{PREVIEW_HEIGHT, PREVIEW_WIDTH},
+    format::open_image,
+    message::Message,
+};
+
+use super::{AppView, Mode};
+
+/// The selected wallpaper shown large, with animated images playing.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct Preview {
+    /// Index into `images` of the wallpaper shown.
+    pub(crate) idx: usize,
+    /// Decoded frames of an animated image, empty for still images.
+    pub(crate) frames: Vec<(iced_image::Handle, Duration)>,
+    pub(crate) current: usize,
+    /// When the current frame was first drawn.
+    shown_at: Option<Instant>,
+}
+
+impl Preview {
+    fn new(idx: usize) -> Self {
+        Self {
+            idx,
+            frames: Vec::new(),
+            current: 0,
+            shown_at: None,
+        }
+    }
+
+    pub(crate) fn is_playing(&self) -> bool {
+        self.frames.len() > 1
+    }
+
+    pub(crate) fn info(&self) -> Option<AnimationInfo> {
+        self.is_playing().then(|| AnimationInfo {
+            frames: self.frames.len(),
+            duration: self.frames.iter().map(|(_, delay)| *delay).sum(),
+        })
+    }
+
+    /// Moves to the next frame once the current one has been shown for its delay.
+    pub(crate) fn tick(&mut self, now: Instant) {
+        if !self.is_playing() {
+            return;
+        }
+        let Some(shown_at) = self.shown_at else {
+            self.shown_at = Some(now);
+            return;
+        };
+
+        if now.duration_since(shown_at) >= self.frames[self.current].1 {
+            self.current = (self.current + 1) % self.frames.len();
+            self.shown_at = Some(now);
+        }
+    }
+}
+
+impl AppView {
+    pub(super) fn open_preview(&mut self) -> Task<Message> {
+        let Some(idx) = self.image_idx(self.selected_idx) else {
+            return Task::none();
+        };
+        self.mode = Mode::Preview(Preview::new(idx));
+
+        // Still images are decoded as a single frame too, so they are shown
+        // upright and in sRGB like their thumbnails, whatever their format
+        let image = &self.images[idx];
+        let animated = image.animated;
+        let img_path = image.img_path.clone();
+
+        Task::perform(
+            async move {
+                let (width, height) = (PREVIEW_WIDTH as u32, PREVIEW_HEIGHT as u32);
+                let frames = if animated {
+                    decode_frames(&img_path, width, height)
+                } else {
+                    open_image(&img_path).map(|img| {
+                        vec![Frame {
+                            image: img.resize(width, height, FilterType::Triangle).to_rgba8(),
+                            delay: Duration::ZERO,
+                        }]
+                    })
+                };
+                frames
+                    .map_err(|e| eprintln!("Failed to decode {}: {:#}", img_path.display(), e))
+                    .ok()
+                    .map(|frames| (img_path, frames))
+            },
+            move |frames| {
+                frames.map(|(img_path, frames)| {
+                    let frames = frames
+                        .into_iter()
+                        .map(|frame| {
+                            let (width, height) = frame.image.dimensions();
+                            let handle = iced_image::Handle::from_rgba(
+                                width,
+                                height,
+                                frame.image.into_raw(),
+                            );
+                            (handle, frame.delay)
+                        })
+                        .collect();
+                    Message::PreviewLoaded(img_path, frames)
+                })
+            },
+        )
+        .and_then(Task::done)
+    }
+
+    pub(super) fn preview_loaded(
+        &mut self,
+        img_path: PathBuf,
+        frames: Vec<(iced_image::Handle, Duration)>,
+    ) -> Task<Message> {
+        // The preview may have been closed while the frames were decoded
+        let idx = self.index_of(&img_path);
+        if let Mode::Preview(ref mut preview) = self.mode
+            && Some(preview.idx) == idx
+        {
+            preview.frames = frames;
+            preview.current = 0;
+        }
+        Task::none()
+    }
+
+    pub(super) fn handle_preview_key(&mut self, key: key::Key) -> Task<Message> {
+        match key.as_ref() {
+            key::Key::Named(key::Named::Escape | key::Named::Space) => {
+                self.mode = Mode::Browse;
+                Task::none()
+            }
+            key::Key::Named(key::Named::Enter) => {
+                self.mode = Mode::Browse;
+                Task::done(Message::WallpaperSelected)
+            }
+            _ => Task::none(),
+        }
+    }
+
+    pub(super) fn preview<'a>(&'a self, preview: &'a Preview) -> Element<'a, Message> {
+        let Some(image) = self.images.get(preview.idx) else {
+            return text("").into();
+        };
+
+        let handle = match preview.frames.get(preview.current) {
+            Some((handle, _)) => handle.clone(),
+            None => image
+                .thumbnail_handle
+                .clone()
+                .unwrap_or_else(|| iced_image::Handle::from_path(&image.img_path)),
+        };
+        let caption = match preview.info() {
+            Some(info) => format!("{}  ({})", image.name, info),
+            None if image.animated => format!("{}  (loading animation...)", image.name),
+            None => image.name.clone(),
+        };
+
+        container(
+            column![
+                Image::new(handle)
+                    .width(PREVIEW_WIDTH)
+                    .height(PREVIEW_HEIGHT)
+                    .content_fit(ContentFit::Contain),
+                text(caption)
+                    .size(self.theme.body_size())
+                    .style(|theme: &iced::Theme| text::Style {
+                        color: Some(theme.palette().text.scale_alpha(0.8)),
+                    }),
+            ]
+            .spacing(8),
+        )
+        .padding(16)
+        .style(container::rounded_box)
+        .into()
+    }
+}
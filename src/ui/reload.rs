@@ -0,0 +1,76 @@
+use std::{path::Path, sync::Arc};
+
+use iced::Task;
+
+use crate::{
+    config::{AppConfig, ConfigReport},
+    library::Library,
+    message::Message,
+    outputs::resolve_outputs,
+    tags::TagStore,
+};
+
+use super::{AppView, Mode};
+
+impl AppView {
+    /// Switches to a reloaded config, discovering the wallpapers again if
+    /// their directories changed.
+    pub(super) fn config_reloaded(
+        &mut self,
+        config: Arc<AppConfig>,
+        report: ConfigReport,
+    ) -> Task<Message> {
+        self.config_report = Some(report).filter(|report| !report.is_empty());
+        let previous = std::mem::replace(&mut self.config, config);
+
+        if previous.tags_path != self.config.tags_path {
+            self.tags_path = self.config.tags_path.clone();
+            self.tags = load_tags(self.tags_path.as_deref());
+        }
+        if previous.library_path != self.config.library_path {
+            self.library_path = self.config.library_path.clone();
+            self.library = load_library(self.library_path.as_deref());
+        }
+        self.outputs = resolve_outputs(&self.config);
+
+        let accent = if previous.theme.accent != self.config.theme.accent {
+            self.wallpaper_accent = None;
+            self.load_wallpaper_accent(self.applied_wallpaper())
+        } else {
+            Task::none()
+        };
+        self.update_theme();
+
+        if previous.wallpaper_path == self.config.wallpaper_path
+            && previous.cache_path == self.config.cache_path
+        {
+            return accent;
+        }
+        // The wallpaper subscription is keyed by these paths and restarts on
+        // its own, so only the wallpapers found so far have to go
+        self.images.clear();
+        self.clear_selection();
+        self.mode = Mode::Browse;
+        Task::batch([accent, self.refilter()])
+    }
+}
+
+pub(super) fn load_tags(path: Option<&Path>) -> TagStore {
+    path.map(|path| {
+        TagStore::load(path).unwrap_or_else(|e| {
+            eprintln!("Failed to load tags: {:#}", e);
+            TagStore::default()
+        })
+    })
+    .unwrap_or_default()
+}
+
+pub(super) fn load_library(path: Option<&Path>) -> Library {
+    path.map(|path| {
+        Library::load(path).unwrap_or_else(|e| {
+            eprintln!("Failed to load library: {:#}", e);
+            Library::default()
+        })
+    })
+    .unwrap_or_default()
+}
@@ -0,0 +1,127 @@
+use std::{
+    collections::BTreeSet,
+    path::{Path, PathBuf},
+};
+
+use iced::Task;
+
+use crate::message::Message;
+
+use super::AppView;
+
+/// Visual mode, marking every wallpaper between where it started and the
+/// cursor as the cursor moves.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct Visual {
+    anchor: PathBuf,
+    /// What was marked before visual mode started.
+    base: BTreeSet<PathBuf>,
+}
+
+impl AppView {
+    /// Position of a wallpaper in the grid as currently filtered and sorted.
+    fn position_of(&self, path: &Path) -> Option<usize> {
+        (0..self.shown_len()).find(|&pos| {
+            self.image_idx(pos)
+                .is_some_and(|idx| self.images[idx].img_path == *path)
+        })
+    }
+
+    fn path_at(&self, pos: usize) -> Option<PathBuf> {
+        self.image_idx(pos)
+            .map(|idx| self.images[idx].img_path.clone())
+    }
+
+    /// A click on the wallpaper at `pos`: with Ctrl it is marked or unmarked,
+    /// with Shift everything from the last marked one up to it is marked, and
+    /// otherwise it is applied.
+    pub(super) fn image_pressed(&mut self, pos: usize) -> Task<Message> {
+        let Some(path) = self.path_at(pos) else {
+            return Task::none();
+        };
+        self.selected_idx = pos;
+
+        if self.modifiers.shift()
+            && let Some(from) = self
+                .selection_anchor
+                .as_ref()
+                .and_then(|anchor| self.position_of(anchor))
+        {
+            let range: Vec<PathBuf> = (from.min(pos)..=from.max(pos))
+                .filter_map(|pos| self.path_at(pos))
+                .collect();
+            self.marked.extend(range);
+        } else if self.modifiers.control() || self.modifiers.shift() {
+            if !self.marked.remove(&path) {
+                self.marked.insert(path.clone());
+            }
+            self.selection_anchor = Some(path);
+        } else {
+            return Task::done(Message::WallpaperSelected);
+        }
+        Task::none()
+    }
+
+    pub(super) fn toggle_visual(&mut self) -> Task<Message> {
+        if self.visual.take().is_some() {
+            return Task::none();
+        }
+        let Some(anchor) = self.path_at(self.selected_idx) else {
+            return Task::none();
+        };
+        self.selection_anchor = Some(anchor.clone());
+        self.visual = Some(Visual {
+            anchor,
+            base: self.marked.clone(),
+        });
+        self.extend_visual();
+        Task::none()
+    }
+
+    /// Marks the range between the visual mode anchor and the cursor, on top
+    /// of what was marked before.
+    pub(super) fn extend_visual(&mut self) {
+        let Some(ref visual) = self.visual else {
+            return;
+        };
+        let Some(from) = self.position_of(&visual.anchor) else {
+            // The anchor was filtered out or removed
+            self.visual = None;
+            return;
+        };
+        let to = self.selected_idx.min(self.shown_len().saturating_sub(1));
+        let mut marked = visual.base.clone();
+        marked.extend((from.min(to)..=from.max(to)).filter_map(|pos| self.path_at(pos)));
+        self.marked = marked;
+    }
+
+    /// Unmarks everything and leaves visual mode. Returns whether there was
+    /// anything to clear.
+    pub(super) fn clear_selection(&mut self) -> bool {
+        let cleared = !self.marked.is_empty() || self.visual.is_some();
+        self.marked.clear();
+        self.visual = None;
+        self.selection_anchor = None;
+        cleared
+    }
+
+    pub(super) fn is_marked(&self, pos: usize) -> bool {
+        self.image_idx(pos)
+            .is_some_and(|idx| self.marked.contains(&self.images[idx].img_path))
+    }
+
+    /// Indices into `images` that bulk actions apply to: the marked
+    /// wallpapers, including ones filtered out of view, or else the one under
+    /// the cursor.
+    pub(super) fn targets(&self) -> Vec<usize> {
+        if self.marked.is_empty() {
+            return self.image_idx(self.selected_idx).into_iter().collect();
+        }
+        self.images
+            .iter()
+            .enumerate()
+            .filter(|(_, image)| self.marked.contains(&image.img_path))
+            .map(|(idx, _)| idx)
+            .collect()
+    }
+}
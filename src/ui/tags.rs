@@ -0,0 +1,131 @@
+use iced::{
+    Element, Length, Task,
+    widget::{button, column, container, operation, scrollable, text, text_input},
+};
+
+use std::collections::BTreeSet;
+
+use crate::{message::Message, rotation::FAVORITE_TAG, tags::parse_tags};
+
+use super::{AppView, Mode};
+
+pub(super) const TAG_INPUT_ID: &str = "tag-input";
+
+impl AppView {
+    pub(super) fn open_tag_editor(&mut self) -> Task<Message> {
+        let Some(image) = self.selected_image() else {
+            return Task::none();
+        };
+
+        let current = self
+            .tags
+            .tags_for(&image.name)
+            .cloned()
+            .collect::<Vec<_>>()
+            .join(", ");
+        self.mode = Mode::TagEditor(current);
+        operation::focus(TAG_INPUT_ID)
+    }
+
+    pub(super) fn save_tag_editor(&mut self) -> Task<Message> {
+        let Mode::TagEditor(ref input) = self.mode else {
+            return Task::none();
+        };
+        let Some(name) = self.selected_image().map(|img| img.name.clone()) else {
+            return Task::none();
+        };
+
+        let tags = parse_tags(input);
+        self.tags.set_tags(&name, tags);
+        self.mode = Mode::Browse;
+        self.save_tags();
+
+        // Drop filters on tags that no longer exist
+        let counts = self.tags.counts();
+        self.tag_filter.retain(|tag| counts.contains_key(tag));
+
+        if self.is_filtering() {
+            self.refilter()
+        } else {
+            Task::none()
+        }
+    }
+
+    pub(super) fn toggle_favorite(&mut self) -> Task<Message> {
+        let Some(name) = self.selected_image().map(|img| img.name.clone()) else {
+            return Task::none();
+        };
+
+        let mut tags: BTreeSet<String> = self.tags.tags_for(&name).cloned().collect();
+        if !tags.remove(FAVORITE_TAG) {
+            tags.insert(FAVORITE_TAG.to_string());
+        }
+        self.tags.set_tags(&name, tags);
+        self.save_tags();
+        Task::none()
+    }
+
+    pub(super) fn toggle_tag_filter(&mut self, tag: String) -> Task<Message> {
+        if !self.tag_filter.remove(&tag) {
+            self.tag_filter.insert(tag);
+        }
+        self.refilter()
+    }
+
+    pub(super) fn save_tags(&self) {
+        let Some(ref tags_path) = self.tags_path else {
+            eprintln!("Tags path not configured");
+            return;
+        };
+        if let Err(e) = self.tags.save(tags_path) {
+            eprintln!("Failed to save tags: {:#}", e);
+        }
+    }
+
+    pub(super) fn tag_sidebar(&self) -> Element<'_, Message> {
+        let mut tags = column![text("Tags").size(self.theme.heading_size())].spacing(4);
+
+        for (tag, count) in self.tags.counts() {
+            let style = if self.tag_filter.contains(&tag) {
+                button::primary
+            } else {
+                button::text
+            };
+            tags = tags.push(
+                button(text(format!("{} ({})", tag, count)).size(self.theme.body_size()))
+                    .width(Length::Fill)
+                    .style(style)
+                    .on_press(Message::TagFilterToggled(tag)),
+            );
+        }
+
+        container(scrollable(tags))
+            .width(Length::Fixed(160.0))
+            .height(Length::Fill)
+            .padding(10)
+            .into()
+    }
+
+    pub(super) fn tag_editor<'a>(&'a self, input: &'a str) -> Element<'a, Message> {
+        let name = self
+            .selected_image()
+            .map(|img| img.name.as_str())
+            .unwrap_or("");
+
+        container(
+            column![
+                text(format!("Tags for {}", name)).size(self.theme.heading_size()),
+                text_input("dark, minimal, work", input)
+                    .id(TAG_INPUT_ID)
+                    .on_input(Message::TagEditorChanged)
+                    .padding(8),
+                text("Enter to save, Esc to cancel").size(self.theme.caption_size()),
+            ]
+            .spacing(8),
+        )
+        .width(Length::Fixed(420.0))
+        .padding(16)
+        .style(container::rounded_box)
+        .into()
+    }
+}
@@ -1,8 +1,16 @@
 use super::*;
+use crate::config::{ConfigReport, Diagnostic, Severity, Source};
+use crate::framing::Framing;
+use crate::library::Library;
 use crate::message::Message;
+use crate::metadata::Metadata;
+use crate::outputs::OutputConfig;
+use crate::palette::{ColorInfo, Swatch};
+use crate::tags::TagStore;
+use crate::theme::{Accent, HexColor};
 use crate::wallpaper_image::WallpaperImage;
 use iced::keyboard::key::Named;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
 fn create_dummy_image(name: &str) -> WallpaperImage {
     WallpaperImage {
@@ -12,6 +20,19 @@ fn create_dummy_image(name: &str) -> WallpaperImage {
         thumbnail_handle: None,
         is_visible: false,
         is_loading: false,
+        color: None,
+        animated: false,
+    }
+}
+
+fn create_colored_image(name: &str, hue: f32, luminance: f32) -> WallpaperImage {
+    WallpaperImage {
+        color: Some(ColorInfo {
+            hue,
+            saturation: 0.8,
+            luminance,
+        }),
+        ..create_dummy_image(name)
     }
 }
 
@@ -108,9 +129,573 @@ fn test_app_view_update_thumbnail_loaded() {
     view.images.push(create_dummy_image("1"));
 
     let handle = iced_image::Handle::from_rgba(1, 1, vec![0, 0, 0, 0]);
-    let _ = view.update(Message::ThumbnailLoaded(0, handle));
+    let _ = view.update(Message::ThumbnailLoaded(PathBuf::from("1"), handle));
 
     assert!(view.images[0].thumbnail_handle.is_some());
     assert!(view.images[0].is_visible);
     assert!(!view.images[0].is_loading);
 }
+
+#[test]
+fn test_app_view_search_filter() {
+    let mut view = AppView::new();
+    for name in ["forest.jpg", "ocean.png", "Forest-night.jpg"] {
+        let _ = view.update(Message::WallpaperDiscovered(create_dummy_image(name)));
+    }
+
+    let _ = view.update(Message::SearchChanged("forest".to_string()));
+    assert_eq!(view.shown_len(), 2);
+    assert_eq!(view.selected_image().unwrap().name, "forest.jpg");
+
+    // Newly discovered wallpapers respect the active filter
+    let _ = view.update(Message::WallpaperDiscovered(create_dummy_image("sky.jpg")));
+    let _ = view.update(Message::WallpaperDiscovered(create_dummy_image(
+        "forest2.jpg",
+    )));
+    assert_eq!(view.shown_len(), 3);
+
+    let _ = view.update(Message::KeyPressed(key::Key::Named(Named::ArrowRight)));
+    assert_eq!(view.selected_image().unwrap().name, "Forest-night.jpg");
+
+    let _ = view.update(Message::SearchChanged(String::new()));
+    assert!(view.filtered.is_none());
+    assert_eq!(view.shown_len(), 5);
+}
+
+#[test]
+fn test_app_view_tag_editor_and_filter() {
+    let dir = tempfile::tempdir().unwrap();
+    let mut view = AppView::new();
+    view.tags = TagStore::default();
+    view.tags_path = Some(dir.path().join("tags.toml"));
+    for name in ["a.jpg", "b.jpg", "c.jpg"] {
+        view.images.push(create_dummy_image(name));
+    }
+
+    let _ = view.update(Message::KeyPressed(key::Key::Character("t".into())));
+    assert_eq!(view.mode, Mode::TagEditor(String::new()));
+
+    // Navigation keys are ignored while typing
+    let _ = view.update(Message::KeyPressed(key::Key::Character("l".into())));
+    assert_eq!(view.selected_idx, 0);
+
+    let _ = view.update(Message::TagEditorChanged("Dark, minimal".to_string()));
+    let _ = view.update(Message::KeyPressed(key::Key::Named(Named::Enter)));
+    assert_eq!(view.mode, Mode::Browse);
+    assert_eq!(view.tags.counts().get("dark"), Some(&1));
+    assert!(dir.path().join("tags.toml").exists());
+
+    let _ = view.update(Message::TagFilterToggled("dark".to_string()));
+    assert_eq!(view.shown_len(), 1);
+    assert_eq!(view.selected_image().unwrap().name, "a.jpg");
+
+    let _ = view.update(Message::TagFilterToggled("dark".to_string()));
+    assert_eq!(view.shown_len(), 3);
+}
+
+#[test]
+fn test_app_view_toggle_favorite() {
+    let dir = tempfile::tempdir().unwrap();
+    let mut view = AppView::new();
+    view.tags = TagStore::default();
+    view.tags_path = Some(dir.path().join("tags.toml"));
+    view.images.push(create_dummy_image("a.jpg"));
+
+    let _ = view.update(Message::KeyPressed(key::Key::Character("f".into())));
+    assert!(view.tags.tags_for("a.jpg").any(|tag| tag == "favorite"));
+
+    let _ = view.update(Message::KeyPressed(key::Key::Character("f".into())));
+    assert!(view.tags.wallpapers.is_empty());
+}
+
+#[test]
+fn test_app_view_output_picker() {
+    let mut view = AppView::new();
+    view.outputs = vec![OutputConfig::named("DP-1"), OutputConfig::named("HDMI-A-1")];
+    view.images.push(create_dummy_image("a.jpg"));
+
+    let _ = view.update(Message::WallpaperSelected);
+    assert_eq!(view.mode, Mode::OutputPicker(0));
+
+    let _ = view.update(Message::KeyPressed(key::Key::Character("j".into())));
+    let _ = view.update(Message::KeyPressed(key::Key::Named(Named::ArrowDown)));
+    let _ = view.update(Message::KeyPressed(key::Key::Named(Named::ArrowDown)));
+    // Spanning is not offered without output geometry
+    assert_eq!(view.mode, Mode::OutputPicker(2));
+
+    let _ = view.update(Message::KeyPressed(key::Key::Character("k".into())));
+    assert_eq!(view.mode, Mode::OutputPicker(1));
+    // Grid navigation is suspended while picking
+    assert_eq!(view.selected_idx, 0);
+
+    let _ = view.update(Message::KeyPressed(key::Key::Named(Named::Escape)));
+    assert_eq!(view.mode, Mode::Browse);
+
+    view.outputs.truncate(1);
+    let _ = view.update(Message::WallpaperSelected);
+    assert_eq!(view.mode, Mode::Browse);
+}
+
+#[test]
+fn test_app_view_output_picker_offers_span() {
+    let mut view = AppView::new();
+    view.outputs = [("DP-1", 0), ("DP-2", 1920)]
+        .into_iter()
+        .map(|(name, x)| OutputConfig {
+            x: Some(x),
+            y: Some(0),
+            width: Some(1920),
+            height: Some(1080),
+            ..OutputConfig::named(name)
+        })
+        .collect();
+    view.images.push(create_dummy_image("a.jpg"));
+
+    let _ = view.update(Message::WallpaperSelected);
+    for _ in 0..5 {
+        let _ = view.update(Message::KeyPressed(key::Key::Character("j".into())));
+    }
+    assert_eq!(view.mode, Mode::OutputPicker(3));
+}
+
+#[test]
+fn test_app_view_color_filter_and_hue_sort() {
+    let mut view = AppView::new();
+    view.images
+        .push(create_colored_image("blue.jpg", 220.0, 0.2));
+    view.images.push(create_dummy_image("unknown.jpg"));
+    view.images.push(create_colored_image("red.jpg", 5.0, 0.5));
+
+    let _ = view.update(Message::ColorFilterToggled(Swatch::Blue));
+    assert_eq!(view.shown_len(), 1);
+    assert_eq!(view.selected_image().unwrap().name, "blue.jpg");
+
+    let _ = view.update(Message::ColorFilterToggled(Swatch::Dark));
+    assert_eq!(view.color_filter, Some(Swatch::Dark));
+    assert_eq!(view.shown_len(), 1);
+
+    // Hiding the swatch row drops the color filter
+    let _ = view.update(Message::KeyPressed(key::Key::Character("c".into())));
+    let _ = view.update(Message::KeyPressed(key::Key::Character("c".into())));
+    assert_eq!(view.color_filter, None);
+    assert_eq!(view.shown_len(), 3);
+
+    let _ = view.update(Message::KeyPressed(key::Key::Character("s".into())));
+    let order: Vec<_> = (0..view.shown_len())
+        .map(|pos| view.images[view.image_idx(pos).unwrap()].name.as_str())
+        .collect();
+    assert_eq!(order, vec!["red.jpg", "blue.jpg", "unknown.jpg"]);
+
+    // Wallpapers discovered while sorting are inserted in hue order
+    let _ = view.update(Message::WallpaperDiscovered(create_colored_image(
+        "green.jpg",
+        120.0,
+        0.5,
+    )));
+    assert_eq!(
+        view.image_idx(1).map(|idx| view.images[idx].name.as_str()),
+        Some("green.jpg")
+    );
+
+    let _ = view.update(Message::SortByHueToggled);
+    assert!(view.filtered.is_none());
+}
+
+#[test]
+fn test_app_view_crop_editor() {
+    let dir = tempfile::tempdir().unwrap();
+    let mut view = AppView::new();
+    view.images.push(create_dummy_image("dune.jpg"));
+    view.library = Library::default();
+    view.library_path = Some(dir.path().join("library.toml"));
+    view.mode = Mode::Crop(framing::CropEditor::new(Framing::default(), (2000, 1000)));
+
+    // The decoded image is shown once it arrives, unless it is another's
+    let handle = iced::widget::image::Handle::from_rgba(1, 1, vec![0; 4]);
+    let _ = view.update(Message::CropImageLoaded(
+        PathBuf::from("other.jpg"),
+        handle.clone(),
+    ));
+    let Mode::Crop(ref editor) = view.mode else {
+        panic!("expected the crop editor");
+    };
+    assert!(editor.image.is_none());
+    let _ = view.update(Message::CropImageLoaded(
+        PathBuf::from("dune.jpg"),
+        handle.clone(),
+    ));
+    let Mode::Crop(ref editor) = view.mode else {
+        panic!("expected the crop editor");
+    };
+    assert_eq!(editor.image, Some(handle));
+
+    // Dragging selects the crop, clicking sets the focus point
+    let _ = view.update(Message::CropCursorMoved(iced::Point::new(96.0, 48.0)));
+    let _ = view.update(Message::CropPressed);
+    let _ = view.update(Message::CropCursorMoved(iced::Point::new(480.0, 240.0)));
+    let _ = view.update(Message::CropReleased);
+    let _ = view.update(Message::CropCursorMoved(iced::Point::new(240.0, 120.0)));
+    let _ = view.update(Message::CropPressed);
+    let _ = view.update(Message::CropCursorMoved(iced::Point::new(241.0, 121.0)));
+    let _ = view.update(Message::CropReleased);
+
+    let Mode::Crop(ref editor) = view.mode else {
+        panic!("expected the crop editor");
+    };
+    assert_eq!(editor.size, (960.0, 480.0));
+    let crop = editor.framing.crop.unwrap();
+    assert!((crop.x - 0.1).abs() < 1e-6 && (crop.width - 0.4).abs() < 1e-6);
+    assert!((crop.y - 0.1).abs() < 1e-6 && (crop.height - 0.4).abs() < 1e-6);
+    let focus = editor.framing.focus.unwrap();
+    assert!((focus.x - 241.0 / 960.0).abs() < 1e-6);
+
+    // Enter stores the framing in the library file
+    let _ = view.update(Message::KeyPressed(key::Key::Named(Named::Enter)));
+    assert_eq!(view.mode, Mode::Browse);
+    let saved = Library::load(view.library_path.as_deref().unwrap()).unwrap();
+    assert_eq!(saved.framing("dune.jpg").crop, Some(crop));
+
+    // Escape leaves without saving, r resets
+    view.mode = Mode::Crop(framing::CropEditor::new(
+        view.library.framing("dune.jpg"),
+        (2000, 1000),
+    ));
+    let _ = view.update(Message::KeyPressed(key::Key::Character("r".into())));
+    let _ = view.update(Message::KeyPressed(key::Key::Named(Named::Escape)));
+    assert_eq!(view.mode, Mode::Browse);
+    assert_eq!(view.library.framing("dune.jpg").crop, Some(crop));
+}
+
+#[test]
+fn test_app_view_preview_playback() {
+    use std::time::{Duration, Instant};
+
+    let mut view = AppView::new();
+    view.images.push(create_dummy_image("still.jpg"));
+    view.images.push(WallpaperImage {
+        animated: true,
+        ..create_dummy_image("loop.gif")
+    });
+
+    let _ = view.update(Message::KeyPressed(key::Key::Named(Named::Space)));
+    let Mode::Preview(ref preview) = view.mode else {
+        panic!("expected the preview");
+    };
+    assert_eq!(preview.idx, 0);
+    assert!(!preview.is_playing());
+    let _ = view.update(Message::KeyPressed(key::Key::Named(Named::Escape)));
+    assert_eq!(view.mode, Mode::Browse);
+
+    view.selected_idx = 1;
+    let _ = view.update(Message::KeyPressed(key::Key::Named(Named::Space)));
+    let frame = |shade: u8| iced_image::Handle::from_rgba(1, 1, vec![shade, shade, shade, 255]);
+    let frames = vec![
+        (frame(0), Duration::from_millis(100)),
+        (frame(255), Duration::from_millis(300)),
+    ];
+    // Frames decoded for a preview that is no longer open are dropped
+    let _ = view.update(Message::PreviewLoaded(
+        PathBuf::from("still.jpg"),
+        frames.clone(),
+    ));
+    let Mode::Preview(ref preview) = view.mode else {
+        panic!("expected the preview");
+    };
+    assert!(preview.frames.is_empty());
+
+    let _ = view.update(Message::PreviewLoaded(PathBuf::from("loop.gif"), frames));
+    let start = Instant::now();
+    for (elapsed_ms, expected) in [(0, 0), (50, 0), (100, 1), (350, 1), (400, 0)] {
+        let _ = view.update(Message::PreviewFrame(
+            start + Duration::from_millis(elapsed_ms),
+        ));
+        let Mode::Preview(ref preview) = view.mode else {
+            panic!("expected the preview");
+        };
+        assert_eq!(preview.current, expected, "at {}ms", elapsed_ms);
+    }
+
+    let Mode::Preview(ref preview) = view.mode else {
+        panic!("expected the preview");
+    };
+    assert_eq!(preview.info().unwrap().to_string(), "2 frames, 0.4s");
+}
+
+#[test]
+fn test_config_banner_dismissed() {
+    let mut view = AppView::new();
+    view.config_report = Some(ConfigReport {
+        path: PathBuf::from("wallity.toml"),
+        diagnostics: vec![Diagnostic {
+            severity: Severity::Warning,
+            source: Source::File,
+            location: Some((1, 1)),
+            message: "unknown key `wallpaper_pth`".to_string(),
+        }],
+    });
+    assert!(view.config_banner().is_some());
+
+    let _ = view.update(Message::ConfigBannerDismissed);
+    assert!(view.config_report.is_none());
+    assert!(view.config_banner().is_none());
+}
+
+#[test]
+fn test_config_reloaded() {
+    let mut view = AppView::new();
+    view.images.push(create_dummy_image("1"));
+    view.images.push(create_dummy_image("2"));
+    view.selected_idx = 1;
+
+    // Unchanged directories keep the wallpapers
+    let config = Arc::new(AppConfig {
+        prefer_dark: Some(true),
+        ..(*view.config).clone()
+    });
+    let report = ConfigReport {
+        path: PathBuf::from("wallity.toml"),
+        diagnostics: vec![Diagnostic {
+            severity: Severity::Error,
+            source: Source::File,
+            location: Some((2, 1)),
+            message: "invalid type: string \"yes\", expected a boolean".to_string(),
+        }],
+    };
+    let _ = view.update(Message::ConfigReloaded(config, report));
+    assert_eq!(view.images.len(), 2);
+    assert_eq!(view.selected_idx, 1);
+    assert_eq!(view.config.prefer_dark, Some(true));
+    assert!(view.config_banner().is_some());
+
+    // A fixed file clears the banner, a new directory the wallpapers
+    let config = Arc::new(AppConfig {
+        wallpaper_path: Some(PathBuf::from("/elsewhere")),
+        ..(*view.config).clone()
+    });
+    let _ = view.update(Message::ConfigReloaded(config, ConfigReport::default()));
+    assert!(view.images.is_empty());
+    assert_eq!(view.selected_idx, 0);
+    assert_eq!(view.mode, Mode::Browse);
+    assert!(view.config_report.is_none());
+}
+
+#[test]
+fn test_theme_reloaded() {
+    let mut view = AppView::new();
+    assert_eq!(view.theme.accent, crate::theme::DEFAULT_ACCENT.color());
+
+    let mut config = (*view.config).clone();
+    config.theme.accent = Some(Accent::Wallpaper);
+    config.theme.border_width = Some(5.0);
+    let _ = view.update(Message::ConfigReloaded(
+        Arc::new(config),
+        ConfigReport::default(),
+    ));
+    // The default accent stands in until the wallpaper's colors are read
+    assert_eq!(view.theme.accent, crate::theme::DEFAULT_ACCENT.color());
+    assert_eq!(view.theme.border_width, 5.0);
+
+    let _ = view.update(Message::AccentLoaded([0x20, 0x80, 0xd0]));
+    assert_eq!(view.theme.accent, Color::from_rgb8(0x20, 0x80, 0xd0));
+
+    // Going back to a fixed accent forgets the wallpaper's
+    let mut config = (*view.config).clone();
+    config.theme.accent = Some(Accent::Color(HexColor([0, 0xff, 0])));
+    let _ = view.update(Message::ConfigReloaded(
+        Arc::new(config),
+        ConfigReport::default(),
+    ));
+    assert_eq!(view.theme.accent, Color::from_rgb8(0, 0xff, 0));
+    assert!(view.wallpaper_accent.is_none());
+}
+
+#[test]
+fn test_details_follow_selection() {
+    let mut view = AppView::new();
+    view.images.push(create_dummy_image("1"));
+    view.images.push(create_dummy_image("2"));
+
+    let _ = view.update(Message::KeyPressed(key::Key::Character("i".into())));
+    assert_eq!(view.details, Some((PathBuf::from("1"), None)));
+
+    let _ = view.update(Message::KeyPressed(key::Key::Named(Named::ArrowRight)));
+    assert_eq!(view.details, Some((PathBuf::from("2"), None)));
+
+    // Details arriving after the selection moved on are dropped
+    let stale = Metadata::read(Path::new("1"), Path::new("1"));
+    let _ = view.update(Message::DetailsLoaded(Box::new(stale)));
+    assert_eq!(view.details, Some((PathBuf::from("2"), None)));
+
+    let current = Metadata::read(Path::new("2"), Path::new("2"));
+    let _ = view.update(Message::DetailsLoaded(Box::new(current.clone())));
+    assert_eq!(view.details, Some((PathBuf::from("2"), Some(current))));
+
+    // Hidden, the panel reads nothing
+    let _ = view.update(Message::KeyPressed(key::Key::Character("i".into())));
+    let _ = view.update(Message::ImageHovered(Some(0)));
+    assert_eq!(view.details.as_ref().unwrap().0, PathBuf::from("2"));
+}
+
+#[test]
+fn test_rename_and_move_wallpapers() {
+    let dir = tempfile::tempdir().unwrap();
+    let archive = dir.path().join("archive");
+    std::fs::create_dir(&archive).unwrap();
+    let mut view = AppView::new();
+    view.tags_path = Some(dir.path().join("tags.toml"));
+    for name in ["a.png", "b.png", "c.png"] {
+        let img_path = dir.path().join(name);
+        std::fs::write(&img_path, "").unwrap();
+        view.images.push(WallpaperImage {
+            img_path,
+            thumbnail_path: dir.path().join(format!("{}.jpeg", name)),
+            ..create_dummy_image(name)
+        });
+    }
+    view.tags
+        .set_tags("a.png", BTreeSet::from(["dark".to_string()]));
+    view.tags
+        .set_tags("b.png", BTreeSet::from(["forest".to_string()]));
+
+    // Renaming keeps the tags and the extension
+    let _ = view.update(Message::KeyPressed(key::Key::Character("r".into())));
+    assert!(matches!(view.mode, Mode::Rename(ref input) if input.value == "a.png"));
+    let _ = view.update(Message::FileInputChanged("night".to_string()));
+    let _ = view.update(Message::KeyPressed(key::Key::Named(Named::Enter)));
+    assert_eq!(view.mode, Mode::Browse);
+    assert_eq!(view.images[0].name, "night.png");
+    assert_eq!(view.images[0].img_path, dir.path().join("night.png"));
+    assert!(dir.path().join("night.png").exists());
+    assert_eq!(view.tags.tags_for("night.png").count(), 1);
+    assert_eq!(view.tags.tags_for("a.png").count(), 0);
+
+    // A taken name keeps the dialog open with the error
+    let _ = view.update(Message::KeyPressed(key::Key::Named(Named::F2)));
+    let _ = view.update(Message::FileInputChanged("b.png".to_string()));
+    let _ = view.update(Message::KeyPressed(key::Key::Named(Named::Enter)));
+    assert!(matches!(view.mode, Mode::Rename(ref input) if input.error.is_some()));
+    let _ = view.update(Message::KeyPressed(key::Key::Named(Named::Escape)));
+
+    // Moving out of the directory drops it from the grid, in place
+    view.filtered = Some(vec![2, 1, 0]);
+    let _ = view.update(Message::FileMenuOpened(1));
+    assert_eq!(view.mode, Mode::FileMenu(0));
+    let _ = view.update(Message::FileAction(FileAction::Move));
+    let _ = view.update(Message::FileInputChanged(archive.display().to_string()));
+    let _ = view.update(Message::KeyPressed(key::Key::Named(Named::Enter)));
+    assert_eq!(view.mode, Mode::Browse);
+    assert!(archive.join("b.png").exists());
+    assert_eq!(view.images.len(), 2);
+    assert_eq!(view.filtered, Some(vec![1, 0]));
+    assert_eq!(view.selected_image().unwrap().name, "night.png");
+    // and keeps its tags
+    assert_eq!(view.tags.tags_for("b.png").count(), 1);
+
+    // Thumbnails still loading reach the right wallpaper despite the shift
+    let handle = iced_image::Handle::from_rgba(1, 1, vec![0; 4]);
+    let _ = view.update(Message::ThumbnailLoaded(
+        dir.path().join("b.png"),
+        handle.clone(),
+    ));
+    assert!(view.images.iter().all(|img| img.thumbnail_handle.is_none()));
+    let _ = view.update(Message::ThumbnailLoaded(dir.path().join("c.png"), handle));
+    assert_eq!(view.images[1].name, "c.png");
+    assert!(view.images[1].thumbnail_handle.is_some());
+    assert!(view.images[0].thumbnail_handle.is_none());
+}
+
+#[test]
+fn test_mark_wallpapers() {
+    let mut view = AppView::new();
+    for name in ["1", "2", "3", "4", "5"] {
+        view.images.push(create_dummy_image(name));
+    }
+    let paths = |names: &[&str]| names.iter().map(PathBuf::from).collect::<BTreeSet<_>>();
+
+    // Ctrl+click toggles, Shift+click marks up to the last toggled one
+    let _ = view.update(Message::ModifiersChanged(keyboard::Modifiers::CTRL));
+    let _ = view.update(Message::ImagePressed(1));
+    let _ = view.update(Message::ImagePressed(3));
+    let _ = view.update(Message::ImagePressed(3));
+    assert_eq!(view.marked, paths(&["2"]));
+    let _ = view.update(Message::ModifiersChanged(keyboard::Modifiers::SHIFT));
+    let _ = view.update(Message::ImagePressed(2));
+    assert_eq!(view.marked, paths(&["2", "3", "4"]));
+    let _ = view.update(Message::ModifiersChanged(keyboard::Modifiers::default()));
+
+    // Marks survive filtering, and bulk actions still reach hidden ones
+    view.search = "4".to_string();
+    let _ = view.refilter();
+    assert_eq!(view.shown_len(), 1);
+    assert!(view.is_marked(0));
+    assert_eq!(view.targets(), vec![1, 2, 3]);
+    view.search.clear();
+    let _ = view.refilter();
+
+    // Esc clears the marks before it quits
+    let _ = view.update(Message::KeyPressed(key::Key::Named(Named::Escape)));
+    assert!(view.marked.is_empty());
+    assert_eq!(view.targets(), vec![0]);
+}
+
+#[test]
+fn test_visual_mode() {
+    let mut view = AppView::new();
+    for name in ["1", "2", "3", "4", "5"] {
+        view.images.push(create_dummy_image(name));
+    }
+    let paths = |names: &[&str]| names.iter().map(PathBuf::from).collect::<BTreeSet<_>>();
+    view.selected_idx = 2;
+
+    let _ = view.update(Message::KeyPressed(key::Key::Character("v".into())));
+    assert_eq!(view.marked, paths(&["3"]));
+    let _ = view.update(Message::KeyPressed(key::Key::Character("l".into())));
+    let _ = view.update(Message::KeyPressed(key::Key::Character("l".into())));
+    assert_eq!(view.marked, paths(&["3", "4", "5"]));
+    // Moving back past the anchor shrinks the range
+    for _ in 0..3 {
+        let _ = view.update(Message::KeyPressed(key::Key::Character("h".into())));
+    }
+    assert_eq!(view.marked, paths(&["2", "3"]));
+
+    // Leaving visual mode keeps the marks
+    let _ = view.update(Message::KeyPressed(key::Key::Character("v".into())));
+    assert!(view.visual.is_none());
+    let _ = view.update(Message::KeyPressed(key::Key::Character("l".into())));
+    assert_eq!(view.marked, paths(&["2", "3"]));
+}
+
+#[test]
+fn test_bulk_move() {
+    let dir = tempfile::tempdir().unwrap();
+    let archive = dir.path().join("archive");
+    std::fs::create_dir(&archive).unwrap();
+    let mut view = AppView::new();
+    for name in ["a.png", "b.png", "c.png", "d.png"] {
+        let img_path = dir.path().join(name);
+        std::fs::write(&img_path, "").unwrap();
+        view.images.push(WallpaperImage {
+            img_path,
+            thumbnail_path: dir.path().join(format!("{}.jpeg", name)),
+            ..create_dummy_image(name)
+        });
+    }
+    std::fs::write(archive.join("d.png"), "").unwrap();
+    view.marked = [0, 2, 3]
+        .iter()
+        .map(|&idx| view.images[idx].img_path.clone())
+        .collect();
+    view.selected_idx = 1;
+
+    let _ = view.update(Message::KeyPressed(key::Key::Character("m".into())));
+    assert!(matches!(view.mode, Mode::Move(_)));
+    let _ = view.update(Message::FileInputChanged(archive.display().to_string()));
+    let _ = view.update(Message::KeyPressed(key::Key::Named(Named::Enter)));
+
+    // The one already in the archive stays, marked, with the dialog open
+    assert!(archive.join("a.png").exists() && archive.join("c.png").exists());
+    assert!(matches!(view.mode, Mode::Move(ref input) if input.error.is_some()));
+    let names: Vec<&str> = view.images.iter().map(|img| img.name.as_str()).collect();
+    assert_eq!(names, vec!["b.png", "d.png"]);
+    assert_eq!(view.marked, BTreeSet::from([dir.path().join("d.png")]));
+}
@@ -1,6 +1,10 @@
-use anyhow::{Context, Result};
+use anyhow::{Context, Result, bail};
 use resolve_path::PathResolveExt;
-use std::{fs, path::PathBuf};
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    time::Duration,
+};
 
 pub fn get_absolute_path(path: &str) -> Result<PathBuf> {
     path.try_resolve()
@@ -10,11 +14,11 @@ pub fn get_absolute_path(path: &str) -> Result<PathBuf> {
 
 pub fn resolve_file_path(path: &str) -> Result<PathBuf> {
     let resolved = get_absolute_path(path)?;
-    if let Some(parent) = resolved.parent() {
-        if !parent.exists() {
-            fs::create_dir_all(parent)
-                .with_context(|| format!("Failed to create parent directory for: {}", path))?;
-        }
+    if let Some(parent) = resolved.parent()
+        && !parent.exists()
+    {
+        fs::create_dir_all(parent)
+            .with_context(|| format!("Failed to create parent directory for: {}", path))?;
     }
 
     Ok(resolved)
@@ -30,6 +34,68 @@ pub fn resolve_dir_path(path: &str) -> Result<PathBuf> {
     Ok(resolved)
 }
 
+/// Whether `file` exists and was modified no earlier than `than`, i.e. a
+/// cached file derived from `than` is still current.
+pub fn is_newer(file: &Path, than: &Path) -> bool {
+    let modified = |path: &Path| fs::metadata(path).and_then(|m| m.modified()).ok();
+    match (modified(file), modified(than)) {
+        (Some(file), Some(than)) => file >= than,
+        _ => false,
+    }
+}
+
+/// Longest duration `parse_duration` accepts, so adding it to the current
+/// time can't overflow.
+const MAX_DURATION: Duration = Duration::from_secs(365 * 24 * 60 * 60);
+
+/// Parses durations such as `90`, `45s`, `30m`, `2h` or `1h30m`; bare numbers are seconds.
+pub fn parse_duration(input: &str) -> Result<Duration> {
+    let input = input.trim();
+    let duration = parse_seconds(input).map(Duration::from_secs)?;
+    if duration.is_zero() {
+        bail!("Invalid duration '{}': must be longer than zero", input);
+    }
+    if duration > MAX_DURATION {
+        bail!("Invalid duration '{}': must be at most 365 days", input);
+    }
+    Ok(duration)
+}
+
+fn parse_seconds(input: &str) -> Result<u64> {
+    if let Ok(secs) = input.parse::<u64>() {
+        return Ok(secs);
+    }
+
+    let mut total = 0u64;
+    let mut digits = String::new();
+    for c in input.chars() {
+        if c.is_ascii_digit() {
+            digits.push(c);
+            continue;
+        }
+        let unit = match c {
+            's' => 1,
+            'm' => 60,
+            'h' => 60 * 60,
+            'd' => 24 * 60 * 60,
+            _ => bail!("Invalid duration '{}': unknown unit '{}'", input, c),
+        };
+        let value: u64 = digits
+            .parse()
+            .with_context(|| format!("Invalid duration '{}'", input))?;
+        total = value
+            .checked_mul(unit)
+            .and_then(|secs| total.checked_add(secs))
+            .with_context(|| format!("Invalid duration '{}': too long", input))?;
+        digits.clear();
+    }
+    if !digits.is_empty() || input.is_empty() {
+        bail!("Invalid duration '{}'", input);
+    }
+
+    Ok(total)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -79,4 +145,26 @@ mod tests {
         assert!(new_dir.exists());
         assert!(new_dir.is_dir());
     }
+
+    #[test]
+    fn test_parse_duration() {
+        assert_eq!(parse_duration("90").unwrap(), Duration::from_secs(90));
+        assert_eq!(parse_duration("45s").unwrap(), Duration::from_secs(45));
+        assert_eq!(parse_duration("30m").unwrap(), Duration::from_secs(1800));
+        assert_eq!(parse_duration("1h30m").unwrap(), Duration::from_secs(5400));
+        assert_eq!(parse_duration("1d").unwrap(), Duration::from_secs(86400));
+
+        assert!(parse_duration("").is_err());
+        assert!(parse_duration("10x").is_err());
+        assert!(parse_duration("m").is_err());
+        assert!(parse_duration("0m").is_err());
+        assert!(parse_duration("0").is_err());
+        assert!(parse_duration("18446744073709551615").is_err());
+        assert!(parse_duration("366d").is_err());
+        assert_eq!(parse_duration("365d").unwrap(), MAX_DURATION);
+        assert!(parse_duration("99999999999999999999d").is_err());
+        assert!(parse_duration("9999999999999999d").is_err());
+        assert!(parse_duration("18446744073709551615s1s").is_err());
+        assert!(parse_duration("5m3").is_err());
+    }
 }
@@ -1,35 +1,48 @@
 use crate::{
     config::CONFIG,
-    thumbnail::{gen_thumbnail, list_thumbnails},
+    format::is_supported,
+    library::Library,
+    thumbnail::{
+        clear_outdated, gen_thumbnail, info_path, list_thumbnails, thumbnail_info, thumbnail_path,
+    },
     wallpaper_image::WallpaperImage,
 };
 
 use rayon::prelude::*;
 use std::{collections::HashSet, path::Path, path::PathBuf, sync::mpsc};
 
-const SUPPORTED_EXTENSIONS: &[&str] = &["png", "jpg", "jpeg", "webp"];
-
-fn is_supported_extension(path: &Path) -> bool {
-    path.extension()
-        .and_then(|e| e.to_str())
-        .map(|ext| {
-            SUPPORTED_EXTENSIONS
-                .iter()
-                .any(|&supported| ext.eq_ignore_ascii_case(supported))
-        })
-        .unwrap_or(false)
+/// Lists supported wallpaper files directly inside `dir`, sorted by path.
+pub fn discover_wallpapers(dir: &Path) -> Vec<PathBuf> {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return Vec::new();
+    };
+
+    let mut paths: Vec<PathBuf> = entries
+        .flatten()
+        .filter(|entry| entry.file_type().map(|f| f.is_file()).unwrap_or(false))
+        .map(|entry| entry.path())
+        .filter(|path| is_supported(path))
+        .collect();
+    paths.sort();
+    paths
 }
 
 pub fn load_wallpapers(tx: mpsc::SyncSender<WallpaperImage>) -> Result<(), String> {
+    let config = CONFIG.load_full();
     std::thread::spawn(move || {
-        let thumbnails: HashSet<String> = list_thumbnails();
-        let Some(ref thumbnail_path_base) = CONFIG.cache_path else {
+        let Some(ref thumbnail_path_base) = config.cache_path else {
             eprintln!("Cache path not configured");
             return;
         };
-        let thumbnail_path_str = thumbnail_path_base.to_string_lossy();
+        clear_outdated(thumbnail_path_base);
+        let thumbnails: HashSet<String> = list_thumbnails();
+        let library = config
+            .library_path
+            .as_deref()
+            .and_then(|path| Library::load(path).ok())
+            .unwrap_or_default();
 
-        let Some(ref absolute_path) = CONFIG.wallpaper_path else {
+        let Some(ref absolute_path) = config.wallpaper_path else {
             eprintln!("Wallpaper Path not configured");
             return;
         };
@@ -40,20 +53,20 @@ pub fn load_wallpapers(tx: mpsc::SyncSender<WallpaperImage>) -> Result<(), Strin
                 .filter(|entry| entry.file_type().map(|f| f.is_file()).unwrap_or(false))
                 .collect();
 
-            // Single scan: collect valid stems and split by thumbnail presence
-            let mut valid_stems: HashSet<String> = HashSet::new();
+            // Single scan: collect valid names and split by thumbnail presence
+            let mut valid_names: HashSet<String> = HashSet::new();
             let mut with_thumbnails: Vec<_> = Vec::new();
             let mut without_thumbnails: Vec<_> = Vec::new();
 
             for entry in entries {
                 let path = entry.path();
-                if !is_supported_extension(&path) {
+                if !is_supported(&path) {
                     continue;
                 }
 
-                if let Some(stem) = path.file_stem().and_then(|s| s.to_str()) {
-                    valid_stems.insert(stem.to_string());
-                    if thumbnails.contains(stem) {
+                if let Some(name) = path.file_name().and_then(|s| s.to_str()) {
+                    valid_names.insert(name.to_string());
+                    if thumbnails.contains(name) {
                         with_thumbnails.push(entry);
                     } else {
                         without_thumbnails.push(entry);
@@ -67,12 +80,13 @@ pub fn load_wallpapers(tx: mpsc::SyncSender<WallpaperImage>) -> Result<(), Strin
                 .for_each_with(tx.clone(), |tx, entry| {
                     let path = entry.path();
                     let file_name = path.file_name().unwrap().to_string_lossy().to_string();
-                    let file_stem = path.file_stem().unwrap().to_string_lossy().to_string();
-                    let thumbnail_path =
-                        PathBuf::from(format!("{}/{}.jpeg", &thumbnail_path_str, &file_stem));
+                    let thumbnail_path = thumbnail_path(thumbnail_path_base, &path);
+                    let info = thumbnail_info(&path, &thumbnail_path);
                     let image = WallpaperImage {
                         name: file_name,
+                        animated: info.is_some_and(|info| info.animation.is_some()),
                         img_path: path,
+                        color: info.map(|info| info.color),
                         thumbnail_path,
                         thumbnail_handle: None,
                         is_visible: false,
@@ -87,20 +101,25 @@ pub fn load_wallpapers(tx: mpsc::SyncSender<WallpaperImage>) -> Result<(), Strin
                 .for_each_with(tx.clone(), |tx, entry| {
                     let path = entry.path();
                     let file_name = path.file_name().unwrap().to_string_lossy().to_string();
-                    let file_stem = path.file_stem().unwrap().to_string_lossy().to_string();
-                    let thumbnail_path =
-                        PathBuf::from(format!("{}/{}.jpeg", &thumbnail_path_str, &file_stem));
+                    let thumbnail_path = thumbnail_path(thumbnail_path_base, &path);
 
-                    let image = WallpaperImage {
+                    let mut image = WallpaperImage {
                         name: file_name,
+                        animated: false,
                         img_path: path,
                         thumbnail_handle: None,
                         thumbnail_path,
                         is_visible: false,
                         is_loading: false,
+                        color: None,
                     };
 
-                    if gen_thumbnail(&image.img_path, &image.thumbnail_path).is_ok() {
+                    let framing = library.framing(&image.name);
+                    if let Ok(info) =
+                        gen_thumbnail(&image.img_path, &image.thumbnail_path, &framing)
+                    {
+                        image.color = Some(info.color);
+                        image.animated = info.animation.is_some();
                         let _ = tx.send(image);
                     } else {
                         eprintln!("Failed to generate thumbnail for: {}", image.name);
@@ -108,16 +127,18 @@ pub fn load_wallpapers(tx: mpsc::SyncSender<WallpaperImage>) -> Result<(), Strin
                 });
 
             // Clean up orphaned thumbnails after main processing
-            for thumbnail_stem in thumbnails {
-                if !valid_stems.contains(&thumbnail_stem) {
+            for thumbnail_name in thumbnails {
+                if !valid_names.contains(&thumbnail_name) {
                     let thumbnail_file =
-                        format!("{}/{}.jpeg", &thumbnail_path_str, &thumbnail_stem);
+                        thumbnail_path(thumbnail_path_base, Path::new(&thumbnail_name));
                     if let Err(e) = std::fs::remove_file(&thumbnail_file) {
                         eprintln!(
                             "Failed to remove orphaned thumbnail {}: {}",
-                            thumbnail_file, e
+                            thumbnail_file.display(),
+                            e
                         );
                     }
+                    let _ = std::fs::remove_file(info_path(&thumbnail_file));
                 }
             }
         }
@@ -129,20 +150,47 @@ pub fn load_wallpapers(tx: mpsc::SyncSender<WallpaperImage>) -> Result<(), Strin
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::format::Format;
     use std::path::Path;
 
     #[test]
     fn test_is_supported_extension() {
-        assert!(is_supported_extension(Path::new("test.png")));
-        assert!(is_supported_extension(Path::new("test.jpg")));
-        assert!(is_supported_extension(Path::new("test.jpeg")));
-        assert!(is_supported_extension(Path::new("test.webp")));
+        assert!(is_supported(Path::new("test.png")));
+        assert!(is_supported(Path::new("test.jpg")));
+        assert!(is_supported(Path::new("test.jpeg")));
+        assert!(is_supported(Path::new("test.webp")));
+        assert!(is_supported(Path::new("test.gif")));
+        assert!(is_supported(Path::new("test.bmp")));
+        assert!(is_supported(Path::new("test.tif")));
+        assert!(is_supported(Path::new("test.qoi")));
+
+        assert!(is_supported(Path::new("test.PNG")));
+        assert!(is_supported(Path::new("test.JPG")));
+
+        assert_eq!(
+            is_supported(Path::new("test.avif")),
+            Format::Avif.is_enabled()
+        );
+        assert_eq!(
+            is_supported(Path::new("test.jxl")),
+            Format::Jxl.is_enabled()
+        );
+        assert!(!is_supported(Path::new("test.txt")));
+        assert!(!is_supported(Path::new("test")));
+    }
 
-        assert!(is_supported_extension(Path::new("test.PNG")));
-        assert!(is_supported_extension(Path::new("test.JPG")));
+    #[test]
+    fn test_discover_wallpapers() {
+        let dir = tempfile::tempdir().unwrap();
+        for name in ["b.png", "a.JPG", "notes.txt"] {
+            std::fs::File::create(dir.path().join(name)).unwrap();
+        }
+        std::fs::create_dir(dir.path().join("nested.png")).unwrap();
 
-        assert!(!is_supported_extension(Path::new("test.txt")));
-        assert!(!is_supported_extension(Path::new("test.gif")));
-        assert!(!is_supported_extension(Path::new("test")));
+        let found = discover_wallpapers(dir.path());
+        assert_eq!(
+            found,
+            vec![dir.path().join("a.JPG"), dir.path().join("b.png")]
+        );
     }
 }
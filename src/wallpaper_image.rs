@@ -2,6 +2,8 @@ use std::path::PathBuf;
 
 use iced::widget::image;
 
+use crate::palette::ColorInfo;
+
 #[derive(Debug, Clone)]
 pub struct WallpaperImage {
     pub name: String,
@@ -10,4 +12,8 @@ pub struct WallpaperImage {
     pub thumbnail_handle: Option<image::Handle>,
     pub is_visible: bool,
     pub is_loading: bool,
+    /// Dominant color, computed along with the thumbnail.
+    pub color: Option<ColorInfo>,
+    /// Whether the image has several frames, e.g. an animated GIF.
+    pub animated: bool,
 }